@@ -34,13 +34,18 @@ fn main() -> Result<(), Error> {
         // When is the next beat due?
         let sample_time = output_start_time + beat as u64 * samples_per_beat;
         // Alternate between cowbell and bass every 2 bars
-        let sample = if (beat / (2 * BEATS_PER_BAR)) % 2 == 0 {
+        let sample = if (beat / (2 * BEATS_PER_BAR)).is_multiple_of(2) {
             &metronome
         } else {
             &bass
         };
         // Raise pitch by octave on first beat of each bar
-        let speed = speed_from_note(60 + if beat % BEATS_PER_BAR == 0 { 12 } else { 0 });
+        let transpose = if beat.is_multiple_of(BEATS_PER_BAR) {
+            12
+        } else {
+            0
+        };
+        let speed = speed_from_note(60 + transpose);
         // Play sample at current beat
         let playback_id = player.play_file_source(
             sample.clone(FilePlaybackOptions::default().speed(speed), sample_rate)?,
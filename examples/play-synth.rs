@@ -106,6 +106,51 @@ fn main() -> Result<(), Error> {
                             position.as_secs_f32()
                         );
                     }
+                    PlaybackStatusEvent::Paused {
+                        id,
+                        path,
+                        context: _,
+                    } => {
+                        println!("Playback of synth #{} '{}' was paused", id, path);
+                    }
+                    PlaybackStatusEvent::Resumed {
+                        id,
+                        path,
+                        context: _,
+                    } => {
+                        println!("Playback of synth #{} '{}' was resumed", id, path);
+                    }
+                    PlaybackStatusEvent::MarkerReached {
+                        id,
+                        path,
+                        context: _,
+                        marker_id,
+                        label,
+                    } => {
+                        println!(
+                            "Playback of synth #{} '{}' reached marker #{} '{}'",
+                            id, path, marker_id, label
+                        );
+                    }
+                    PlaybackStatusEvent::MetadataChanged {
+                        id,
+                        path,
+                        context: _,
+                        metadata,
+                    } => {
+                        println!(
+                            "Tags of synth #{} '{}' changed: {:?}",
+                            id, path, metadata.title
+                        );
+                    }
+                    PlaybackStatusEvent::PlaylistTrackChanged {
+                        id,
+                        path,
+                        context: _,
+                        index,
+                    } => {
+                        println!("Playlist #{} started track #{} '{}'", id, index, path);
+                    }
                     PlaybackStatusEvent::Stopped {
                         id,
                         path,
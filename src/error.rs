@@ -11,6 +11,7 @@ pub enum Error {
     MediaFileSeekError,
     AudioDecodingError(Box<dyn error::Error + Send + Sync>),
     OutputDeviceError(Box<dyn error::Error + Send + Sync>),
+    InputDeviceError(Box<dyn error::Error + Send + Sync>),
     ResamplingError(Box<dyn error::Error + Send + Sync>),
     IoError(io::Error),
     ParameterError(String),
@@ -27,6 +28,7 @@ impl fmt::Display for Error {
             Self::MediaFileSeekError => write!(f, "Audio file failed to seek"),
             Self::AudioDecodingError(err)
             | Self::OutputDeviceError(err)
+            | Self::InputDeviceError(err)
             | Self::ResamplingError(err) => err.fmt(f),
             Self::IoError(err) => err.fmt(f),
             Self::ParameterError(str) => write!(f, "Invalid parameter: {str}"),
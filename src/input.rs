@@ -0,0 +1,2 @@
+#[cfg(feature = "cpal")]
+pub mod cpal;
@@ -0,0 +1,600 @@
+use std::{sync::Mutex, time::Duration};
+
+#[cfg(feature = "assert_no_alloc")]
+use assert_no_alloc::*;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use crossbeam_channel::{bounded, Receiver, Sender};
+
+use crate::{
+    error::Error,
+    output::{
+        cpal::{
+            CpalDeviceConfigRange, CpalDeviceId, CpalDeviceInfo, CpalOutput,
+            PREFERRED_SAMPLE_FORMATS,
+        },
+        AudioHostId,
+    },
+    player::PlaybackStatusEvent,
+    source::{
+        input::{InputSource, InputSourceConnection, InputSourceProducer},
+        synth::SynthPlaybackOptions,
+    },
+    utils::actor::{Act, Actor, ActorHandle},
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Number of sample frames which are converted at once for non f32 sample formats.
+const CONVERT_BUFFER_FRAMES: usize = 1024;
+
+/// Max number of input sources which can be fed at once. Preallocated, so the capture callback
+/// never reallocates.
+const PREALLOCATED_SOURCES: usize = 16;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Options to open a [`CpalInput`]: which host and device to use and the requested stream
+/// config. Unset stream config values use the device's default values.
+#[derive(Debug, Clone)]
+pub struct CpalInputConfig {
+    /// By default [`AudioHostId::Default`]: the audio host to open the device from.
+    pub host: AudioHostId,
+    /// By default [`CpalDeviceId::Default`]: the input device to open.
+    pub device: CpalDeviceId,
+    /// By default None: requested input sample rate. Opening fails when the device does not
+    /// support the given sample rate.
+    pub sample_rate: Option<u32>,
+    /// By default None: requested input channel count. Opening fails when the device does not
+    /// support the given channel count.
+    pub channel_count: Option<usize>,
+    /// By default None: requested buffer size in frames. Gets clamped to the range the device
+    /// supports, when known.
+    pub buffer_size: Option<u32>,
+    /// By default 100ms: maximum amount of captured audio which gets buffered in input sources.
+    /// When sources don't consume the captured audio fast enough, older samples get skipped.
+    pub max_latency: Duration,
+}
+
+impl Default for CpalInputConfig {
+    fn default() -> Self {
+        Self {
+            host: AudioHostId::Default,
+            device: CpalDeviceId::Default,
+            sample_rate: None,
+            channel_count: None,
+            buffer_size: None,
+            max_latency: Duration::from_millis(100),
+        }
+    }
+}
+
+impl CpalInputConfig {
+    pub fn host(mut self, host: AudioHostId) -> Self {
+        self.host = host;
+        self
+    }
+
+    pub fn device(mut self, device: CpalDeviceId) -> Self {
+        self.device = device;
+        self
+    }
+    pub fn device_name(mut self, name: impl Into<String>) -> Self {
+        self.device = CpalDeviceId::Name(name.into());
+        self
+    }
+    pub fn device_index(mut self, index: usize) -> Self {
+        self.device = CpalDeviceId::Index(index);
+        self
+    }
+
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+    pub fn channel_count(mut self, channel_count: usize) -> Self {
+        self.channel_count = Some(channel_count);
+        self
+    }
+    pub fn buffer_size(mut self, frames: u32) -> Self {
+        self.buffer_size = Some(frames);
+        self
+    }
+
+    pub fn max_latency(mut self, duration: Duration) -> Self {
+        self.max_latency = duration;
+        self
+    }
+
+    /// Validate all parameters. Returns Error::ParameterError on errors.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.sample_rate == Some(0) {
+            return Err(Error::ParameterError(
+                "input config 'sample_rate' value is '0'".to_string(),
+            ));
+        }
+        if let Some(channel_count) = self.channel_count {
+            if channel_count == 0 || channel_count > cpal::ChannelCount::MAX as usize {
+                return Err(Error::ParameterError(format!(
+                    "input config 'channel_count' value is '{}'",
+                    channel_count
+                )));
+            }
+        }
+        if self.buffer_size == Some(0) {
+            return Err(Error::ParameterError(
+                "input config 'buffer_size' value is '0'".to_string(),
+            ));
+        }
+        if self.max_latency.is_zero() {
+            return Err(Error::ParameterError(
+                "input config 'max_latency' value is '0'".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Audio input device, which captures audio from a cpal input device such as a microphone or
+/// line input.
+///
+/// Captured audio can be monitored or recorded by playing back [`InputSource`]s, which are
+/// created via [`source`](Self::source), with a [`Player`](crate::Player). Each source gets fed
+/// with the captured audio via its own lock-free ring buffer.
+///
+/// Capturing starts when the input gets opened and stops when the input is dropped. Playing
+/// input sources are exhausted then.
+pub struct CpalInput {
+    handle: ActorHandle<InputStreamMsg>,
+    name: String,
+    channel_count: usize,
+    sample_rate: u32,
+    max_latency: Duration,
+    callback_send: Sender<InputCallbackMsg>,
+    sources: Mutex<Vec<InputSourceConnection>>,
+}
+
+impl CpalInput {
+    /// Open the default input device of the default host.
+    pub fn open() -> Result<Self, Error> {
+        Self::open_with_config(CpalInputConfig::default())
+    }
+
+    /// Open the default input device of the given host.
+    pub fn open_with_host(hostid: AudioHostId) -> Result<Self, Error> {
+        Self::open_with_config(CpalInputConfig::default().host(hostid))
+    }
+
+    /// Open an input device with the given host, device and stream config.
+    pub fn open_with_config(config: CpalInputConfig) -> Result<Self, Error> {
+        config.validate()?;
+
+        let host = Self::host(config.host)?;
+        let device = Self::find_device(&host, &config.device)?;
+        let name = device.name().unwrap_or_else(|_| "audio input".to_string());
+        log::info!("using audio input device: {}", name);
+
+        let supported = Self::preferred_input_config(&device, &config)?;
+        let buffer_size = match (config.buffer_size, *supported.buffer_size()) {
+            (Some(frames), cpal::SupportedBufferSize::Range { min, max }) => {
+                cpal::BufferSize::Fixed(frames.clamp(min, max))
+            }
+            (Some(frames), cpal::SupportedBufferSize::Unknown) => cpal::BufferSize::Fixed(frames),
+            (None, _) => cpal::BufferSize::Default,
+        };
+        let stream_config = cpal::StreamConfig {
+            buffer_size,
+            ..supported.config()
+        };
+        let sample_format = supported.sample_format();
+
+        let (callback_send, callback_recv) = bounded(PREALLOCATED_SOURCES);
+        let (result_send, result_recv) = bounded(1);
+
+        let handle = InputStream::spawn_with_default_cap("audio_input", move |stream_send| {
+            let callback = InputStreamCallback {
+                callback_recv,
+                stream_send,
+                producers: Vec::with_capacity(PREALLOCATED_SOURCES),
+                convert_buffer: vec![0.0; CONVERT_BUFFER_FRAMES * stream_config.channels as usize],
+            };
+            match InputStream::open(&device, &stream_config, sample_format, callback) {
+                Ok(stream) => {
+                    let _ = result_send.send(Ok(()));
+                    InputStream {
+                        stream: Some(stream),
+                    }
+                }
+                Err(err) => {
+                    let _ = result_send.send(Err(err));
+                    InputStream { stream: None }
+                }
+            }
+        });
+        result_recv.recv().map_err(|_| Error::SendError)??;
+
+        Ok(Self {
+            handle,
+            name,
+            channel_count: supported.channels() as usize,
+            sample_rate: supported.sample_rate().0,
+            max_latency: config.max_latency,
+            callback_send,
+            sources: Mutex::new(Vec::with_capacity(PREALLOCATED_SOURCES)),
+        })
+    }
+
+    /// List all available input devices of the given host.
+    pub fn devices(hostid: AudioHostId) -> Result<Vec<CpalDeviceInfo>, Error> {
+        let host = Self::host(hostid)?;
+        let default_name = host
+            .default_input_device()
+            .and_then(|device| device.name().ok());
+
+        let mut devices = Vec::new();
+        for (index, device) in host
+            .input_devices()
+            .map_err(|err| Error::InputDeviceError(Box::new(err)))?
+            .enumerate()
+        {
+            let name = match device.name() {
+                Ok(name) => name,
+                Err(err) => {
+                    log::warn!("failed to query name of audio device #{}: {}", index, err);
+                    continue;
+                }
+            };
+            let supported_configs = match device.supported_input_configs() {
+                Ok(configs) => CpalDeviceConfigRange::from_supported_configs(configs),
+                Err(err) => {
+                    log::warn!(
+                        "failed to query configs of audio device '{}': {}",
+                        name,
+                        err
+                    );
+                    Vec::new()
+                }
+            };
+            devices.push(CpalDeviceInfo {
+                index,
+                is_default: default_name.as_ref() == Some(&name),
+                name,
+                supported_configs,
+            });
+        }
+        Ok(devices)
+    }
+
+    /// The opened device's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Actual device's input sample buffer channel count.
+    pub fn channel_count(&self) -> usize {
+        self.channel_count
+    }
+
+    /// Actual device's input sample rate.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Create a new source, which plays back the captured audio from now on, with the given
+    /// playback options. Play it with [`Player::play_synth_source`](crate::Player::play_synth_source).
+    ///
+    /// Up to 16 sources can be fed at once. Creating more sources fails with an error until
+    /// some of the existing sources got dropped.
+    pub fn source(&self, options: SynthPlaybackOptions) -> Result<InputSource, Error> {
+        self.source_with_status_sender(options, None)
+    }
+
+    /// Create a new source with the given playback options, which sends playback status events
+    /// to the given channel. See [`source`](Self::source).
+    pub fn source_with_status_sender(
+        &self,
+        options: SynthPlaybackOptions,
+        event_send: Option<Sender<PlaybackStatusEvent>>,
+    ) -> Result<InputSource, Error> {
+        let mut sources = self.sources.lock().expect("input sources got poisoned");
+        // forget about dropped sources: the callback drops their producers too
+        sources.retain(|source| source.is_connected());
+        if sources.len() >= PREALLOCATED_SOURCES {
+            return Err(Error::ParameterError(format!(
+                "too many input sources: max {PREALLOCATED_SOURCES} sources are allowed"
+            )));
+        }
+        let (source, producer) = InputSource::new(
+            &self.name,
+            self.channel_count,
+            self.sample_rate,
+            self.max_latency,
+            options,
+            event_send,
+        )?;
+        let connection = producer.connection();
+        self.callback_send
+            .try_send(InputCallbackMsg::AddSource(producer))
+            .map_err(|_| Error::SendError)?;
+        sources.push(connection);
+        Ok(source)
+    }
+
+    fn host(hostid: AudioHostId) -> Result<cpal::Host, Error> {
+        CpalOutput::host(hostid).map_err(|err| match err {
+            Error::OutputDeviceError(err) => Error::InputDeviceError(err),
+            err => err,
+        })
+    }
+
+    fn find_device(host: &cpal::Host, device_id: &CpalDeviceId) -> Result<cpal::Device, Error> {
+        let device = match device_id {
+            CpalDeviceId::Default => host.default_input_device(),
+            CpalDeviceId::Name(name) => host
+                .input_devices()
+                .map_err(|err| Error::InputDeviceError(Box::new(err)))?
+                .find(|device| device.name().is_ok_and(|n| n == *name)),
+            CpalDeviceId::Index(index) => host
+                .input_devices()
+                .map_err(|err| Error::InputDeviceError(Box::new(err)))?
+                .nth(*index),
+        };
+        device.ok_or_else(|| match device_id {
+            CpalDeviceId::Default => Error::InputDeviceError(Box::new(
+                cpal::DefaultStreamConfigError::DeviceNotAvailable,
+            )),
+            CpalDeviceId::Name(name) => {
+                Error::InputDeviceError(format!("no audio device named '{}'", name).into())
+            }
+            CpalDeviceId::Index(index) => {
+                Error::InputDeviceError(format!("no audio device with index '{}'", index).into())
+            }
+        })
+    }
+
+    fn preferred_input_config(
+        device: &cpal::Device,
+        config: &CpalInputConfig,
+    ) -> Result<cpal::SupportedStreamConfig, Error> {
+        let default_config = device
+            .default_input_config()
+            .map_err(|err| Error::InputDeviceError(Box::new(err)))?;
+        let channels = config
+            .channel_count
+            .map_or(default_config.channels(), |count| {
+                count as cpal::ChannelCount
+            });
+        let sample_rate = config
+            .sample_rate
+            .map_or(default_config.sample_rate(), cpal::SampleRate);
+
+        // Use the most preferred sample format for the requested channels and rate
+        let format_rank = |format: cpal::SampleFormat| {
+            PREFERRED_SAMPLE_FORMATS
+                .iter()
+                .position(|preferred| *preferred == format)
+        };
+        device
+            .supported_input_configs()
+            .map_err(|err| Error::InputDeviceError(Box::new(err)))?
+            .filter(|s| {
+                s.channels() == channels
+                    && (s.min_sample_rate()..=s.max_sample_rate()).contains(&sample_rate)
+            })
+            .filter_map(|s| format_rank(s.sample_format()).map(|rank| (rank, s)))
+            .min_by_key(|(rank, _)| *rank)
+            .map(|(_, s)| s.with_sample_rate(sample_rate))
+            .ok_or_else(|| {
+                Error::InputDeviceError(
+                    format!(
+                        "audio device does not support {} channels at {} Hz",
+                        channels, sample_rate.0
+                    )
+                    .into(),
+                )
+            })
+    }
+}
+
+impl Drop for CpalInput {
+    fn drop(&mut self) {
+        let _ = self.handle.send(InputStreamMsg::Close);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+struct InputStream {
+    stream: Option<cpal::Stream>,
+}
+
+impl InputStream {
+    fn open(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        sample_format: cpal::SampleFormat,
+        callback: InputStreamCallback,
+    ) -> Result<cpal::Stream, Error> {
+        log::info!(
+            "opening input stream: {:?} with sample format {}",
+            config,
+            sample_format
+        );
+        // f32 buffers are read directly, all other sample formats get converted
+        let c = callback;
+        let stream = match sample_format {
+            cpal::SampleFormat::F32 => Self::build(device, config, c, |c, i: &[f32]| c.read(i)),
+            cpal::SampleFormat::F64 => {
+                Self::build(device, config, c, |c, i: &[f64]| c.read_converted(i))
+            }
+            cpal::SampleFormat::I8 => {
+                Self::build(device, config, c, |c, i: &[i8]| c.read_converted(i))
+            }
+            cpal::SampleFormat::U8 => {
+                Self::build(device, config, c, |c, i: &[u8]| c.read_converted(i))
+            }
+            cpal::SampleFormat::I16 => {
+                Self::build(device, config, c, |c, i: &[i16]| c.read_converted(i))
+            }
+            cpal::SampleFormat::U16 => {
+                Self::build(device, config, c, |c, i: &[u16]| c.read_converted(i))
+            }
+            cpal::SampleFormat::I32 => {
+                Self::build(device, config, c, |c, i: &[i32]| c.read_converted(i))
+            }
+            cpal::SampleFormat::U32 => {
+                Self::build(device, config, c, |c, i: &[u32]| c.read_converted(i))
+            }
+            cpal::SampleFormat::I64 => {
+                Self::build(device, config, c, |c, i: &[i64]| c.read_converted(i))
+            }
+            cpal::SampleFormat::U64 => {
+                Self::build(device, config, c, |c, i: &[u64]| c.read_converted(i))
+            }
+            format => Err(Error::InputDeviceError(
+                format!("unsupported sample format: {}", format).into(),
+            )),
+        }?;
+        stream
+            .play()
+            .map_err(|err| Error::InputDeviceError(Box::new(err)))?;
+        Ok(stream)
+    }
+
+    /// Build an input stream with the given sample type, using the given reader function to
+    /// consume the stream's input buffers.
+    fn build<T>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        mut callback: InputStreamCallback,
+        read: fn(&mut InputStreamCallback, &[T]),
+    ) -> Result<cpal::Stream, Error>
+    where
+        T: cpal::SizedSample + 'static,
+    {
+        device
+            .build_input_stream(
+                config,
+                move |input: &[T], _: &cpal::InputCallbackInfo| {
+                    #[cfg(not(feature = "assert_no_alloc"))]
+                    {
+                        callback.receive_messages();
+                        read(&mut callback, input);
+                    }
+                    #[cfg(feature = "assert_no_alloc")]
+                    assert_no_alloc(|| {
+                        callback.receive_messages();
+                        read(&mut callback, input);
+                    });
+                },
+                |err| {
+                    log::error!("audio input error: {}", err);
+                },
+                None,
+            )
+            .map_err(|err| Error::InputDeviceError(Box::new(err)))
+    }
+}
+
+impl Actor for InputStream {
+    type Message = InputStreamMsg;
+    type Error = Error;
+
+    fn handle(&mut self, msg: Self::Message) -> Result<Act<Self>, Self::Error> {
+        match msg {
+            InputStreamMsg::Close => {
+                log::debug!("closing audio input stream");
+                if let Some(stream) = self.stream.take() {
+                    let _ = stream.pause();
+                }
+                Ok(Act::Shutdown)
+            }
+            InputStreamMsg::DropProducer(producer) => {
+                // release the producer's ring buffer outside of the capture callback
+                drop(producer);
+                Ok(Act::Continue)
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+enum InputStreamMsg {
+    Close,
+    DropProducer(InputSourceProducer),
+}
+
+enum InputCallbackMsg {
+    AddSource(InputSourceProducer),
+}
+
+struct InputStreamCallback {
+    callback_recv: Receiver<InputCallbackMsg>,
+    stream_send: Sender<InputStreamMsg>,
+    producers: Vec<InputSourceProducer>,
+    convert_buffer: Vec<f32>,
+}
+
+impl InputStreamCallback {
+    fn receive_messages(&mut self) {
+        // Forget about producers of dropped sources, so their slots can be reused
+        let mut index = 0;
+        while index < self.producers.len() {
+            if self.producers[index].is_connected() {
+                index += 1;
+            } else {
+                let producer = self.producers.swap_remove(index);
+                self.drop_producer(producer);
+            }
+        }
+        while let Ok(msg) = self.callback_recv.try_recv() {
+            match msg {
+                InputCallbackMsg::AddSource(producer) => {
+                    if self.producers.len() < PREALLOCATED_SOURCES {
+                        self.producers.push(producer);
+                    } else {
+                        log::warn!(
+                            "too many input sources: rejecting source, max {} sources are allowed",
+                            PREALLOCATED_SOURCES
+                        );
+                        self.drop_producer(producer);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Let the input stream's thread drop the given producer, so its ring buffer does not get
+    /// freed in the capture callback.
+    fn drop_producer(&self, producer: InputSourceProducer) {
+        // NB: the stream's message queue only fails when the stream got closed
+        let _ = self
+            .stream_send
+            .try_send(InputStreamMsg::DropProducer(producer));
+    }
+
+    fn read(&mut self, input: &[f32]) {
+        for producer in &self.producers {
+            producer.write(input);
+        }
+    }
+
+    fn read_converted<T>(&mut self, input: &[T])
+    where
+        T: cpal::SizedSample,
+        f32: cpal::FromSample<T>,
+    {
+        // Convert the input in chunks into our temp buffer and pass it along
+        let mut convert_buffer = std::mem::take(&mut self.convert_buffer);
+        for chunk in input.chunks(convert_buffer.len()) {
+            let buffer = &mut convert_buffer[..chunk.len()];
+            for (o, i) in buffer.iter_mut().zip(chunk.iter()) {
+                *o = cpal::Sample::to_sample::<f32>(*i);
+            }
+            self.read(buffer);
+        }
+        self.convert_buffer = convert_buffer;
+    }
+}
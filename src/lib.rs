@@ -2,7 +2,7 @@
 
 // private mods (will be partly re-exported)
 mod error;
-#[cfg(any(feature = "cpal", feature = "sokol", doc))]
+mod input;
 mod output;
 mod player;
 mod source;
@@ -10,23 +10,52 @@ mod source;
 // public mods
 pub mod utils;
 
+// catch (de)allocations in real-time threads in tests
+#[cfg(all(test, feature = "assert_no_alloc"))]
+#[global_allocator]
+static A: assert_no_alloc::AllocDisabler = assert_no_alloc::AllocDisabler;
+
 // public, flat re-exports
 pub use error::Error;
+pub use utils::automation::{EnvelopePoint, VolumeEnvelope};
+pub use utils::fader::FadeCurve;
 
+#[cfg(feature = "cpal")]
+pub use output::cpal::{
+    CpalDeviceConfigRange, CpalDeviceEvent, CpalDeviceId, CpalDeviceInfo, CpalOutput,
+    CpalOutputConfig,
+};
 #[cfg(any(feature = "cpal", doc))]
 pub use output::AudioHostId;
+pub use output::{
+    manual::{ManualOutput, ManualSink},
+    OutputDevice, OutputSink,
+};
 #[cfg(any(feature = "cpal", feature = "sokol", doc))]
-pub use output::{DefaultOutputDevice, DefaultOutputSink, OutputDevice, OutputSink};
+pub use output::{DefaultOutputDevice, DefaultOutputSink};
+
+#[cfg(feature = "cpal")]
+pub use input::cpal::{CpalInput, CpalInputConfig};
+#[cfg(feature = "cpal")]
+pub use source::input::InputSource;
+
+#[cfg(feature = "hound")]
+pub use output::wav::{WavOutput, WavSink};
 
-pub use player::{PlaybackId, PlaybackStatusContext, PlaybackStatusEvent, Player};
+pub use player::{BusId, PlaybackId, PlaybackStatusContext, PlaybackStatusEvent, Player};
 
 #[cfg(feature = "dasp")]
 pub use source::synth::dasp::DaspSynthSource;
 
 pub use source::{
     file::{
-        preloaded::PreloadedFileSource, streamed::StreamedFileSource, FilePlaybackOptions,
-        FileSource,
+        buffer::{PcmBuffer, PcmSample},
+        playlist::{PlaylistItem, PlaylistSource},
+        preloaded::PreloadedFileSource,
+        probe::{probe_file, probe_tracks, CoverArt, FileInfo, FileMetadata, TrackInfo},
+        streamed::StreamedFileSource,
+        EncodedAudio, FileMarker, FilePlaybackOptions, FilePosition, FileSource, LoopMode,
+        TrackSelector,
     },
     mixed::MixedSource,
     resampled::ResamplingQuality,
@@ -8,6 +8,10 @@ pub mod cpal;
 #[cfg(all(feature = "sokol", not(feature = "cpal")))]
 pub mod sokol;
 
+pub mod manual;
+#[cfg(feature = "hound")]
+pub mod wav;
+
 /// The enabled audio output type: cpal or sokol
 #[cfg(feature = "cpal")]
 pub type DefaultOutputDevice = cpal::CpalOutput;
@@ -17,7 +21,9 @@ pub type DefaultOutputDevice = sokol::SokolOutput;
 
 /// Available audio hosts for cpal output (platform specific)
 #[cfg(feature = "cpal")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum AudioHostId {
+    #[default]
     Default, // system default
     #[cfg(target_os = "windows")]
     Asio,
@@ -30,14 +36,17 @@ pub enum AudioHostId {
 }
 
 /// The enabled audio output sink type: cpal or sokol
+#[cfg(any(feature = "cpal", feature = "sokol"))]
 pub type DefaultOutputSink = <DefaultOutputDevice as OutputDevice>::Sink;
 
+use std::time::Duration;
+
 use super::source::Source;
 
 // -------------------------------------------------------------------------------------------------
 
 /// OutputDevice controller
-pub trait OutputSink {
+pub trait OutputSink: Send {
     /// true when audio output is currently suspended by the system: only used in Sokol's
     /// WebAudio backend, all other backends return false
     fn suspended(&self) -> bool;
@@ -48,6 +57,10 @@ pub trait OutputSink {
     fn sample_rate(&self) -> u32;
     /// Actual device's output playhead position in **samples** (NOT frames).
     fn sample_position(&self) -> u64;
+    /// Actual device's output latency: the time it takes until a sample frame which got written
+    /// into the output gets audible. Includes the output buffer size and, when known, the
+    /// device's latency. Offline outputs have no latency.
+    fn output_latency(&self) -> Duration;
 
     /// Get actual output volume.
     fn volume(&self) -> f32;
@@ -55,7 +68,7 @@ pub trait OutputSink {
     fn set_volume(&mut self, volume: f32);
 
     /// Play given source as main output source.
-    fn play(&mut self, source: impl Source);
+    fn play(&mut self, source: Box<dyn Source>);
     /// Drop actual source, replacing it with silence
     fn stop(&mut self);
     /// Pause playback without dropping the ouput source.
@@ -1,9 +1,9 @@
 use std::{
     sync::{
         atomic::{AtomicU64, Ordering},
-        Arc,
+        Arc, Mutex,
     },
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 #[cfg(feature = "assert_no_alloc")]
@@ -24,7 +24,20 @@ use crate::{
 
 // -------------------------------------------------------------------------------------------------
 
-const PREFERRED_SAMPLE_FORMAT: cpal::SampleFormat = cpal::SampleFormat::F32;
+/// Supported sample formats, ordered by preference: f32 is the mixer's native format and
+/// needs no conversion. Integer formats are converted, optionally with dithering.
+pub(crate) const PREFERRED_SAMPLE_FORMATS: [cpal::SampleFormat; 10] = [
+    cpal::SampleFormat::F32,
+    cpal::SampleFormat::F64,
+    cpal::SampleFormat::I32,
+    cpal::SampleFormat::U32,
+    cpal::SampleFormat::I64,
+    cpal::SampleFormat::U64,
+    cpal::SampleFormat::I16,
+    cpal::SampleFormat::U16,
+    cpal::SampleFormat::I8,
+    cpal::SampleFormat::U8,
+];
 const PREFERRED_SAMPLE_RATE: cpal::SampleRate = cpal::SampleRate(44100);
 const PREFERRED_CHANNELS: cpal::ChannelCount = 2;
 const PREFERRED_BUFFER_SIZE: cpal::BufferSize = if cfg!(debug_assertions) {
@@ -33,6 +46,225 @@ const PREFERRED_BUFFER_SIZE: cpal::BufferSize = if cfg!(debug_assertions) {
     cpal::BufferSize::Fixed(2048)
 };
 
+/// Interval in which auto recovering streams try to reopen a device.
+const RECOVERY_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of sample frames which are converted at once for non f32 sample formats.
+const CONVERT_BUFFER_FRAMES: usize = 1024;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Selects the audio device which should be opened by a [`CpalOutput`] or
+/// [`CpalInput`](crate::CpalInput).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum CpalDeviceId {
+    /// The host's default device.
+    #[default]
+    Default,
+    /// A device with the given name, as listed by the `devices` functions.
+    Name(String),
+    /// A device with the given index, as listed by the `devices` functions.
+    Index(usize),
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A stream config range, which is supported by a cpal output device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpalDeviceConfigRange {
+    /// Number of output channels.
+    pub channel_count: usize,
+    /// Minimum supported sample rate.
+    pub min_sample_rate: u32,
+    /// Maximum supported sample rate.
+    pub max_sample_rate: u32,
+    /// Supported buffer size range in frames, when known.
+    pub buffer_size: Option<(u32, u32)>,
+}
+
+impl CpalDeviceConfigRange {
+    /// Convert cpal's supported configs. Configs which only differ by their sample format
+    /// show up once.
+    pub(crate) fn from_supported_configs(
+        configs: impl Iterator<Item = cpal::SupportedStreamConfigRange>,
+    ) -> Vec<Self> {
+        let mut ranges = Vec::<Self>::new();
+        for config in configs {
+            let range = Self {
+                channel_count: config.channels() as usize,
+                min_sample_rate: config.min_sample_rate().0,
+                max_sample_rate: config.max_sample_rate().0,
+                buffer_size: match *config.buffer_size() {
+                    cpal::SupportedBufferSize::Range { min, max } => Some((min, max)),
+                    cpal::SupportedBufferSize::Unknown => None,
+                },
+            };
+            if !ranges.contains(&range) {
+                ranges.push(range);
+            }
+        }
+        ranges
+    }
+}
+
+/// Describes an available cpal output or input device. See [`CpalOutput::devices`] and
+/// [`CpalInput::devices`](crate::CpalInput::devices).
+#[derive(Debug, Clone)]
+pub struct CpalDeviceInfo {
+    /// Index of the device in the host's output or input device list.
+    pub index: usize,
+    /// The device's name.
+    pub name: String,
+    /// True when this is the host's default output device.
+    pub is_default: bool,
+    /// All stream config ranges the device supports.
+    pub supported_configs: Vec<CpalDeviceConfigRange>,
+}
+
+impl CpalDeviceInfo {
+    /// All supported channel counts, sorted and without duplicates.
+    pub fn channel_counts(&self) -> Vec<usize> {
+        let mut channel_counts = self
+            .supported_configs
+            .iter()
+            .map(|config| config.channel_count)
+            .collect::<Vec<_>>();
+        channel_counts.sort_unstable();
+        channel_counts.dedup();
+        channel_counts
+    }
+
+    /// True when the device supports the given channel count and sample rate.
+    pub fn supports(&self, channel_count: usize, sample_rate: u32) -> bool {
+        self.supported_configs.iter().any(|config| {
+            config.channel_count == channel_count
+                && (config.min_sample_rate..=config.max_sample_rate).contains(&sample_rate)
+        })
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Audio device events of a [`CpalOutput`], which get sent to the
+/// [`device_event_sender`](CpalOutputConfig::device_event_sender) of the output.
+#[derive(Debug, Clone)]
+pub enum CpalDeviceEvent {
+    /// The device got disconnected or is no longer available. Playback stops, unless
+    /// [`auto_recover`](CpalOutputConfig::auto_recover) is enabled.
+    Disconnected,
+    /// The device reported some backend specific error.
+    Error(String),
+    /// The output got recovered after a disconnection: the stream got reopened with the
+    /// default device of the output's host.
+    Recovered { device_name: String },
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Options to open a [`CpalOutput`]: which host and device to use and the requested stream
+/// config. Unset stream config values use the device's preferred or default values.
+#[derive(Debug, Clone, Default)]
+pub struct CpalOutputConfig {
+    /// By default [`AudioHostId::Default`]: the audio host to open the device from.
+    pub host: AudioHostId,
+    /// By default [`CpalDeviceId::Default`]: the output device to open.
+    pub device: CpalDeviceId,
+    /// By default None: requested output sample rate. Opening fails when the device does not
+    /// support the given sample rate.
+    pub sample_rate: Option<u32>,
+    /// By default None: requested output channel count. Opening fails when the device does not
+    /// support the given channel count.
+    pub channel_count: Option<usize>,
+    /// By default None: requested buffer size in frames. Gets clamped to the range the device
+    /// supports, when known. When unset, release builds use 2048 frames and debug builds use
+    /// the device's default buffer size. Smaller buffers lower the output latency, see
+    /// [`OutputSink::output_latency`].
+    pub buffer_size: Option<u32>,
+    /// By default None: when set, audio device errors and disconnections are sent as
+    /// [`CpalDeviceEvent`]s to the given channel.
+    pub device_event_sender: Option<Sender<CpalDeviceEvent>>,
+    /// By default false: when enabled and the device gets disconnected, the output periodically
+    /// tries to reopen the host's default device with the same sample rate, channel count and
+    /// buffer size. The running source, e.g. a player's mixer with all its scheduled sources,
+    /// and the playback position are kept.
+    pub auto_recover: bool,
+    /// By default false: when enabled, TPDF dither is applied when converting the mixer's f32
+    /// output to 8 or 16 bit integer sample formats. The wider 32 and 64 bit integer formats
+    /// are not dithered: they resolve finer than the mixer's 24 bit f32 mantissa, so converting
+    /// to them adds no audible quantization noise. Has no effect for float sample formats.
+    pub dither: bool,
+}
+
+impl CpalOutputConfig {
+    pub fn host(mut self, host: AudioHostId) -> Self {
+        self.host = host;
+        self
+    }
+
+    pub fn device(mut self, device: CpalDeviceId) -> Self {
+        self.device = device;
+        self
+    }
+    pub fn device_name(mut self, name: impl Into<String>) -> Self {
+        self.device = CpalDeviceId::Name(name.into());
+        self
+    }
+    pub fn device_index(mut self, index: usize) -> Self {
+        self.device = CpalDeviceId::Index(index);
+        self
+    }
+
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+    pub fn channel_count(mut self, channel_count: usize) -> Self {
+        self.channel_count = Some(channel_count);
+        self
+    }
+    pub fn buffer_size(mut self, frames: u32) -> Self {
+        self.buffer_size = Some(frames);
+        self
+    }
+
+    pub fn device_event_sender(mut self, sender: Sender<CpalDeviceEvent>) -> Self {
+        self.device_event_sender = Some(sender);
+        self
+    }
+    pub fn auto_recover(mut self, enabled: bool) -> Self {
+        self.auto_recover = enabled;
+        self
+    }
+
+    pub fn dither(mut self, enabled: bool) -> Self {
+        self.dither = enabled;
+        self
+    }
+
+    /// Validate all parameters. Returns Error::ParameterError on errors.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.sample_rate == Some(0) {
+            return Err(Error::ParameterError(
+                "output config 'sample_rate' value is '0'".to_string(),
+            ));
+        }
+        if let Some(channel_count) = self.channel_count {
+            if channel_count == 0 || channel_count > cpal::ChannelCount::MAX as usize {
+                return Err(Error::ParameterError(format!(
+                    "output config 'channel_count' value is '{}'",
+                    channel_count
+                )));
+            }
+        }
+        if self.buffer_size == Some(0) {
+            return Err(Error::ParameterError(
+                "output config 'buffer_size' value is '0'".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
 // -------------------------------------------------------------------------------------------------
 
 pub struct CpalOutput {
@@ -41,57 +273,79 @@ pub struct CpalOutput {
 }
 
 impl CpalOutput {
+    /// Open the default output device of the default host.
     pub fn open() -> Result<Self, Error> {
-        Self::open_with_host(AudioHostId::Default)
+        Self::open_with_config(CpalOutputConfig::default())
     }
 
+    /// Open the default output device of the given host.
     pub fn open_with_host(hostid: AudioHostId) -> Result<Self, Error> {
-        let host = match hostid {
-            AudioHostId::Default => cpal::default_host(),
-            #[cfg(target_os = "windows")]
-            AudioHostId::Asio => cpal::host_from_id(cpal::HostId::Asio)
-                .map_err(|err| Error::OutputDeviceError(Box::new(err)))?,
-            #[cfg(target_os = "windows")]
-            AudioHostId::Wasapi => cpal::host_from_id(cpal::HostId::Wasapi)
-                .map_err(|err| Error::OutputDeviceError(Box::new(err)))?,
-            #[cfg(target_os = "linux")]
-            AudioHostId::Alsa => cpal::host_from_id(cpal::HostId::Alsa)
-                .map_err(|err| Error::OutputDeviceError(Box::new(err)))?,
-            #[cfg(target_os = "linux")]
-            AudioHostId::Jack => cpal::host_from_id(cpal::HostId::Jack)
-                .map_err(|err| Error::OutputDeviceError(Box::new(err)))?,
-        };
+        Self::open_with_config(CpalOutputConfig::default().host(hostid))
+    }
 
-        // Open the default output device.
-        let device = host
-            .default_output_device()
-            .ok_or(cpal::DefaultStreamConfigError::DeviceNotAvailable)?;
+    /// Open an output device with the given host, device and stream config.
+    pub fn open_with_config(config: CpalOutputConfig) -> Result<Self, Error> {
+        config.validate()?;
+
+        let host = Self::host(config.host)?;
+        let device = Self::find_device(&host, &config.device)?;
 
         if let Ok(name) = device.name() {
             log::info!("using audio device: {}", name);
         }
 
-        // Get the default device config, so we know what sample format and sample rate
+        // Get the device config, so we know what sample format and sample rate
         // the device supports.
-        let supported = Self::preferred_output_config(&device)?;
+        let supported = Self::preferred_output_config(&device, &config)?;
+        let buffer_size = Self::preferred_buffer_size(&supported, &config);
         // Shared playback position counter
         let playback_pos = Arc::new(AtomicU64::new(0));
+        // Shared output latency in nanoseconds: estimated from the buffer size until the first
+        // callback measures it.
+        let output_latency = Arc::new(AtomicU64::new(match buffer_size {
+            cpal::BufferSize::Fixed(frames) => {
+                Duration::from_secs_f64(frames as f64 / supported.sample_rate().0 as f64).as_nanos()
+                    as u64
+            }
+            cpal::BufferSize::Default => 0,
+        }));
 
         let (callback_send, callback_recv) = bounded(16);
 
-        let handle = Stream::spawn_with_default_cap("audio_output", {
-            let config = StreamConfig {
-                buffer_size: PREFERRED_BUFFER_SIZE,
+        let callback = Arc::new(Mutex::new(StreamCallback {
+            callback_recv,
+            source: Box::new(EmptySource),
+            playback_pos: Arc::clone(&playback_pos),
+            playback_pos_instant: Instant::now(),
+            output_latency: Arc::clone(&output_latency),
+            channel_count: supported.channels() as usize,
+            sample_rate: supported.sample_rate().0,
+            state: CallbackState::Paused,
+            volume: 1.0,
+            convert_buffer: vec![0.0; CONVERT_BUFFER_FRAMES * supported.channels() as usize],
+            dither: config.dither,
+            dither_state: 0x9E37_79B9,
+        }));
+        let stream_device = StreamDevice {
+            host: config.host,
+            sample_format: supported.sample_format(),
+            config: StreamConfig {
+                buffer_size,
                 ..supported.config()
-            };
-            let playback_pos = Arc::clone(&playback_pos);
-            move |this| Stream::open(device, config, playback_pos, callback_recv, this).unwrap()
+            },
+            auto_recover: config.auto_recover,
+            event_sender: config.device_event_sender,
+        };
+
+        let handle = Stream::spawn_with_default_cap("audio_output", move |this| {
+            Stream::new(device, stream_device, callback, this).unwrap()
         });
         let sink = CpalSink {
             channel_count: supported.channels(),
             sample_rate: supported.sample_rate(),
             volume: 1.0,
             playback_pos,
+            output_latency,
             stream_send: handle.sender(),
             callback_send,
         };
@@ -102,20 +356,165 @@ impl CpalOutput {
         })
     }
 
+    /// List all available output devices of the given host.
+    pub fn devices(hostid: AudioHostId) -> Result<Vec<CpalDeviceInfo>, Error> {
+        let host = Self::host(hostid)?;
+        let default_name = host
+            .default_output_device()
+            .and_then(|device| device.name().ok());
+
+        let mut devices = Vec::new();
+        for (index, device) in host.output_devices()?.enumerate() {
+            let name = match device.name() {
+                Ok(name) => name,
+                Err(err) => {
+                    log::warn!("failed to query name of audio device #{}: {}", index, err);
+                    continue;
+                }
+            };
+            let supported_configs = match device.supported_output_configs() {
+                Ok(configs) => CpalDeviceConfigRange::from_supported_configs(configs),
+                Err(err) => {
+                    log::warn!(
+                        "failed to query configs of audio device '{}': {}",
+                        name,
+                        err
+                    );
+                    Vec::new()
+                }
+            };
+            devices.push(CpalDeviceInfo {
+                index,
+                is_default: default_name.as_ref() == Some(&name),
+                name,
+                supported_configs,
+            });
+        }
+        Ok(devices)
+    }
+
+    pub(crate) fn host(hostid: AudioHostId) -> Result<cpal::Host, Error> {
+        let host = match hostid {
+            AudioHostId::Default => cpal::default_host(),
+            #[cfg(target_os = "windows")]
+            AudioHostId::Asio => cpal::host_from_id(cpal::HostId::Asio)
+                .map_err(|err| Error::OutputDeviceError(Box::new(err)))?,
+            #[cfg(target_os = "windows")]
+            AudioHostId::Wasapi => cpal::host_from_id(cpal::HostId::Wasapi)
+                .map_err(|err| Error::OutputDeviceError(Box::new(err)))?,
+            #[cfg(target_os = "linux")]
+            AudioHostId::Alsa => cpal::host_from_id(cpal::HostId::Alsa)
+                .map_err(|err| Error::OutputDeviceError(Box::new(err)))?,
+            #[cfg(target_os = "linux")]
+            AudioHostId::Jack => cpal::host_from_id(cpal::HostId::Jack)
+                .map_err(|err| Error::OutputDeviceError(Box::new(err)))?,
+        };
+        Ok(host)
+    }
+
+    fn find_device(host: &cpal::Host, device_id: &CpalDeviceId) -> Result<cpal::Device, Error> {
+        let device = match device_id {
+            CpalDeviceId::Default => host.default_output_device(),
+            CpalDeviceId::Name(name) => host
+                .output_devices()?
+                .find(|device| device.name().is_ok_and(|n| n == *name)),
+            CpalDeviceId::Index(index) => host.output_devices()?.nth(*index),
+        };
+        device.ok_or_else(|| match device_id {
+            CpalDeviceId::Default => Error::OutputDeviceError(Box::new(
+                cpal::DefaultStreamConfigError::DeviceNotAvailable,
+            )),
+            CpalDeviceId::Name(name) => {
+                Error::OutputDeviceError(format!("no audio device named '{}'", name).into())
+            }
+            CpalDeviceId::Index(index) => {
+                Error::OutputDeviceError(format!("no audio device with index '{}'", index).into())
+            }
+        })
+    }
+
     fn preferred_output_config(
         device: &cpal::Device,
+        config: &CpalOutputConfig,
     ) -> Result<cpal::SupportedStreamConfig, Error> {
-        for s in device.supported_output_configs()? {
-            let rates = s.min_sample_rate()..=s.max_sample_rate();
-            if s.channels() == PREFERRED_CHANNELS
-                && s.sample_format() == PREFERRED_SAMPLE_FORMAT
-                && rates.contains(&PREFERRED_SAMPLE_RATE)
-            {
-                return Ok(s.with_sample_rate(PREFERRED_SAMPLE_RATE));
-            }
+        let channels = config
+            .channel_count
+            .map_or(PREFERRED_CHANNELS, |count| count as cpal::ChannelCount);
+        let sample_rate = config
+            .sample_rate
+            .map_or(PREFERRED_SAMPLE_RATE, cpal::SampleRate);
+
+        if let Some(supported) = Self::supported_output_config(device, channels, sample_rate)? {
+            return Ok(supported);
         }
 
-        Ok(device.default_output_config()?)
+        // Explicitly requested configs must be supported, else use the device's default config.
+        if config.channel_count.is_some() || config.sample_rate.is_some() {
+            return Err(Error::OutputDeviceError(
+                format!(
+                    "audio device does not support {} channels at {} Hz",
+                    channels, sample_rate.0
+                )
+                .into(),
+            ));
+        }
+        let supported = device.default_output_config()?;
+        if !PREFERRED_SAMPLE_FORMATS.contains(&supported.sample_format()) {
+            return Err(Error::OutputDeviceError(
+                format!(
+                    "audio device uses an unsupported sample format: {}",
+                    supported.sample_format()
+                )
+                .into(),
+            ));
+        }
+        Ok(supported)
+    }
+
+    /// Find a supported config with the given channel count and sample rate, using the most
+    /// preferred sample format the device supports.
+    fn supported_output_config(
+        device: &cpal::Device,
+        channels: cpal::ChannelCount,
+        sample_rate: cpal::SampleRate,
+    ) -> Result<Option<cpal::SupportedStreamConfig>, Error> {
+        let format_rank = |format: cpal::SampleFormat| {
+            PREFERRED_SAMPLE_FORMATS
+                .iter()
+                .position(|preferred| *preferred == format)
+        };
+        Ok(device
+            .supported_output_configs()?
+            .filter(|s| {
+                s.channels() == channels
+                    && (s.min_sample_rate()..=s.max_sample_rate()).contains(&sample_rate)
+            })
+            .filter_map(|s| format_rank(s.sample_format()).map(|rank| (rank, s)))
+            .min_by_key(|(rank, _)| *rank)
+            .map(|(_, s)| s.with_sample_rate(sample_rate)))
+    }
+
+    fn preferred_buffer_size(
+        supported: &cpal::SupportedStreamConfig,
+        config: &CpalOutputConfig,
+    ) -> cpal::BufferSize {
+        match config.buffer_size {
+            Some(frames) => match *supported.buffer_size() {
+                cpal::SupportedBufferSize::Range { min, max } => {
+                    let clamped = frames.clamp(min, max);
+                    if clamped != frames {
+                        log::warn!(
+                            "audio device does not support a buffer size of {} frames, using {}",
+                            frames,
+                            clamped
+                        );
+                    }
+                    cpal::BufferSize::Fixed(clamped)
+                }
+                cpal::SupportedBufferSize::Unknown => cpal::BufferSize::Fixed(frames),
+            },
+            None => PREFERRED_BUFFER_SIZE,
+        }
     }
 }
 
@@ -135,6 +534,7 @@ pub struct CpalSink {
     sample_rate: cpal::SampleRate,
     volume: f32,
     playback_pos: Arc<AtomicU64>,
+    output_latency: Arc<AtomicU64>,
     callback_send: Sender<CallbackMsg>,
     stream_send: Sender<StreamMsg>,
 }
@@ -169,6 +569,9 @@ impl OutputSink for CpalSink {
     fn sample_position(&self) -> u64 {
         self.playback_pos.load(Ordering::Relaxed)
     }
+    fn output_latency(&self) -> Duration {
+        Duration::from_nanos(self.output_latency.load(Ordering::Relaxed))
+    }
 
     fn volume(&self) -> f32 {
         self.volume
@@ -178,12 +581,12 @@ impl OutputSink for CpalSink {
         self.send_to_callback(CallbackMsg::SetVolume(volume));
     }
 
-    fn play(&mut self, source: impl Source) {
+    fn play(&mut self, source: Box<dyn Source>) {
         // ensure source has our sample rate and channel layout
         assert_eq!(source.channel_count(), self.channel_count());
         assert_eq!(source.sample_rate(), self.sample_rate());
         // send message to activate it in the writer
-        self.send_to_callback(CallbackMsg::PlaySource(Box::new(source)));
+        self.send_to_callback(CallbackMsg::PlaySource(source));
     }
 
     fn pause(&mut self) {
@@ -207,45 +610,209 @@ impl OutputSink for CpalSink {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Device state and recovery options of a running output stream.
+struct StreamDevice {
+    host: AudioHostId,
+    sample_format: cpal::SampleFormat,
+    config: cpal::StreamConfig,
+    auto_recover: bool,
+    event_sender: Option<Sender<CpalDeviceEvent>>,
+}
+
+impl StreamDevice {
+    fn send_event(&self, event: CpalDeviceEvent) {
+        if let Some(sender) = &self.event_sender {
+            if let Err(err) = sender.try_send(event) {
+                log::warn!("failed to send audio device event: {}", err);
+            }
+        }
+    }
+}
+
 struct Stream {
-    stream: cpal::Stream,
-    _device: cpal::Device,
+    stream: Option<cpal::Stream>,
+    device: StreamDevice,
+    callback: Arc<Mutex<StreamCallback>>,
+    stream_send: Sender<StreamMsg>,
+    playing: bool,
 }
 
 impl Stream {
-    fn open(
+    fn new(
         device: cpal::Device,
-        config: cpal::StreamConfig,
-        playback_pos: Arc<AtomicU64>,
-        callback_recv: Receiver<CallbackMsg>,
-        _stream_send: Sender<StreamMsg>,
+        stream_device: StreamDevice,
+        callback: Arc<Mutex<StreamCallback>>,
+        stream_send: Sender<StreamMsg>,
     ) -> Result<Self, Error> {
-        let mut callback = StreamCallback {
-            _stream_send,
-            callback_recv,
-            source: Box::new(EmptySource),
-            volume: 1.0,
-            playback_pos,
-            playback_pos_instant: Instant::now(),
-            state: CallbackState::Paused,
-        };
+        let stream = Self::open(
+            &device,
+            &stream_device.config,
+            stream_device.sample_format,
+            Arc::clone(&callback),
+            stream_send.clone(),
+        )?;
+        Ok(Self {
+            stream: Some(stream),
+            device: stream_device,
+            callback,
+            stream_send,
+            playing: false,
+        })
+    }
 
-        log::info!("opening output stream: {:?}", config);
+    fn open(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        sample_format: cpal::SampleFormat,
+        callback: Arc<Mutex<StreamCallback>>,
+        stream_send: Sender<StreamMsg>,
+    ) -> Result<cpal::Stream, Error> {
+        log::info!(
+            "opening output stream: {:?} with sample format {}",
+            config,
+            sample_format
+        );
+        // f32 buffers are written directly, all other sample formats get converted
+        let s = stream_send;
+        match sample_format {
+            cpal::SampleFormat::F32 => {
+                Self::build(device, config, callback, s, |c, o: &mut [f32]| {
+                    c.write_samples(o)
+                })
+            }
+            cpal::SampleFormat::F64 => {
+                Self::build(device, config, callback, s, |c, o: &mut [f64]| {
+                    c.write_converted(o, 0)
+                })
+            }
+            cpal::SampleFormat::I8 => {
+                Self::build(device, config, callback, s, |c, o: &mut [i8]| {
+                    c.write_converted(o, 8)
+                })
+            }
+            cpal::SampleFormat::U8 => {
+                Self::build(device, config, callback, s, |c, o: &mut [u8]| {
+                    c.write_converted(o, 8)
+                })
+            }
+            cpal::SampleFormat::I16 => {
+                Self::build(device, config, callback, s, |c, o: &mut [i16]| {
+                    c.write_converted(o, 16)
+                })
+            }
+            cpal::SampleFormat::U16 => {
+                Self::build(device, config, callback, s, |c, o: &mut [u16]| {
+                    c.write_converted(o, 16)
+                })
+            }
+            cpal::SampleFormat::I32 => {
+                Self::build(device, config, callback, s, |c, o: &mut [i32]| {
+                    c.write_converted(o, 0)
+                })
+            }
+            cpal::SampleFormat::U32 => {
+                Self::build(device, config, callback, s, |c, o: &mut [u32]| {
+                    c.write_converted(o, 0)
+                })
+            }
+            cpal::SampleFormat::I64 => {
+                Self::build(device, config, callback, s, |c, o: &mut [i64]| {
+                    c.write_converted(o, 0)
+                })
+            }
+            cpal::SampleFormat::U64 => {
+                Self::build(device, config, callback, s, |c, o: &mut [u64]| {
+                    c.write_converted(o, 0)
+                })
+            }
+            format => Err(Error::OutputDeviceError(
+                format!("unsupported sample format: {}", format).into(),
+            )),
+        }
+    }
+
+    /// Build an output stream with the given sample type, using the given writer function to
+    /// fill the stream's output buffers.
+    fn build<T>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        callback: Arc<Mutex<StreamCallback>>,
+        stream_send: Sender<StreamMsg>,
+        write: fn(&mut StreamCallback, &mut [T]),
+    ) -> Result<cpal::Stream, Error>
+    where
+        T: cpal::SizedSample + 'static,
+    {
         let stream = device.build_output_stream(
-            &config,
-            move |output, _| {
-                callback.write_samples(output);
+            config,
+            move |output: &mut [T], info: &cpal::OutputCallbackInfo| {
+                // The callback only is locked by the audio threads of the streams which share it:
+                // after a reopen, a failed stream's thread may still run while the new one starts.
+                // Output silence then instead of blocking.
+                if let Ok(mut callback) = callback.try_lock() {
+                    callback.update_output_latency(output.len(), info);
+                    write(&mut callback, output);
+                } else {
+                    output.iter_mut().for_each(|s| *s = T::EQUILIBRIUM);
+                }
             },
-            |err| {
+            move |err| {
                 log::error!("audio output error: {}", err);
+                let _ = stream_send.try_send(StreamMsg::DeviceError(err));
             },
             None,
         )?;
+        Ok(stream)
+    }
 
-        Ok(Self {
-            _device: device,
-            stream,
-        })
+    fn reopen(&mut self) -> Result<(), Error> {
+        let host = CpalOutput::host(self.device.host)?;
+        let device = CpalOutput::find_device(&host, &CpalDeviceId::Default)?;
+        let device_name = device.name().unwrap_or_default();
+        log::info!("reopening audio device: {}", device_name);
+
+        // The new device may use another native sample format
+        let config = &self.device.config;
+        let sample_format =
+            CpalOutput::supported_output_config(&device, config.channels, config.sample_rate)?
+                .ok_or_else(|| {
+                    Error::OutputDeviceError(
+                        format!(
+                            "audio device does not support {} channels at {} Hz",
+                            config.channels, config.sample_rate.0
+                        )
+                        .into(),
+                    )
+                })?
+                .sample_format();
+
+        // The shared callback keeps the running source, playback position and volume.
+        let stream = Self::open(
+            &device,
+            config,
+            sample_format,
+            Arc::clone(&self.callback),
+            self.stream_send.clone(),
+        )?;
+        if self.playing {
+            stream.play()?;
+        }
+        self.stream = Some(stream);
+        self.device.sample_format = sample_format;
+        self.device
+            .send_event(CpalDeviceEvent::Recovered { device_name });
+        Ok(())
+    }
+
+    fn next_act(&self) -> Act<Self> {
+        if self.stream.is_none() && self.device.auto_recover {
+            Act::WaitOr {
+                timeout: RECOVERY_RETRY_INTERVAL,
+                timeout_msg: StreamMsg::Reopen,
+            }
+        } else {
+            Act::Continue
+        }
     }
 }
 
@@ -257,21 +824,54 @@ impl Actor for Stream {
         match msg {
             StreamMsg::Pause => {
                 log::debug!("pausing audio output stream");
-                if let Err(err) = self.stream.pause() {
-                    log::error!("failed to stop stream: {}", err);
+                self.playing = false;
+                if let Some(stream) = &self.stream {
+                    if let Err(err) = stream.pause() {
+                        log::error!("failed to stop stream: {}", err);
+                    }
                 }
-                Ok(Act::Continue)
+                Ok(self.next_act())
             }
             StreamMsg::Resume => {
                 log::debug!("resuming audio output stream");
-                if let Err(err) = self.stream.play() {
-                    log::error!("failed to start stream: {}", err);
+                self.playing = true;
+                if let Some(stream) = &self.stream {
+                    if let Err(err) = stream.play() {
+                        log::error!("failed to start stream: {}", err);
+                    }
+                }
+                Ok(self.next_act())
+            }
+            StreamMsg::DeviceError(err) => {
+                match err {
+                    cpal::StreamError::DeviceNotAvailable => {
+                        self.device.send_event(CpalDeviceEvent::Disconnected);
+                        if self.device.auto_recover && self.stream.is_some() {
+                            // drop the dead stream and try reopening a device immediately
+                            self.stream = None;
+                            return self.handle(StreamMsg::Reopen);
+                        }
+                    }
+                    cpal::StreamError::BackendSpecific { err } => {
+                        self.device
+                            .send_event(CpalDeviceEvent::Error(err.description));
+                    }
                 }
-                Ok(Act::Continue)
+                Ok(self.next_act())
+            }
+            StreamMsg::Reopen => {
+                if self.stream.is_none() {
+                    if let Err(err) = self.reopen() {
+                        log::warn!("failed to reopen audio device: {}", err);
+                    }
+                }
+                Ok(self.next_act())
             }
             StreamMsg::Close => {
                 log::debug!("closing audio output stream");
-                let _ = self.stream.pause();
+                if let Some(stream) = &self.stream {
+                    let _ = stream.pause();
+                }
                 Ok(Act::Shutdown)
             }
         }
@@ -283,6 +883,8 @@ impl Actor for Stream {
 enum StreamMsg {
     Pause,
     Resume,
+    DeviceError(cpal::StreamError),
+    Reopen,
     Close,
 }
 
@@ -299,16 +901,80 @@ enum CallbackState {
 }
 
 struct StreamCallback {
-    _stream_send: Sender<StreamMsg>,
     callback_recv: Receiver<CallbackMsg>,
     source: Box<dyn Source>,
     playback_pos: Arc<AtomicU64>,
     playback_pos_instant: Instant,
+    output_latency: Arc<AtomicU64>,
+    channel_count: usize,
+    sample_rate: u32,
     state: CallbackState,
     volume: f32,
+    convert_buffer: Vec<f32>,
+    dither: bool,
+    dither_state: u32,
 }
 
 impl StreamCallback {
+    fn update_output_latency(&mut self, output_samples: usize, info: &cpal::OutputCallbackInfo) {
+        // Measure the output latency: the device latency, as reported by the output
+        // timestamps, plus the duration of the buffer we are going to write.
+        let timestamp = info.timestamp();
+        let device_latency = timestamp
+            .playback
+            .duration_since(&timestamp.callback)
+            .unwrap_or_default();
+        let buffer_latency = Duration::from_secs_f64(
+            (output_samples / self.channel_count) as f64 / self.sample_rate as f64,
+        );
+        self.output_latency.store(
+            (device_latency + buffer_latency).as_nanos() as u64,
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Write into a non f32 output buffer. `dither_bits` is the bit depth of low resolution
+    /// formats, which get quantized and optionally dithered here, or 0 for all other formats.
+    fn write_converted<T>(&mut self, output: &mut [T], dither_bits: u32)
+    where
+        T: cpal::SizedSample + cpal::FromSample<f32>,
+    {
+        // Render into our temp buffer and convert it to the output's sample format.
+        let mut convert_buffer = std::mem::take(&mut self.convert_buffer);
+        for chunk in output.chunks_mut(convert_buffer.len()) {
+            let buffer = &mut convert_buffer[..chunk.len()];
+            self.write_samples(buffer);
+            if dither_bits > 0 {
+                // Quantize low bit depth formats here, rounding to the nearest value instead
+                // of truncating, and optionally apply TPDF dither with a 1 LSB peak.
+                let scale = (1_u32 << (dither_bits - 1)) as f32;
+                let max = (scale - 1.0) / scale;
+                for (o, i) in chunk.iter_mut().zip(buffer.iter()) {
+                    let mut sample = *i * scale;
+                    if self.dither {
+                        sample += self.next_random() - self.next_random();
+                    }
+                    *o = T::from_sample((sample.round() / scale).clamp(-1.0, max));
+                }
+            } else {
+                for (o, i) in chunk.iter_mut().zip(buffer.iter()) {
+                    *o = T::from_sample(i.clamp(-1.0, 1.0));
+                }
+            }
+        }
+        self.convert_buffer = convert_buffer;
+    }
+
+    /// Uniform random value in range [0, 1) from a xorshift32 generator.
+    fn next_random(&mut self) -> f32 {
+        let mut x = self.dither_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.dither_state = x;
+        (x >> 8) as f32 / (1_u32 << 24) as f32
+    }
+
     fn write_samples(&mut self, output: &mut [f32]) {
         // Process any pending data messages.
         while let Ok(msg) = self.callback_recv.try_recv() {
@@ -367,6 +1033,12 @@ impl From<cpal::DefaultStreamConfigError> for Error {
     }
 }
 
+impl From<cpal::DevicesError> for Error {
+    fn from(err: cpal::DevicesError) -> Error {
+        Error::OutputDeviceError(Box::new(err))
+    }
+}
+
 impl From<cpal::SupportedStreamConfigsError> for Error {
     fn from(err: cpal::SupportedStreamConfigsError) -> Error {
         Error::OutputDeviceError(Box::new(err))
@@ -390,3 +1062,63 @@ impl From<cpal::PauseStreamError> for Error {
         Error::OutputDeviceError(Box::new(err))
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FilePlaybackOptions, PreloadedFileSource};
+
+    fn new_callback(buffer: Vec<f32>, dither: bool) -> StreamCallback {
+        let source = PreloadedFileSource::with_buffer(
+            Arc::new(buffer),
+            44100,
+            1,
+            "test_buffer",
+            None,
+            FilePlaybackOptions::default(),
+            44100,
+        )
+        .unwrap();
+        let (_callback_send, callback_recv) = bounded(1);
+        StreamCallback {
+            callback_recv,
+            source: Box::new(source),
+            playback_pos: Arc::new(AtomicU64::new(0)),
+            playback_pos_instant: Instant::now(),
+            output_latency: Arc::new(AtomicU64::new(0)),
+            channel_count: 1,
+            sample_rate: 44100,
+            state: CallbackState::Playing,
+            volume: 1.0,
+            convert_buffer: vec![0.0; 4],
+            dither,
+            dither_state: 0x9E37_79B9,
+        }
+    }
+
+    #[test]
+    fn convert_samples() {
+        let buffer = vec![0.0, 0.5, -0.5, 1.0, -1.0, 2.0, 0.25];
+
+        // converts in chunks of the convert buffer size and clamps
+        let mut callback = new_callback(buffer.clone(), false);
+        let mut output = [0_i16; 10];
+        callback.write_converted(&mut output, 16);
+        assert_eq!(
+            output,
+            [0, 16384, -16384, 32767, -32768, 32767, 8192, 0, 0, 0]
+        );
+
+        // dither adds at most 1 LSB noise
+        let mut callback = new_callback(buffer, true);
+        let mut dithered = [0_i16; 10];
+        callback.write_converted(&mut dithered, 16);
+        assert!(output
+            .iter()
+            .zip(dithered.iter())
+            .all(|(a, b)| (*a as i32 - *b as i32).abs() <= 1));
+        assert!(output != dithered);
+    }
+}
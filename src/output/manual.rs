@@ -0,0 +1,189 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    output::{OutputDevice, OutputSink},
+    source::{empty::EmptySource, Source, SourceTime},
+};
+
+// -------------------------------------------------------------------------------------------------
+
+enum RenderState {
+    Playing,
+    Paused,
+}
+
+/// Shared render state of a [`ManualOutput`] and its [`ManualSink`]s.
+struct ManualOutputState {
+    source: Box<dyn Source>,
+    state: RenderState,
+    volume: f32,
+    playback_pos_instant: Instant,
+}
+
+impl ManualOutputState {
+    fn render(&mut self, output: &mut [f32], channel_count: usize, playback_pos: &AtomicU64) {
+        let written = if matches!(self.state, RenderState::Playing) {
+            // Write out as many samples as possible from the audio source to the output buffer.
+            let time = SourceTime {
+                pos_in_frames: playback_pos.load(Ordering::Relaxed) / channel_count as u64,
+                pos_instant: self.playback_pos_instant,
+            };
+            let written = self.source.write(output, &time);
+
+            // Apply the global volume level.
+            output[..written].iter_mut().for_each(|s| *s *= self.volume);
+
+            // Advance playback pos
+            playback_pos.fetch_add(output.len() as u64, Ordering::Relaxed);
+
+            // return modified samples
+            written
+        } else {
+            0
+        };
+
+        // Mute any remaining samples.
+        output[written..].iter_mut().for_each(|s| *s = 0.0);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// OutputSink for the manually pulled output.
+#[derive(Clone)]
+pub struct ManualSink {
+    channel_count: usize,
+    sample_rate: u32,
+    volume: f32,
+    playback_pos: Arc<AtomicU64>,
+    state: Arc<Mutex<ManualOutputState>>,
+}
+
+impl ManualSink {
+    fn with_state<R>(&self, func: impl FnOnce(&mut ManualOutputState) -> R) -> R {
+        let mut state = self.state.lock().expect("manual output state got poisoned");
+        func(&mut state)
+    }
+}
+
+impl OutputSink for ManualSink {
+    fn suspended(&self) -> bool {
+        false
+    }
+
+    fn channel_count(&self) -> usize {
+        self.channel_count
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn sample_position(&self) -> u64 {
+        self.playback_pos.load(Ordering::Relaxed)
+    }
+    fn output_latency(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn volume(&self) -> f32 {
+        self.volume
+    }
+    fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+        self.with_state(|state| state.volume = volume);
+    }
+
+    fn play(&mut self, source: Box<dyn Source>) {
+        // ensure source has our sample rate and channel layout
+        assert_eq!(source.channel_count(), self.channel_count());
+        assert_eq!(source.sample_rate(), self.sample_rate());
+        self.with_state(|state| state.source = source);
+    }
+
+    fn pause(&mut self) {
+        self.with_state(|state| state.state = RenderState::Paused);
+    }
+
+    fn resume(&mut self) {
+        self.with_state(|state| state.state = RenderState::Playing);
+    }
+
+    fn stop(&mut self) {
+        self.with_state(|state| state.source = Box::new(EmptySource));
+    }
+
+    fn close(&mut self) {
+        self.stop();
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Audio output without an audio device, where the caller pulls the output's samples manually.
+///
+/// No samples are rendered unless [`render`](Self::render) or [`render_into`](Self::render_into)
+/// gets called, so this output can be used to test a [`Player`](crate::Player)'s exact sample
+/// output without timing races, or to embed the player's output into some other audio engine.
+///
+/// Like a realtime audio device callback, a paused sink renders silence and doesn't move the
+/// output's sample position.
+pub struct ManualOutput {
+    sink: ManualSink,
+}
+
+impl ManualOutput {
+    /// Create a new manual output with the given signal specs.
+    pub fn new(channel_count: usize, sample_rate: u32) -> Self {
+        assert!(channel_count > 0, "invalid channel count");
+        assert!(sample_rate > 0, "invalid sample rate");
+        let state = Arc::new(Mutex::new(ManualOutputState {
+            source: Box::new(EmptySource),
+            state: RenderState::Paused,
+            volume: 1.0,
+            playback_pos_instant: Instant::now(),
+        }));
+        let sink = ManualSink {
+            channel_count,
+            sample_rate,
+            volume: 1.0,
+            playback_pos: Arc::new(AtomicU64::new(0)),
+            state,
+        };
+        Self { sink }
+    }
+
+    /// Render the given amount of sample frames and return them as interleaved buffer.
+    pub fn render(&self, frame_count: usize) -> Vec<f32> {
+        let mut output = vec![0.0; frame_count * self.sink.channel_count];
+        self.render_into(&mut output);
+        output
+    }
+
+    /// Render into the given interleaved output buffer. The buffer's length must be a multiple
+    /// of the output's channel count.
+    pub fn render_into(&self, output: &mut [f32]) {
+        let channel_count = self.sink.channel_count;
+        debug_assert!(
+            output.len().is_multiple_of(channel_count),
+            "output buffer size must be a multiple of the output channel count"
+        );
+        let playback_pos = &self.sink.playback_pos;
+        self.sink
+            .with_state(|state| state.render(output, channel_count, playback_pos));
+    }
+}
+
+impl OutputDevice for ManualOutput {
+    type Sink = ManualSink;
+
+    fn sink(&self) -> Self::Sink {
+        self.sink.clone()
+    }
+}
@@ -8,7 +8,7 @@ use std::{
         atomic::{AtomicU64, Ordering},
         Arc,
     },
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use crossbeam_channel::{bounded, Receiver, Sender};
@@ -107,6 +107,14 @@ impl OutputSink for SokolSink {
     fn sample_position(&self) -> u64 {
         self.playback_pos.load(Ordering::Relaxed)
     }
+    fn output_latency(&self) -> Duration {
+        assert!(
+            saudio::isvalid(),
+            "audio not yet initialized or already shut down"
+        );
+        // sokol does not report device latencies: use the buffer size only
+        Duration::from_secs_f64(saudio::buffer_frames() as f64 / saudio::sample_rate() as f64)
+    }
 
     fn volume(&self) -> f32 {
         self.volume
@@ -118,13 +126,13 @@ impl OutputSink for SokolSink {
             .unwrap();
     }
 
-    fn play(&mut self, source: impl Source) {
+    fn play(&mut self, source: Box<dyn Source>) {
         // ensure source has our sample rate and channel layout
         assert_eq!(source.channel_count(), self.channel_count());
         assert_eq!(source.sample_rate(), self.sample_rate());
         // send message to activate it in the writer
         self.callback_send
-            .send(CallbackMessage::PlaySource(Box::new(source)))
+            .send(CallbackMessage::PlaySource(source))
             .unwrap()
     }
 
@@ -0,0 +1,318 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::Error,
+    output::{OutputDevice, OutputSink},
+    source::{empty::EmptySource, Source, SourceTime},
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Number of sample frames which are rendered at once.
+const RENDER_BUFFER_FRAMES: usize = 1024;
+
+// -------------------------------------------------------------------------------------------------
+
+enum RenderState {
+    Playing,
+    Paused,
+}
+
+/// Shared render state of a [`WavOutput`] and its [`WavSink`]s.
+struct WavOutputState {
+    writer: Option<hound::WavWriter<BufWriter<File>>>,
+    source: Box<dyn Source>,
+    state: RenderState,
+    volume: f32,
+    playback_pos_instant: Instant,
+    render_buffer: Vec<f32>,
+}
+
+impl WavOutputState {
+    fn render(
+        &mut self,
+        frame_count: u64,
+        channel_count: usize,
+        playback_pos: &AtomicU64,
+    ) -> Result<u64, Error> {
+        // paused outputs don't render and don't move the playback position
+        if matches!(self.state, RenderState::Paused) {
+            return Ok(0);
+        }
+        let writer = self.writer.as_mut().ok_or_else(|| {
+            Error::OutputDeviceError("the wav output file is already closed".into())
+        })?;
+        let mut frames_rendered = 0;
+        while frames_rendered < frame_count {
+            let frames = (frame_count - frames_rendered).min(RENDER_BUFFER_FRAMES as u64) as usize;
+            let output = &mut self.render_buffer[..frames * channel_count];
+
+            // Write out as many samples as possible from the audio source to the output buffer.
+            let time = SourceTime {
+                pos_in_frames: playback_pos.load(Ordering::Relaxed) / channel_count as u64,
+                pos_instant: self.playback_pos_instant,
+            };
+            let written = self.source.write(output, &time);
+
+            // Apply the global volume level.
+            output[..written].iter_mut().for_each(|s| *s *= self.volume);
+            // Mute any remaining samples.
+            output[written..].iter_mut().for_each(|s| *s = 0.0);
+
+            // Write the rendered samples into the file
+            for sample in output.iter() {
+                writer.write_sample(*sample)?;
+            }
+
+            // Advance playback pos
+            playback_pos.fetch_add(output.len() as u64, Ordering::Relaxed);
+            frames_rendered += frames as u64;
+        }
+        Ok(frames_rendered)
+    }
+
+    fn finalize(&mut self) -> Result<(), Error> {
+        if let Some(writer) = self.writer.take() {
+            writer.finalize()?;
+        }
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// OutputSink for the offline WAV file output.
+#[derive(Clone)]
+pub struct WavSink {
+    channel_count: usize,
+    sample_rate: u32,
+    volume: f32,
+    playback_pos: Arc<AtomicU64>,
+    state: Arc<Mutex<WavOutputState>>,
+}
+
+impl WavSink {
+    fn with_state<R>(&self, func: impl FnOnce(&mut WavOutputState) -> R) -> R {
+        let mut state = self.state.lock().expect("wav output state got poisoned");
+        func(&mut state)
+    }
+}
+
+impl OutputSink for WavSink {
+    fn suspended(&self) -> bool {
+        false
+    }
+
+    fn channel_count(&self) -> usize {
+        self.channel_count
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn sample_position(&self) -> u64 {
+        self.playback_pos.load(Ordering::Relaxed)
+    }
+    fn output_latency(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn volume(&self) -> f32 {
+        self.volume
+    }
+    fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+        self.with_state(|state| state.volume = volume);
+    }
+
+    fn play(&mut self, source: Box<dyn Source>) {
+        // ensure source has our sample rate and channel layout
+        assert_eq!(source.channel_count(), self.channel_count());
+        assert_eq!(source.sample_rate(), self.sample_rate());
+        self.with_state(|state| state.source = source);
+    }
+
+    fn pause(&mut self) {
+        self.with_state(|state| state.state = RenderState::Paused);
+    }
+
+    fn resume(&mut self) {
+        self.with_state(|state| state.state = RenderState::Playing);
+    }
+
+    fn stop(&mut self) {
+        self.with_state(|state| state.source = Box::new(EmptySource));
+    }
+
+    fn close(&mut self) {
+        if let Err(err) = self.with_state(|state| state.finalize()) {
+            log::error!("failed to finalize wav output file: {}", err);
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Offline, non-realtime audio output, which renders the played source into a 32-bit float WAV
+/// file as fast as possible. Useful to bounce [`Player`](crate::Player) sessions on headless
+/// systems, where no audio device is available.
+///
+/// Rendering happens on demand only: call [`render`](Self::render) or
+/// [`render_duration`](Self::render_duration) from any thread to advance the output. Like with
+/// realtime outputs, a paused sink doesn't render anything and won't move the output's sample
+/// position.
+///
+/// The WAV file gets finalized when the sink is closed, e.g. when dropping the player, or when
+/// the last reference to the output's state is dropped.
+pub struct WavOutput {
+    sink: WavSink,
+}
+
+impl WavOutput {
+    /// Create a new WAV output which writes to the given file path with the given signal specs.
+    /// Existing files will be overwritten.
+    pub fn open(
+        file_path: impl AsRef<Path>,
+        channel_count: usize,
+        sample_rate: u32,
+    ) -> Result<Self, Error> {
+        if channel_count == 0 || channel_count > u16::MAX as usize {
+            return Err(Error::ParameterError(format!(
+                "wav output 'channel_count' value is '{}'",
+                channel_count
+            )));
+        }
+        if sample_rate == 0 {
+            return Err(Error::ParameterError(
+                "wav output 'sample_rate' value is '0'".to_string(),
+            ));
+        }
+        let spec = hound::WavSpec {
+            channels: channel_count as u16,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let writer = hound::WavWriter::create(file_path, spec)?;
+
+        let state = Arc::new(Mutex::new(WavOutputState {
+            writer: Some(writer),
+            source: Box::new(EmptySource),
+            state: RenderState::Paused,
+            volume: 1.0,
+            playback_pos_instant: Instant::now(),
+            render_buffer: vec![0.0; RENDER_BUFFER_FRAMES * channel_count],
+        }));
+        let sink = WavSink {
+            channel_count,
+            sample_rate,
+            volume: 1.0,
+            playback_pos: Arc::new(AtomicU64::new(0)),
+            state,
+        };
+
+        Ok(Self { sink })
+    }
+
+    /// Render the given amount of sample frames from the played source into the file.
+    /// Returns the number of rendered frames, which is 0 when the sink is paused.
+    pub fn render(&self, frame_count: u64) -> Result<u64, Error> {
+        let channel_count = self.sink.channel_count;
+        let playback_pos = &self.sink.playback_pos;
+        self.sink
+            .with_state(|state| state.render(frame_count, channel_count, playback_pos))
+    }
+
+    /// Render the given duration from the played source into the file.
+    /// Returns the number of rendered frames, which is 0 when the sink is paused.
+    pub fn render_duration(&self, duration: Duration) -> Result<u64, Error> {
+        let frame_count = (duration.as_secs_f64() * self.sink.sample_rate as f64).round() as u64;
+        self.render(frame_count)
+    }
+}
+
+impl OutputDevice for WavOutput {
+    type Sink = WavSink;
+
+    fn sink(&self) -> Self::Sink {
+        self.sink.clone()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl From<hound::Error> for Error {
+    fn from(err: hound::Error) -> Error {
+        match err {
+            hound::Error::IoError(err) => Error::IoError(err),
+            err => Error::OutputDeviceError(Box::new(err)),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{utils::unique_usize_id, FilePlaybackOptions, Player, PreloadedFileSource};
+
+    #[test]
+    fn render() {
+        let file_name = format!(
+            "phonic_wav_output_test_{}_{}.wav",
+            std::process::id(),
+            unique_usize_id()
+        );
+        let file_path = std::env::temp_dir().join(file_name);
+        let output = WavOutput::open(&file_path, 2, 44100).unwrap();
+
+        let mut player = Player::new(output.sink(), None);
+        // schedule a mono DC buffer at frame 100
+        let source = PreloadedFileSource::with_buffer(
+            Arc::new(vec![0.5; 44100]),
+            44100,
+            1,
+            "dc_buffer",
+            None,
+            FilePlaybackOptions::default(),
+            44100,
+        )
+        .unwrap();
+        player.play_file_source(source, Some(100)).unwrap();
+
+        // paused outputs don't render
+        player.stop();
+        assert_eq!(output.render(1000).unwrap(), 0);
+        assert_eq!(player.output_sample_frame_position(), 0);
+
+        player.start();
+        assert_eq!(output.render(1000).unwrap(), 1000);
+        assert_eq!(player.output_sample_frame_position(), 1000);
+
+        // closes and finalizes the file
+        drop(player);
+
+        let mut reader = hound::WavReader::open(&file_path).unwrap();
+        assert_eq!(reader.spec().channels, 2);
+        assert_eq!(reader.duration(), 1000);
+        let samples = reader
+            .samples::<f32>()
+            .map(|s| s.unwrap())
+            .collect::<Vec<_>>();
+        assert!(samples[..200].iter().all(|s| *s == 0.0));
+        assert!(samples[200..].iter().all(|s| *s == 0.5));
+
+        let _ = std::fs::remove_file(file_path);
+    }
+}
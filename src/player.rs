@@ -1,6 +1,8 @@
 use core::time;
 use std::{
     any::Any,
+    collections::HashMap,
+    ops::Range,
     sync::{
         atomic::{self, AtomicBool},
         Arc,
@@ -16,21 +18,33 @@ use dashmap::DashMap;
 
 use crate::{
     error::Error,
-    output::{DefaultOutputSink, OutputSink},
+    output::OutputSink,
     source::{
+        bus::{BusGain, BusSource},
         converted::ConvertedSource,
-        file::{FilePlaybackMessage, FileSource},
-        mixed::{MixedSource, MixedSourceMsg},
+        file::{
+            playlist::PlaylistWorkerMessage, probe::FileMetadata, FileMarker, FilePlaybackMessage,
+            FileSource,
+        },
+        mixed::MixedSourceMsg,
+        panned::{PannedSource, PannedSourceMessage},
         resampled::ResamplingQuality,
         synth::{SynthPlaybackMessage, SynthSource},
     },
+    utils::{automation::VolumeEnvelope, fader::FadeCurve, unique_usize_id},
 };
 
+#[cfg(feature = "hound")]
+use crate::source::recorder::{RecorderMsg, RecorderSource, WavRecorder};
+
 // -------------------------------------------------------------------------------------------------
 
 /// A unique ID for a newly created File or Synth Sources.
 pub type PlaybackId = usize;
 
+/// A unique ID for mixer buses, created via [`Player::add_bus`].
+pub type BusId = usize;
+
 // -------------------------------------------------------------------------------------------------
 
 /// Custom context type for playback status events.
@@ -58,6 +72,61 @@ pub enum PlaybackStatusEvent {
         /// true when the source finished playing (e.g. reaching EOF), false when manually stopped
         exhausted: bool,
     },
+    Paused {
+        /// Unique id to resolve played back sources
+        id: PlaybackId,
+        /// the file path for file based sources, else a name to somewhat identify the source
+        path: Arc<String>,
+        /// Custom, optional context, passed along when starting playback.
+        context: Option<PlaybackStatusContext>,
+    },
+    Resumed {
+        /// Unique id to resolve played back sources
+        id: PlaybackId,
+        /// the file path for file based sources, else a name to somewhat identify the source
+        path: Arc<String>,
+        /// Custom, optional context, passed along when starting playback.
+        context: Option<PlaybackStatusContext>,
+    },
+    /// Playback of a file source crossed a marker, e.g. a WAV cue point or a marker which got
+    /// added via [`Player::add_source_marker`].
+    MarkerReached {
+        /// Unique id to resolve played back sources
+        id: PlaybackId,
+        /// the file path for file based sources, else a name to somewhat identify the source
+        path: Arc<String>,
+        /// Custom, optional context, passed along when starting playback.
+        context: Option<PlaybackStatusContext>,
+        /// Id of the reached marker.
+        marker_id: u32,
+        /// Label of the reached marker. Empty when the marker has no label.
+        label: Arc<String>,
+    },
+    /// Tags of a streamed file source changed while playing, e.g. in chained Ogg streams.
+    /// Sent when the decoder reaches the new tags, which may be a bit ahead of the playback
+    /// position. Use [`probe_file`](crate::probe_file) to read the initial tags of a file.
+    MetadataChanged {
+        /// Unique id to resolve played back sources
+        id: PlaybackId,
+        /// the file path for file based sources, else a name to somewhat identify the source
+        path: Arc<String>,
+        /// Custom, optional context, passed along when starting playback.
+        context: Option<PlaybackStatusContext>,
+        /// The file's new tags.
+        metadata: FileMetadata,
+    },
+    /// A playlist source started playing a new track, either because the previous track ended
+    /// or after jumping to it. Also sent for the playlist's first track.
+    PlaylistTrackChanged {
+        /// Unique id to resolve played back sources
+        id: PlaybackId,
+        /// the file path of the new track
+        path: Arc<String>,
+        /// Custom, optional context, passed along when starting playback.
+        context: Option<PlaybackStatusContext>,
+        /// Index of the new track in the playlist.
+        index: usize,
+    },
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -80,6 +149,91 @@ impl PlaybackMessageSender {
                 .map_err(|_err| Error::SendError),
         }
     }
+
+    pub fn send_pause(&self) -> Result<(), Error> {
+        match self {
+            PlaybackMessageSender::File(sender) => sender
+                .push(FilePlaybackMessage::Pause)
+                .map_err(|_err| Error::SendError),
+            PlaybackMessageSender::Synth(sender) => sender
+                .push(SynthPlaybackMessage::Pause)
+                .map_err(|_err| Error::SendError),
+        }
+    }
+
+    pub fn send_resume(&self) -> Result<(), Error> {
+        match self {
+            PlaybackMessageSender::File(sender) => sender
+                .push(FilePlaybackMessage::Resume)
+                .map_err(|_err| Error::SendError),
+            PlaybackMessageSender::Synth(sender) => sender
+                .push(SynthPlaybackMessage::Resume)
+                .map_err(|_err| Error::SendError),
+        }
+    }
+
+    /// Seek file sources. Synth sources can't be seeked, so this does nothing for synths.
+    pub fn send_seek(&self, position: Duration, crossfade: Option<Duration>) -> Result<(), Error> {
+        match self {
+            PlaybackMessageSender::File(sender) => {
+                let message = match crossfade {
+                    Some(crossfade) if !crossfade.is_zero() => {
+                        FilePlaybackMessage::SeekWithCrossfade {
+                            position,
+                            crossfade,
+                        }
+                    }
+                    _ => FilePlaybackMessage::Seek(position),
+                };
+                sender.push(message).map_err(|_err| Error::SendError)
+            }
+            PlaybackMessageSender::Synth(_) => Ok(()),
+        }
+    }
+
+    pub fn send_volume(&self, volume: f32) -> Result<(), Error> {
+        match self {
+            PlaybackMessageSender::File(sender) => sender
+                .push(FilePlaybackMessage::SetVolume(volume))
+                .map_err(|_err| Error::SendError),
+            PlaybackMessageSender::Synth(sender) => sender
+                .push(SynthPlaybackMessage::SetVolume(volume))
+                .map_err(|_err| Error::SendError),
+        }
+    }
+
+    pub fn send_fade_volume(&self, volume: f32, duration: Duration) -> Result<(), Error> {
+        match self {
+            PlaybackMessageSender::File(sender) => sender
+                .push(FilePlaybackMessage::FadeVolume { volume, duration })
+                .map_err(|_err| Error::SendError),
+            PlaybackMessageSender::Synth(sender) => sender
+                .push(SynthPlaybackMessage::FadeVolume { volume, duration })
+                .map_err(|_err| Error::SendError),
+        }
+    }
+}
+
+/// A playing source in the player, with the bus it's playing on.
+#[derive(Clone)]
+struct PlayingSource {
+    message_sender: PlaybackMessageSender,
+    panning_queue: Arc<ArrayQueue<PannedSourceMessage>>,
+    playlist_sender: Option<Sender<PlaylistWorkerMessage>>,
+    bus_id: BusId,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Player internal state of a mixer bus.
+struct Bus {
+    name: String,
+    parent: Option<BusId>,
+    event_queue: Arc<ArrayQueue<MixedSourceMsg>>,
+    gain: BusGain,
+    volume: f32,
+    muted: bool,
+    solo: bool,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -87,26 +241,42 @@ impl PlaybackMessageSender {
 /// Playback controller, which drives an [`OutputSink`] and runs a [`MixedSource`] which
 /// can play an unlimited number of [`FileSource`] or [`SynthSource`] at the same time.
 ///
+/// The player can drive any output sink: usually the sink of a realtime audio device such as
+/// the [`DefaultOutputDevice`](crate::DefaultOutputDevice), but also offline sinks, which e.g.
+/// render the player's output into a file.
+///
 /// Playback status of all sources can be tracked via an optional event channel.
 /// New sources can be added any time, and can be stopped and seeked (seeking works for file
 /// based sources only).
 ///
+/// Sources can be routed into mixer buses, e.g. to group music, sound effects and voices.
+/// Buses are created via [`Player::add_bus`] and can be nested. Each bus has its own volume,
+/// mute and solo state, and all sources of a bus can be stopped at once. Sources play on the
+/// [`Player::MAIN_BUS`] by default.
+///
 /// NB: For playback of [`SynthSource`]s, the `dasp-synth` feature needs to be enabled.
 pub struct Player {
-    sink: DefaultOutputSink,
-    playing_sources: Arc<DashMap<PlaybackId, PlaybackMessageSender>>,
+    sink: Box<dyn OutputSink>,
+    playing_sources: Arc<DashMap<PlaybackId, PlayingSource>>,
     playback_status_sender: Sender<PlaybackStatusEvent>,
     collector_handle: Handle,
     collector_running: Arc<AtomicBool>,
-    mixer_event_queue: Arc<ArrayQueue<MixedSourceMsg>>,
+    buses: HashMap<BusId, Bus>,
+    #[cfg(feature = "hound")]
+    recorder_event_queue: Arc<ArrayQueue<RecorderMsg>>,
+    #[cfg(feature = "hound")]
+    recorder: Option<WavRecorder>,
 }
 
 impl Player {
-    /// Create a new Player for the given DefaultOutputSink.
+    /// Id of the player's main bus, which mixes down all other buses and sources.
+    pub const MAIN_BUS: BusId = 0;
+
+    /// Create a new Player for the given output sink.
     /// Param `playback_status_sender` is an optional channel which can be used to receive
     /// playback status events for the currently playing sources.
     pub fn new(
-        sink: DefaultOutputSink,
+        sink: impl OutputSink + 'static,
         playback_status_sender: Option<Sender<PlaybackStatusEvent>>,
     ) -> Self {
         // Create a proxy for the playback status channel, so we can trap stop messages
@@ -120,11 +290,36 @@ impl Player {
         let collector_running = Arc::new(AtomicBool::new(true));
         Self::handle_drop_collects(collector, collector_running.clone());
 
-        // Create a mixer source, add it to the audio sink and start running
-        let mixer_source = MixedSource::new(sink.channel_count(), sink.sample_rate());
-        let mixer_event_queue = mixer_source.event_queue();
-        let mut sink = sink;
-        sink.play(mixer_source);
+        // Create the main bus' mixer source, add it to the audio sink and start running
+        let main_bus_gain = BusGain::new(1.0);
+        let mixer_source = BusSource::new(
+            sink.channel_count(),
+            sink.sample_rate(),
+            main_bus_gain.clone(),
+        );
+        let mut buses = HashMap::new();
+        buses.insert(
+            Self::MAIN_BUS,
+            Bus {
+                name: "main".to_string(),
+                parent: None,
+                event_queue: mixer_source.event_queue(),
+                gain: main_bus_gain,
+                volume: 1.0,
+                muted: false,
+                solo: false,
+            },
+        );
+        let mut sink: Box<dyn OutputSink> = Box::new(sink);
+        // Tap the mixer's output for recordings, when recording is enabled
+        #[cfg(feature = "hound")]
+        let recorder_source = RecorderSource::new(mixer_source);
+        #[cfg(feature = "hound")]
+        let recorder_event_queue = recorder_source.event_queue();
+        #[cfg(feature = "hound")]
+        sink.play(Box::new(recorder_source));
+        #[cfg(not(feature = "hound"))]
+        sink.play(Box::new(mixer_source));
         sink.resume();
 
         Self {
@@ -133,7 +328,11 @@ impl Player {
             playback_status_sender: playback_status_sender_proxy,
             collector_handle,
             collector_running,
-            mixer_event_queue,
+            buses,
+            #[cfg(feature = "hound")]
+            recorder_event_queue,
+            #[cfg(feature = "hound")]
+            recorder: None,
         }
     }
 
@@ -158,6 +357,11 @@ impl Player {
     pub fn output_sample_frame_position(&self) -> u64 {
         self.output_sample_position() / self.output_channel_count() as u64
     }
+    /// Our audio device's actual output latency: the time it takes until samples which get
+    /// written now are audible. Use this to compensate sequencer scheduling or GUI playheads.
+    pub fn output_latency(&self) -> Duration {
+        self.sink.output_latency()
+    }
 
     /// Get a copy of our playback status sender channel.
     /// Should be used by custom audio sources only.
@@ -191,6 +395,19 @@ impl Player {
         start_time: Option<u64>,
         context: Option<PlaybackStatusContext>,
     ) -> Result<PlaybackId, Error> {
+        self.play_file_source_on_bus(file_source, Self::MAIN_BUS, start_time, context)
+    }
+    /// Play a self created or cloned file source on the given mixer bus, with the given
+    /// playback status context.
+    pub fn play_file_source_on_bus<Source: FileSource>(
+        &mut self,
+        file_source: Source,
+        bus_id: BusId,
+        start_time: Option<u64>,
+        context: Option<PlaybackStatusContext>,
+    ) -> Result<PlaybackId, Error> {
+        // make sure the bus exists
+        let bus_event_queue = self.bus(bus_id)?.event_queue.clone();
         // make sure the source has a valid playback status channel
         let mut file_source = file_source;
         if file_source.playback_status_sender().is_none() {
@@ -202,8 +419,6 @@ impl Player {
         let playback_id = file_source.playback_id();
         let playback_message_queue =
             PlaybackMessageSender::File(file_source.playback_message_queue());
-        self.playing_sources
-            .insert(playback_id, playback_message_queue.clone());
         // convert file to mixer's rate and channel layout and apply optional pitch
         let converted_source = ConvertedSource::new(
             file_source,
@@ -211,13 +426,23 @@ impl Player {
             self.sink.sample_rate(),
             ResamplingQuality::Default,
         );
-        // play the source by adding it to the mixer
-        if self
-            .mixer_event_queue
+        // apply panning
+        let panned_source = PannedSource::new(converted_source, 0.0);
+        self.playing_sources.insert(
+            playback_id,
+            PlayingSource {
+                message_sender: playback_message_queue.clone(),
+                panning_queue: panned_source.message_queue(),
+                playlist_sender: None,
+                bus_id,
+            },
+        );
+        // play the source by adding it to the bus' mixer
+        if bus_event_queue
             .push(MixedSourceMsg::AddSource {
                 playback_id,
                 playback_message_queue,
-                source: Owned::new(&self.collector_handle, Box::new(converted_source)),
+                source: Owned::new(&self.collector_handle, Box::new(panned_source)),
                 sample_time: start_time.unwrap_or(0),
             })
             .is_err()
@@ -245,6 +470,19 @@ impl Player {
         start_time: Option<u64>,
         context: Option<PlaybackStatusContext>,
     ) -> Result<PlaybackId, Error> {
+        self.play_synth_source_on_bus(synth_source, Self::MAIN_BUS, start_time, context)
+    }
+    /// Play a self created synth source on the given mixer bus, with the given playback
+    /// options and playback status context.
+    pub fn play_synth_source_on_bus<S: SynthSource>(
+        &mut self,
+        synth_source: S,
+        bus_id: BusId,
+        start_time: Option<u64>,
+        context: Option<PlaybackStatusContext>,
+    ) -> Result<PlaybackId, Error> {
+        // make sure the bus exists
+        let bus_event_queue = self.bus(bus_id)?.event_queue.clone();
         // make sure the source has a valid playback status channel
         let mut synth_source = synth_source;
         if synth_source.playback_status_sender().is_none() {
@@ -255,8 +493,6 @@ impl Player {
         let playback_id = synth_source.playback_id();
         let playback_message_queue =
             PlaybackMessageSender::Synth(synth_source.playback_message_queue());
-        self.playing_sources
-            .insert(playback_id, playback_message_queue.clone());
         // convert file to mixer's rate and channel layout
         let converted = ConvertedSource::new(
             synth_source,
@@ -264,13 +500,23 @@ impl Player {
             self.sink.sample_rate(),
             ResamplingQuality::Default, // usually unused
         );
+        // apply panning
+        let panned = PannedSource::new(converted, 0.0);
+        self.playing_sources.insert(
+            playback_id,
+            PlayingSource {
+                message_sender: playback_message_queue.clone(),
+                panning_queue: panned.message_queue(),
+                playlist_sender: None,
+                bus_id,
+            },
+        );
         // play the source
-        if self
-            .mixer_event_queue
+        if bus_event_queue
             .push(MixedSourceMsg::AddSource {
                 playback_id,
                 playback_message_queue,
-                source: Owned::new(&self.collector_handle, Box::new(converted)),
+                source: Owned::new(&self.collector_handle, Box::new(panned)),
                 sample_time: start_time.unwrap_or(0),
             })
             .is_err()
@@ -289,8 +535,8 @@ impl Player {
         playback_id: PlaybackId,
         position: Duration,
     ) -> Result<(), Error> {
-        if let Some(msg_sender) = self.playing_sources.get(&playback_id) {
-            if let PlaybackMessageSender::File(queue) = msg_sender.value() {
+        if let Some(playing_source) = self.playing_sources.get(&playback_id) {
+            if let PlaybackMessageSender::File(queue) = &playing_source.message_sender {
                 if queue.push(FilePlaybackMessage::Seek(position)).is_err() {
                     log::warn!("failed to send seek command to file");
                     return Err(Error::SendError);
@@ -305,12 +551,429 @@ impl Player {
         Err(Error::MediaFileNotFound)
     }
 
+    /// Seek a playing file source at the given sample time in the output stream, so the seek
+    /// happens on an exact output frame, e.g. for beat-synced jumps. When a crossfade duration
+    /// is given, the output of the old and new position get crossfaded with the given duration,
+    /// which is limited to 100ms.
+    /// This is only supported for files and thus won't do anything for synths.
+    ///
+    /// Only preloaded file sources seek gap-free. Streamed file sources drop out when seeking:
+    /// they play silence until their decoder thread decoded the new position, so a crossfade
+    /// fades the old position into this silence and the new position starts a few ms late.
+    pub fn seek_source_at_sample_time(
+        &mut self,
+        playback_id: PlaybackId,
+        position: Duration,
+        seek_time: u64,
+        crossfade: Option<Duration>,
+    ) -> Result<(), Error> {
+        // check if the given playback id is still know (playing)
+        let (bus_id, is_file) = match self.playing_sources.get(&playback_id) {
+            Some(playing_source) => (
+                playing_source.bus_id,
+                matches!(
+                    playing_source.message_sender,
+                    PlaybackMessageSender::File(_)
+                ),
+            ),
+            None => {
+                log::warn!(
+                    "trying to seek source #{playback_id} which is not or no longer playing"
+                );
+                return Err(Error::MediaFileNotFound);
+            }
+        };
+        if !is_file {
+            log::warn!("trying to seek a synth source, which is not supported");
+            return Ok(());
+        }
+        // pass seek request to the bus' mixer (force push seek events!)
+        self.bus(bus_id)?
+            .event_queue
+            .force_push(MixedSourceMsg::SeekSource {
+                playback_id,
+                position,
+                crossfade,
+                sample_time: seek_time,
+            });
+        Ok(())
+    }
+
+    /// Crossfade from one playing file or synth source to another one with the given duration
+    /// and fade curve, starting immediately. See [`Self::crossfade_sources_at_sample_time`].
+    pub fn crossfade_sources(
+        &mut self,
+        from_playback_id: PlaybackId,
+        to_playback_id: PlaybackId,
+        duration: Duration,
+        curve: FadeCurve,
+    ) -> Result<(), Error> {
+        let length = (duration.as_secs_f64() * self.output_sample_rate() as f64).round() as u64;
+        self.schedule_crossfade(from_playback_id, to_playback_id, 0, length, curve)
+    }
+
+    /// Crossfade from one playing file or synth source to another one within the given range
+    /// of sample frame times in the output stream, e.g. for DJ-style transitions between
+    /// tracks. The `from` source fades out with the given fade curve and gets stopped when
+    /// the crossfade completed, while the `to` source fades in. The `to` source stays silent
+    /// until the crossfade starts, so it can be started, even scheduled, before the crossfade.
+    ///
+    /// Fades get applied per sample frame in the player's mixers, so they are sample accurate.
+    pub fn crossfade_sources_at_sample_time(
+        &mut self,
+        from_playback_id: PlaybackId,
+        to_playback_id: PlaybackId,
+        sample_range: Range<u64>,
+        curve: FadeCurve,
+    ) -> Result<(), Error> {
+        if sample_range.start > sample_range.end {
+            return Err(Error::ParameterError(format!(
+                "crossfade sample range '{sample_range:?}' is invalid"
+            )));
+        }
+        let length = sample_range.end - sample_range.start;
+        self.schedule_crossfade(
+            from_playback_id,
+            to_playback_id,
+            sample_range.start,
+            length,
+            curve,
+        )
+    }
+
+    fn schedule_crossfade(
+        &mut self,
+        from_playback_id: PlaybackId,
+        to_playback_id: PlaybackId,
+        sample_time: u64,
+        length: u64,
+        curve: FadeCurve,
+    ) -> Result<(), Error> {
+        if from_playback_id == to_playback_id {
+            return Err(Error::ParameterError(
+                "can't crossfade a source with itself".to_string(),
+            ));
+        }
+        // check if the given playback ids are still known (playing)
+        let mut bus_ids = [Self::MAIN_BUS; 2];
+        for (bus_id, playback_id) in bus_ids.iter_mut().zip([from_playback_id, to_playback_id]) {
+            match self.playing_sources.get(&playback_id) {
+                Some(playing_source) => *bus_id = playing_source.bus_id,
+                None => {
+                    log::warn!(
+                        "trying to crossfade source #{playback_id} which is not or no longer playing"
+                    );
+                    return Err(Error::MediaFileNotFound);
+                }
+            }
+        }
+        // pass fade requests to the bus' mixers (force push fade events!)
+        for ((bus_id, playback_id), fade_in) in bus_ids
+            .into_iter()
+            .zip([from_playback_id, to_playback_id])
+            .zip([false, true])
+        {
+            self.bus(bus_id)?
+                .event_queue
+                .force_push(MixedSourceMsg::FadeSource {
+                    playback_id,
+                    sample_time,
+                    length,
+                    curve,
+                    fade_in,
+                });
+        }
+        Ok(())
+    }
+
+    /// Smoothly change the volume of a playing file or synth source to the given linear
+    /// volume factor.
+    pub fn set_source_volume(&mut self, playback_id: PlaybackId, volume: f32) -> Result<(), Error> {
+        if volume < 0.0 || volume.is_nan() {
+            return Err(Error::ParameterError(format!(
+                "source 'volume' value is '{volume}'"
+            )));
+        }
+        if let Some(playing_source) = self.playing_sources.get(&playback_id) {
+            if playing_source.message_sender.send_volume(volume).is_err() {
+                log::warn!("failed to send volume command to source");
+                return Err(Error::SendError);
+            }
+            Ok(())
+        } else {
+            log::warn!("trying to change volume of source #{playback_id} which is not or no longer playing");
+            Err(Error::MediaFileNotFound)
+        }
+    }
+
+    /// Fade the volume of a playing file or synth source to the given linear volume factor.
+    /// The fade reaches the given volume after exactly the given duration, shaped by the
+    /// source's `fade_curve` option, and gets applied on top of the source's volume as set via
+    /// [`Self::set_source_volume`]. Fades are ignored when the source is fading out after
+    /// getting stopped.
+    pub fn fade_source_volume(
+        &mut self,
+        playback_id: PlaybackId,
+        volume: f32,
+        duration: Duration,
+    ) -> Result<(), Error> {
+        if volume < 0.0 || volume.is_nan() {
+            return Err(Error::ParameterError(format!(
+                "source fade 'volume' value is '{volume}'"
+            )));
+        }
+        if let Some(playing_source) = self.playing_sources.get(&playback_id) {
+            if playing_source
+                .message_sender
+                .send_fade_volume(volume, duration)
+                .is_err()
+            {
+                log::warn!("failed to send fade volume command to source");
+                return Err(Error::SendError);
+            }
+            Ok(())
+        } else {
+            log::warn!(
+                "trying to fade volume of source #{playback_id} which is not or no longer playing"
+            );
+            Err(Error::MediaFileNotFound)
+        }
+    }
+
+    /// Attach a breakpoint volume automation envelope to a playing file or synth source,
+    /// replacing any previously set envelope of the source. Envelope point times are absolute
+    /// output sample frame times, see [`Self::output_sample_frame_position`].
+    ///
+    /// The envelope gets applied sample accurately in the source's mixer, on top of the source's
+    /// volume and fades.
+    pub fn set_source_volume_envelope(
+        &mut self,
+        playback_id: PlaybackId,
+        envelope: VolumeEnvelope,
+    ) -> Result<(), Error> {
+        envelope.validate()?;
+        let envelope = Owned::new(&self.collector_handle, envelope);
+        self.send_source_volume_envelope(playback_id, Some(envelope))
+    }
+
+    /// Remove the volume automation envelope of a playing file or synth source, if any.
+    pub fn clear_source_volume_envelope(&mut self, playback_id: PlaybackId) -> Result<(), Error> {
+        self.send_source_volume_envelope(playback_id, None)
+    }
+
+    fn send_source_volume_envelope(
+        &self,
+        playback_id: PlaybackId,
+        envelope: Option<Owned<VolumeEnvelope>>,
+    ) -> Result<(), Error> {
+        // check if the given playback id is still known (playing)
+        let Some(bus_id) = self
+            .playing_sources
+            .get(&playback_id)
+            .map(|playing_source| playing_source.bus_id)
+        else {
+            log::warn!("trying to change envelope of source #{playback_id} which is not or no longer playing");
+            return Err(Error::MediaFileNotFound);
+        };
+        // pass envelope to the bus' mixer (force push envelope events!)
+        self.bus(bus_id)?
+            .event_queue
+            .force_push(MixedSourceMsg::SetVolumeEnvelope {
+                playback_id,
+                envelope,
+            });
+        Ok(())
+    }
+
+    /// Smoothly change the stereo panning of a playing file or synth source. Panning is in
+    /// range [-1, 1]: -1 is full left, 0 center and 1 full right. Panning only applies when the
+    /// output has two or more channels.
+    pub fn set_source_panning(
+        &mut self,
+        playback_id: PlaybackId,
+        panning: f32,
+    ) -> Result<(), Error> {
+        if !(-1.0..=1.0).contains(&panning) {
+            return Err(Error::ParameterError(format!(
+                "source 'panning' value is '{panning}'"
+            )));
+        }
+        if let Some(playing_source) = self.playing_sources.get(&playback_id) {
+            if playing_source
+                .panning_queue
+                .push(PannedSourceMessage::SetPanning(panning))
+                .is_err()
+            {
+                log::warn!("failed to send panning command to source");
+                return Err(Error::SendError);
+            }
+            Ok(())
+        } else {
+            log::warn!("trying to change panning of source #{playback_id} which is not or no longer playing");
+            Err(Error::MediaFileNotFound)
+        }
+    }
+
+    /// Change the playback speed of a playing file source. See [`FilePlaybackOptions::speed`](crate::FilePlaybackOptions::speed).
+    /// Speed changes get ramped to avoid zipper noise.
+    ///
+    /// This is only supported for files: synth sources generate their output at the player's
+    /// sample rate and can't be resampled, so changing their speed returns an
+    /// `Error::ParameterError`.
+    ///
+    /// When using [`ResamplingQuality::HighQuality`], the speed can be changed by a factor of 8
+    /// at most, relative to the speed the source got started with.
+    pub fn set_source_speed(&mut self, playback_id: PlaybackId, speed: f64) -> Result<(), Error> {
+        if speed <= 0.0 || speed.is_nan() || speed.is_infinite() {
+            return Err(Error::ParameterError(format!(
+                "source 'speed' value is '{speed}'"
+            )));
+        }
+        if let Some(playing_source) = self.playing_sources.get(&playback_id) {
+            if let PlaybackMessageSender::File(queue) = &playing_source.message_sender {
+                if queue.push(FilePlaybackMessage::SetSpeed(speed)).is_err() {
+                    log::warn!("failed to send speed command to file");
+                    return Err(Error::SendError);
+                }
+            } else {
+                return Err(Error::ParameterError(format!(
+                    "source #{playback_id} is a synth source, which can't change its speed"
+                )));
+            }
+            Ok(())
+        } else {
+            log::warn!(
+                "trying to change speed of source #{playback_id} which is not or no longer playing"
+            );
+            Err(Error::MediaFileNotFound)
+        }
+    }
+
+    /// Change the loop region of a playing file source in sample frames. None loops the whole
+    /// file. See [`FilePlaybackOptions::loop_start`](crate::FilePlaybackOptions::loop_start).
+    /// This is only supported for files and thus won't do anything for synths.
+    ///
+    /// Loops only get applied while the source has repeats left. Streamed sources apply the new
+    /// loop region to audio which has not yet been decoded.
+    pub fn set_source_loop(
+        &mut self,
+        playback_id: PlaybackId,
+        range: Option<Range<u64>>,
+    ) -> Result<(), Error> {
+        if let Some(range) = &range {
+            if range.start >= range.end {
+                return Err(Error::ParameterError(format!(
+                    "source loop range '{range:?}' is empty"
+                )));
+            }
+        }
+        if let Some(playing_source) = self.playing_sources.get(&playback_id) {
+            if let PlaybackMessageSender::File(queue) = &playing_source.message_sender {
+                if queue.push(FilePlaybackMessage::SetLoop(range)).is_err() {
+                    log::warn!("failed to send loop command to file");
+                    return Err(Error::SendError);
+                }
+            } else {
+                log::warn!("trying to change loop of a synth source, which is not supported");
+            }
+            Ok(())
+        } else {
+            log::warn!(
+                "trying to change loop of source #{playback_id} which is not or no longer playing"
+            );
+            Err(Error::MediaFileNotFound)
+        }
+    }
+
+    /// Add a new marker to a playing file source or replace its marker with the same id.
+    /// A [`PlaybackStatusEvent::MarkerReached`] event is sent when playback crosses the marker.
+    /// This is only supported for files and thus won't do anything for synths.
+    ///
+    /// Playing sources preallocate room for 256 markers: new markers get ignored when a source
+    /// already holds that many markers.
+    pub fn add_source_marker(
+        &mut self,
+        playback_id: PlaybackId,
+        marker: FileMarker,
+    ) -> Result<(), Error> {
+        let slot = Owned::new(&self.collector_handle, Some(marker));
+        self.send_source_marker_message(playback_id, FilePlaybackMessage::AddMarker(slot))
+    }
+
+    /// Remove the marker with the given id from a playing file source.
+    /// This is only supported for files and thus won't do anything for synths.
+    pub fn remove_source_marker(
+        &mut self,
+        playback_id: PlaybackId,
+        marker_id: u32,
+    ) -> Result<(), Error> {
+        let slot = Owned::new(&self.collector_handle, None);
+        self.send_source_marker_message(
+            playback_id,
+            FilePlaybackMessage::RemoveMarker(marker_id, slot),
+        )
+    }
+
+    fn send_source_marker_message(
+        &mut self,
+        playback_id: PlaybackId,
+        message: FilePlaybackMessage,
+    ) -> Result<(), Error> {
+        if let Some(playing_source) = self.playing_sources.get(&playback_id) {
+            if let PlaybackMessageSender::File(queue) = &playing_source.message_sender {
+                if queue.push(message).is_err() {
+                    log::warn!("failed to send marker command to file");
+                    return Err(Error::SendError);
+                }
+            } else {
+                log::warn!("trying to change markers of a synth source, which is not supported");
+            }
+            Ok(())
+        } else {
+            log::warn!(
+                "trying to change markers of source #{playback_id} which is not or no longer playing"
+            );
+            Err(Error::MediaFileNotFound)
+        }
+    }
+
+    /// Pause a playing file or synth source. The source gets faded out shortly and then stops
+    /// producing output, until it gets resumed at the same position via [`Self::resume_source`].
+    /// A [`PlaybackStatusEvent::Paused`] event is sent when the source got paused.
+    pub fn pause_source(&mut self, playback_id: PlaybackId) -> Result<(), Error> {
+        if let Some(playing_source) = self.playing_sources.get(&playback_id) {
+            if playing_source.message_sender.send_pause().is_err() {
+                log::warn!("failed to send pause command to source");
+                return Err(Error::SendError);
+            }
+            Ok(())
+        } else {
+            log::warn!("trying to pause source #{playback_id} which is not or no longer playing");
+            Err(Error::MediaFileNotFound)
+        }
+    }
+
+    /// Resume a paused file or synth source with a short fade-in.
+    /// A [`PlaybackStatusEvent::Resumed`] event is sent when the source got resumed.
+    pub fn resume_source(&mut self, playback_id: PlaybackId) -> Result<(), Error> {
+        if let Some(playing_source) = self.playing_sources.get(&playback_id) {
+            if playing_source.message_sender.send_resume().is_err() {
+                log::warn!("failed to send resume command to source");
+                return Err(Error::SendError);
+            }
+            Ok(())
+        } else {
+            log::warn!("trying to resume source #{playback_id} which is not or no longer playing");
+            Err(Error::MediaFileNotFound)
+        }
+    }
+
     /// Immediately stop a playing file or synth source. NB: This will fade-out the source when a
     /// stop_fade_out_duration option was set in the playback options it got started with.
     pub fn stop_source(&mut self, playback_id: PlaybackId) -> Result<(), Error> {
         let stopped = match self.playing_sources.get(&playback_id) {
-            Some(msg_queue) => {
-                if msg_queue.value().send_stop().is_err() {
+            Some(playing_source) => {
+                if playing_source.message_sender.send_stop().is_err() {
                     return Err(Error::SendError);
                 }
                 true
@@ -334,18 +997,19 @@ impl Player {
         stop_time: u64,
     ) -> Result<(), Error> {
         // check if the given playback id is still know (playing)
-        if self.playing_sources.contains_key(&playback_id) {
-            // pass stop request to mixer (force push stop events!)
-            self.mixer_event_queue
-                .force_push(MixedSourceMsg::StopSource {
-                    playback_id,
-                    sample_time: stop_time,
-                });
-            // NB: do not remove from playing_sources, as the event may apply in a long time in future.
-            Ok(())
-        } else {
-            Err(Error::MediaFileNotFound)
-        }
+        let bus_id = match self.playing_sources.get(&playback_id) {
+            Some(playing_source) => playing_source.bus_id,
+            None => return Err(Error::MediaFileNotFound),
+        };
+        // pass stop request to the bus' mixer (force push stop events!)
+        self.bus(bus_id)?
+            .event_queue
+            .force_push(MixedSourceMsg::StopSource {
+                playback_id,
+                sample_time: stop_time,
+            });
+        // NB: do not remove from playing_sources, as the event may apply in a long time in future.
+        Ok(())
     }
 
     /// Immediately stop all playing and possibly scheduled sources.
@@ -360,18 +1024,220 @@ impl Player {
         for source_id in playing_source_ids {
             self.stop_source(source_id)?;
         }
-        // remove all upcoming, scheduled sources in the mixer too (force push stop events!)
-        self.mixer_event_queue
-            .force_push(MixedSourceMsg::RemoveAllPendingSources);
+        // remove all upcoming, scheduled sources in all mixers too (force push stop events!)
+        for bus in self.buses.values() {
+            bus.event_queue
+                .force_push(MixedSourceMsg::RemoveAllPendingSources);
+        }
+        Ok(())
+    }
+
+    /// Create a new mixer bus with the given name, which gets mixed into the given parent bus,
+    /// or into the [`Player::MAIN_BUS`] when no parent is set. Returns the new bus' id, which
+    /// can be passed along when playing sources to route them into the bus.
+    pub fn add_bus(&mut self, name: &str, parent: Option<BusId>) -> Result<BusId, Error> {
+        let parent = parent.unwrap_or(Self::MAIN_BUS);
+        let parent_event_queue = self.bus(parent)?.event_queue.clone();
+        // create the bus' mixer and add it to the parent's mixer
+        let bus_id = unique_usize_id();
+        let gain = BusGain::new(1.0);
+        let bus_source = BusSource::new(
+            self.sink.channel_count(),
+            self.sink.sample_rate(),
+            gain.clone(),
+        );
+        let event_queue = bus_source.event_queue();
+        if parent_event_queue
+            .push(MixedSourceMsg::AddBus {
+                bus_id,
+                source: Owned::new(&self.collector_handle, Box::new(bus_source)),
+            })
+            .is_err()
+        {
+            log::warn!("mixer's event queue is full. failed to add bus!");
+            return Err(Error::SendError);
+        }
+        self.buses.insert(
+            bus_id,
+            Bus {
+                name: name.to_string(),
+                parent: Some(parent),
+                event_queue,
+                gain,
+                volume: 1.0,
+                muted: false,
+                solo: false,
+            },
+        );
+        // apply solo states of other buses
+        self.update_bus_gains();
+        Ok(bus_id)
+    }
+
+    /// Find a bus by its name. Returns the first matching bus, if any.
+    pub fn bus_id(&self, name: &str) -> Option<BusId> {
+        self.buses
+            .iter()
+            .find(|(_, bus)| bus.name == name)
+            .map(|(bus_id, _)| *bus_id)
+    }
+
+    /// Get the volume of the given bus.
+    pub fn bus_volume(&self, bus_id: BusId) -> Result<f32, Error> {
+        Ok(self.bus(bus_id)?.volume)
+    }
+    /// Set the volume of the given bus as linear factor. Changes get applied smoothly.
+    pub fn set_bus_volume(&mut self, bus_id: BusId, volume: f32) -> Result<(), Error> {
+        if volume < 0.0 || volume.is_nan() {
+            return Err(Error::ParameterError(format!(
+                "bus 'volume' value is '{volume}'"
+            )));
+        }
+        self.bus_mut(bus_id)?.volume = volume;
+        self.update_bus_gains();
+        Ok(())
+    }
+
+    /// True when the given bus is muted.
+    pub fn bus_muted(&self, bus_id: BusId) -> Result<bool, Error> {
+        Ok(self.bus(bus_id)?.muted)
+    }
+    /// Mute or unmute the given bus. Sources in muted buses continue playing silently.
+    pub fn set_bus_muted(&mut self, bus_id: BusId, muted: bool) -> Result<(), Error> {
+        self.bus_mut(bus_id)?.muted = muted;
+        self.update_bus_gains();
+        Ok(())
+    }
+
+    /// True when the given bus is soloed.
+    pub fn bus_solo(&self, bus_id: BusId) -> Result<bool, Error> {
+        Ok(self.bus(bus_id)?.solo)
+    }
+    /// Solo or unsolo the given bus. While any bus is soloed, all buses which are not soloed,
+    /// not nested in a soloed bus and not a parent of a soloed bus, get muted.
+    pub fn set_bus_solo(&mut self, bus_id: BusId, solo: bool) -> Result<(), Error> {
+        self.bus_mut(bus_id)?.solo = solo;
+        self.update_bus_gains();
+        Ok(())
+    }
+
+    /// Immediately stop all playing and scheduled sources in the given bus and all buses
+    /// which are nested in it.
+    pub fn stop_bus(&mut self, bus_id: BusId) -> Result<(), Error> {
+        self.bus(bus_id)?;
+        let bus_ids = self.nested_bus_ids(bus_id);
+        // stop everything that is playing now
+        let playing_source_ids = {
+            self.playing_sources
+                .iter()
+                .filter(|e| bus_ids.contains(&e.value().bus_id))
+                .map(|e| *e.key())
+                .collect::<Vec<_>>()
+        };
+        for source_id in playing_source_ids {
+            self.stop_source(source_id)?;
+        }
+        // remove all upcoming, scheduled sources in the bus' mixers too
+        for bus_id in bus_ids {
+            self.bus(bus_id)?
+                .event_queue
+                .force_push(MixedSourceMsg::RemoveAllPendingSources);
+        }
+        Ok(())
+    }
+
+    /// Remove the given bus and all buses which are nested in it. Sources which still play in
+    /// the removed buses get dropped immediately, without sending stop events, so stop them via
+    /// [`stop_bus`](Self::stop_bus) first and wait until they stopped for a click-free removal.
+    ///
+    /// The [`Player::MAIN_BUS`] can not be removed.
+    pub fn remove_bus(&mut self, bus_id: BusId) -> Result<(), Error> {
+        if bus_id == Self::MAIN_BUS {
+            return Err(Error::ParameterError(
+                "the main bus can not be removed".to_string(),
+            ));
+        }
+        let parent = self.bus(bus_id)?.parent.unwrap_or(Self::MAIN_BUS);
+        let bus_ids = self.nested_bus_ids(bus_id);
+        // remove the bus from its parent's mixer: this also drops all nested buses and sources
+        self.bus(parent)?
+            .event_queue
+            .force_push(MixedSourceMsg::RemoveBus { bus_id });
+        self.playing_sources
+            .retain(|_, playing_source| !bus_ids.contains(&playing_source.bus_id));
+        for bus_id in bus_ids {
+            self.buses.remove(&bus_id);
+        }
+        // apply solo states of the remaining buses
+        self.update_bus_gains();
         Ok(())
     }
+
+    /// Start recording the player's final mix, as it's sent to the output sink, into a 32-bit
+    /// float WAV file at the given path. Existing files will be overwritten.
+    ///
+    /// Recording starts at the given sample frame time in the audio output stream, see
+    /// [`output_sample_frame_position`](Self::output_sample_frame_position), or immediately
+    /// when no time is given. Samples are passed to a background thread which writes the file,
+    /// so the audio output never blocks.
+    ///
+    /// Only one recording can run at a time. Stop it via [`stop_recording`](Self::stop_recording).
+    /// The file gets finalized when the recording stopped or when the player is dropped.
+    #[cfg(feature = "hound")]
+    pub fn start_recording(
+        &mut self,
+        file_path: impl AsRef<std::path::Path>,
+        sample_time: Option<u64>,
+    ) -> Result<(), Error> {
+        if self.is_recording() {
+            return Err(Error::ParameterError(
+                "a recording is already running".to_string(),
+            ));
+        }
+        let (recorder, start_msg) = WavRecorder::new(
+            &self.collector_handle,
+            file_path,
+            self.output_channel_count(),
+            self.output_sample_rate(),
+            sample_time.unwrap_or(0),
+        )?;
+        self.recorder_event_queue
+            .push(start_msg)
+            .map_err(|_| Error::SendError)?;
+        self.recorder = Some(recorder);
+        Ok(())
+    }
+
+    /// Stop the running recording at the given sample frame time in the audio output stream,
+    /// or immediately when no time is given. The recorded range thus is the range from the
+    /// recording's start time up to, but not including the stop time.
+    #[cfg(feature = "hound")]
+    pub fn stop_recording(&mut self, sample_time: Option<u64>) -> Result<(), Error> {
+        if !self.is_recording() {
+            return Err(Error::ParameterError("no recording is running".to_string()));
+        }
+        self.recorder_event_queue
+            .push(RecorderMsg::Stop {
+                sample_time: sample_time.unwrap_or(0),
+            })
+            .map_err(|_| Error::SendError)
+    }
+
+    /// True when a recording is running, or when a stopped recording's file is not yet
+    /// finalized.
+    #[cfg(feature = "hound")]
+    pub fn is_recording(&self) -> bool {
+        self.recorder
+            .as_ref()
+            .is_some_and(|recorder| !recorder.is_finished())
+    }
 }
 
 /// details
 impl Player {
     fn handle_playback_events(
         playback_sender: Option<Sender<PlaybackStatusEvent>>,
-        playing_sources: Arc<DashMap<PlaybackId, PlaybackMessageSender>>,
+        playing_sources: Arc<DashMap<PlaybackId, PlayingSource>>,
     ) -> Sender<PlaybackStatusEvent> {
         let (playback_send_proxy, playback_recv_proxy) = {
             // use same capacity in proxy as original one
@@ -411,6 +1277,102 @@ impl Player {
         playback_send_proxy
     }
 
+    /// Handle of the player's collector, which drops audio thread garbage in a worker thread.
+    pub(crate) fn collector_handle(&self) -> &Handle {
+        &self.collector_handle
+    }
+
+    /// Memorize the worker channel of a playing playlist source.
+    pub(crate) fn set_playlist_sender(
+        &self,
+        playback_id: PlaybackId,
+        sender: Sender<PlaylistWorkerMessage>,
+    ) {
+        if let Some(mut playing_source) = self.playing_sources.get_mut(&playback_id) {
+            playing_source.playlist_sender = Some(sender);
+        }
+    }
+
+    /// Get the worker channel of a playing playlist source.
+    pub(crate) fn playlist_sender(
+        &self,
+        playback_id: PlaybackId,
+    ) -> Result<Sender<PlaylistWorkerMessage>, Error> {
+        match self.playing_sources.get(&playback_id) {
+            Some(playing_source) => playing_source.playlist_sender.clone().ok_or_else(|| {
+                Error::ParameterError(format!("source #{playback_id} is not a playlist"))
+            }),
+            None => {
+                log::warn!(
+                    "trying to control playlist #{playback_id} which is not or no longer playing"
+                );
+                Err(Error::MediaFileNotFound)
+            }
+        }
+    }
+
+    fn bus(&self, bus_id: BusId) -> Result<&Bus, Error> {
+        self.buses
+            .get(&bus_id)
+            .ok_or_else(|| Error::ParameterError(format!("bus #{bus_id} does not exist")))
+    }
+
+    fn bus_mut(&mut self, bus_id: BusId) -> Result<&mut Bus, Error> {
+        self.buses
+            .get_mut(&bus_id)
+            .ok_or_else(|| Error::ParameterError(format!("bus #{bus_id} does not exist")))
+    }
+
+    /// Returns the given bus id and the ids of all buses which are nested in it.
+    fn nested_bus_ids(&self, bus_id: BusId) -> Vec<BusId> {
+        let mut bus_ids = vec![bus_id];
+        let mut index = 0;
+        while index < bus_ids.len() {
+            let parent = Some(bus_ids[index]);
+            bus_ids.extend(
+                self.buses
+                    .iter()
+                    .filter(|(_, bus)| bus.parent == parent)
+                    .map(|(bus_id, _)| *bus_id),
+            );
+            index += 1;
+        }
+        bus_ids
+    }
+
+    /// True when the given bus is the given ancestor bus or is nested in it.
+    fn is_nested_bus(&self, bus_id: BusId, ancestor_id: BusId) -> bool {
+        let mut bus_id = Some(bus_id);
+        while let Some(id) = bus_id {
+            if id == ancestor_id {
+                return true;
+            }
+            bus_id = self.buses.get(&id).and_then(|bus| bus.parent);
+        }
+        false
+    }
+
+    /// Apply volume, mute and solo states of all buses to the bus mixers' gains.
+    fn update_bus_gains(&self) {
+        let soloed_bus_ids = self
+            .buses
+            .iter()
+            .filter(|(_, bus)| bus.solo)
+            .map(|(bus_id, _)| *bus_id)
+            .collect::<Vec<_>>();
+        for (bus_id, bus) in &self.buses {
+            let in_solo_path = soloed_bus_ids.is_empty()
+                || soloed_bus_ids.iter().any(|solo_id| {
+                    self.is_nested_bus(*bus_id, *solo_id) || self.is_nested_bus(*solo_id, *bus_id)
+                });
+            if bus.muted || !in_solo_path {
+                bus.gain.set(0.0);
+            } else {
+                bus.gain.set(bus.volume);
+            }
+        }
+    }
+
     fn handle_drop_collects(mut collector: Collector, running: Arc<AtomicBool>) {
         std::thread::Builder::new()
             .name("audio_player_drops".to_string())
@@ -438,3 +1400,340 @@ impl Drop for Player {
         self.sink.close();
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FilePlaybackOptions, ManualOutput, OutputDevice, PreloadedFileSource};
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    fn new_player() -> (ManualOutput, Player) {
+        let output = ManualOutput::new(1, SAMPLE_RATE);
+        let player = Player::new(output.sink(), None);
+        (output, player)
+    }
+
+    fn new_source(buffer: Vec<f32>, options: FilePlaybackOptions) -> PreloadedFileSource {
+        PreloadedFileSource::with_buffer(
+            Arc::new(buffer),
+            SAMPLE_RATE,
+            1,
+            "test_buffer",
+            None,
+            options,
+            SAMPLE_RATE,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn play_file_source() {
+        let (output, mut player) = new_player();
+        let source = new_source(vec![0.1, 0.2, 0.3, 0.0], FilePlaybackOptions::default());
+        player.play_file_source(source, Some(2)).unwrap();
+        assert_eq!(
+            output.render(8),
+            vec![0.0, 0.0, 0.1, 0.2, 0.3, 0.0, 0.0, 0.0]
+        );
+        assert_eq!(player.output_sample_frame_position(), 8);
+
+        // sources which are scheduled in the past start playing immediately
+        let source = new_source(vec![0.5, 0.5, 0.0], FilePlaybackOptions::default());
+        player.play_file_source(source, Some(2)).unwrap();
+        assert_eq!(output.render(4), vec![0.5, 0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn stop_source_at_sample_time() {
+        let (output, mut player) = new_player();
+        let options = FilePlaybackOptions {
+            fade_out_duration: None,
+            ..Default::default()
+        };
+        let source = new_source(vec![1.0; 1024], options);
+        let playback_id = player.play_file_source(source, Some(4)).unwrap();
+        player.stop_source_at_sample_time(playback_id, 100).unwrap();
+        let samples = output.render(256);
+        assert!(samples[..4].iter().all(|s| *s == 0.0));
+        assert!(samples[4..100].iter().all(|s| *s == 1.0));
+        assert!(samples[100..].iter().all(|s| *s == 0.0));
+    }
+
+    #[test]
+    fn fades() {
+        let (output, mut player) = new_player();
+        let fade_duration = Duration::from_millis(10);
+        let fade_frames = (fade_duration.as_secs_f64() * SAMPLE_RATE as f64) as usize;
+
+        // fade-in
+        let options = FilePlaybackOptions::default()
+            .fade_in(fade_duration)
+            .fade_out(fade_duration);
+        let source = new_source(vec![1.0; SAMPLE_RATE as usize], options);
+        let playback_id = player.play_file_source(source, None).unwrap();
+        let samples = output.render(2 * fade_frames);
+        assert!(samples[0] < 0.1);
+        assert!(samples[..fade_frames].windows(2).all(|w| w[0] <= w[1]));
+        assert!(samples[fade_frames - 1..].iter().all(|s| *s == 1.0));
+
+        // fade-out on stop
+        player.stop_source(playback_id).unwrap();
+        let samples = output.render(4 * fade_frames);
+        assert!(samples[0] > 0.9);
+        assert!(samples.windows(2).all(|w| w[0] >= w[1]));
+        assert!(samples[4 * fade_frames - 1] < 0.001);
+
+        // fades to other volumes
+        let options = FilePlaybackOptions::default().fade_curve(FadeCurve::Linear);
+        let source = new_source(vec![1.0; SAMPLE_RATE as usize], options);
+        let playback_id = player.play_file_source(source, None).unwrap();
+        output.render(16);
+        player
+            .fade_source_volume(playback_id, 0.5, fade_duration)
+            .unwrap();
+        let samples = output.render(2 * fade_frames);
+        assert!((samples[fade_frames / 2 - 1] - 0.75).abs() < 0.01);
+        assert!(samples[fade_frames - 1..].iter().all(|s| *s == 0.5));
+        assert!(player
+            .fade_source_volume(playback_id, -1.0, fade_duration)
+            .is_err());
+    }
+
+    #[test]
+    fn buses() {
+        let (output, mut player) = new_player();
+        let options = FilePlaybackOptions {
+            fade_out_duration: None,
+            ..Default::default()
+        };
+        let music = player.add_bus("music", None).unwrap();
+        let sfx = player.add_bus("sfx", None).unwrap();
+        assert_eq!(player.bus_id("sfx"), Some(sfx));
+        assert!(player.add_bus("voice", Some(usize::MAX)).is_err());
+
+        // bus volume
+        let source = new_source(vec![1.0; 1024], options);
+        player
+            .play_file_source_on_bus(source, music, None, None)
+            .unwrap();
+        player.set_bus_volume(music, 0.5).unwrap();
+        output.render(16);
+        assert_eq!(output.render(4), vec![0.5; 4]);
+
+        let source = new_source(vec![0.25; 1024], options);
+        player
+            .play_file_source_on_bus(source, sfx, None, None)
+            .unwrap();
+        assert_eq!(output.render(4), vec![0.75; 4]);
+
+        // solo mutes all other buses
+        player.set_bus_solo(sfx, true).unwrap();
+        output.render(16);
+        assert_eq!(output.render(4), vec![0.25; 4]);
+
+        // stop all sources in a bus
+        player.stop_bus(sfx).unwrap();
+        player.set_bus_solo(sfx, false).unwrap();
+        output.render(16);
+        assert_eq!(output.render(4), vec![0.5; 4]);
+
+        // mute
+        player.set_bus_muted(music, true).unwrap();
+        output.render(16);
+        assert_eq!(output.render(4), vec![0.0; 4]);
+
+        // remove buses along with their nested buses and sources
+        // NB: nested buses get scaled by their parent bus' volume
+        player.set_bus_muted(music, false).unwrap();
+        let nested = player.add_bus("nested", Some(music)).unwrap();
+        let source = new_source(vec![0.25; 1024], options);
+        player
+            .play_file_source_on_bus(source, nested, None, None)
+            .unwrap();
+        output.render(16);
+        assert_eq!(output.render(4), vec![0.625; 4]);
+        assert!(player.remove_bus(Player::MAIN_BUS).is_err());
+        player.remove_bus(music).unwrap();
+        assert!(player.bus_id("nested").is_none());
+        assert!(player.bus_volume(music).is_err());
+        assert_eq!(output.render(4), vec![0.0; 4]);
+        assert!(player.remove_bus(music).is_err());
+    }
+
+    #[test]
+    fn source_parameters() {
+        let output = ManualOutput::new(2, SAMPLE_RATE);
+        let mut player = Player::new(output.sink(), None);
+        let options = FilePlaybackOptions {
+            fade_out_duration: None,
+            ..Default::default()
+        };
+
+        // volume and panning get applied smoothly
+        let source = new_source(vec![1.0; SAMPLE_RATE as usize], options);
+        let playback_id = player.play_file_source(source, None).unwrap();
+        player.set_source_volume(playback_id, 0.5).unwrap();
+        let samples = output.render(2048);
+        assert!(samples.windows(2).all(|w| w[0] >= w[1]));
+        assert_eq!(output.render(2), vec![0.5; 4]);
+        player.set_source_panning(playback_id, -1.0).unwrap();
+        output.render(2048);
+        assert_eq!(output.render(2), vec![0.5, 0.0, 0.5, 0.0]);
+        assert!(player.set_source_panning(playback_id, 2.0).is_err());
+        assert!(player.set_source_volume(playback_id, -1.0).is_err());
+        player.stop_source(playback_id).unwrap();
+
+        // speed changes get ramped
+        let source = new_source(vec![1.0; 1000], options);
+        let playback_id = player.play_file_source(source, None).unwrap();
+        player.set_source_speed(playback_id, 2.0).unwrap();
+        let samples = output.render(1000);
+        assert_eq!(samples[2 * 600], 1.0);
+        assert_eq!(samples[2 * 900], 0.0);
+        assert!(player.set_source_speed(playback_id, 0.0).is_err());
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let output = ManualOutput::new(1, SAMPLE_RATE);
+        let (event_sender, event_receiver) = crossbeam_channel::unbounded();
+        let mut player = Player::new(output.sink(), Some(event_sender));
+        let recv_event = || {
+            event_receiver
+                .recv_timeout(Duration::from_secs(1))
+                .expect("missing playback event")
+        };
+
+        let source = new_source(
+            (0..SAMPLE_RATE).map(|i| i as f32).collect(),
+            FilePlaybackOptions::default(),
+        );
+        let playback_id = player.play_file_source(source, None).unwrap();
+        assert_eq!(output.render(100)[99], 99.0);
+
+        // pause fades out and then stops producing output
+        player.pause_source(playback_id).unwrap();
+        let samples = output.render(2000);
+        assert!(samples[0] > 99.0);
+        assert!(samples[1000..].iter().all(|s| *s == 0.0));
+        assert!(
+            matches!(recv_event(), PlaybackStatusEvent::Paused { id, .. } if id == playback_id)
+        );
+        assert!(output.render(100).iter().all(|s| *s == 0.0));
+
+        // resume fades in and continues at the paused position
+        player.resume_source(playback_id).unwrap();
+        let samples = output.render(1000);
+        assert!(
+            matches!(recv_event(), PlaybackStatusEvent::Resumed { id, .. } if id == playback_id)
+        );
+        let fade_frames = (0.02 * SAMPLE_RATE as f32) as usize;
+        let paused_frame = (100 + fade_frames) as f32;
+        assert!(samples[0] < paused_frame);
+        assert_eq!(samples[999], paused_frame + 999.0);
+
+        // paused sources stop without fading out
+        player.pause_source(playback_id).unwrap();
+        output.render(2000);
+        player.stop_source(playback_id).unwrap();
+        output.render(16);
+        assert!(matches!(recv_event(), PlaybackStatusEvent::Paused { .. }));
+        assert!(matches!(recv_event(), PlaybackStatusEvent::Stopped { .. }));
+    }
+
+    #[test]
+    fn seek_at_sample_time() {
+        let output = ManualOutput::new(1, SAMPLE_RATE);
+        let mut player = Player::new(output.sink(), None);
+
+        let source = new_source(
+            (0..SAMPLE_RATE).map(|i| i as f32).collect(),
+            FilePlaybackOptions::default(),
+        );
+        let playback_id = player.play_file_source(source, None).unwrap();
+        output.render(100);
+
+        // seeks apply on the exact output frame
+        player
+            .seek_source_at_sample_time(playback_id, Duration::from_millis(100), 150, None)
+            .unwrap();
+        let samples = output.render(100);
+        assert_eq!(samples[49], 149.0);
+        assert_eq!(samples[50], 4410.0);
+        assert_eq!(samples[99], 4459.0);
+
+        // crossfades fade from the old to the new position
+        let crossfade = Duration::from_millis(10);
+        player
+            .seek_source_at_sample_time(playback_id, Duration::ZERO, 300, Some(crossfade))
+            .unwrap();
+        let samples = output.render(1000);
+        assert_eq!(samples[99], 4559.0);
+        assert!(samples[100] > 4000.0);
+        assert_eq!(samples[999], 899.0);
+    }
+
+    #[test]
+    fn crossfade_sources() {
+        let (output, mut player) = new_player();
+        let from = new_source(vec![1.0; 1024], FilePlaybackOptions::default());
+        let to = new_source(vec![-1.0; 1024], FilePlaybackOptions::default());
+        let from_id = player.play_file_source(from, None).unwrap();
+        let to_id = player.play_file_source(to, Some(50)).unwrap();
+        player
+            .crossfade_sources_at_sample_time(from_id, to_id, 100..200, FadeCurve::Linear)
+            .unwrap();
+        let samples = output.render(300);
+        assert!(samples[..50].iter().all(|s| *s == 1.0));
+        // the faded in source stays silent until the crossfade starts
+        assert!(samples[50..100].iter().all(|s| *s == 1.0));
+        // linear crossfades of opposite signals pass zero in the middle
+        assert_eq!(samples[150], 0.0);
+        assert!(samples[100..200].windows(2).all(|w| w[0] > w[1]));
+        assert!(samples[200..].iter().all(|s| *s == -1.0));
+
+        assert!(player
+            .crossfade_sources(to_id, to_id, Duration::ZERO, FadeCurve::default())
+            .is_err());
+        assert!(player
+            .crossfade_sources(to_id, PlaybackId::MAX, Duration::ZERO, FadeCurve::default())
+            .is_err());
+    }
+
+    #[test]
+    fn volume_envelopes() {
+        let (output, mut player) = new_player();
+        let source = new_source(vec![1.0; 1024], FilePlaybackOptions::default());
+        let playback_id = player.play_file_source(source, None).unwrap();
+        let envelope = VolumeEnvelope::new()
+            .with_point(100, 1.0, FadeCurve::Linear)
+            .with_point(200, 0.5, FadeCurve::Linear);
+        player
+            .set_source_volume_envelope(playback_id, envelope)
+            .unwrap();
+        let samples = output.render(300);
+        assert!(samples[..=100].iter().all(|s| *s == 1.0));
+        assert_eq!(samples[150], 0.75);
+        assert!(samples[200..].iter().all(|s| *s == 0.5));
+
+        // replace and clear envelopes while playing
+        let envelope = VolumeEnvelope::new().with_point(0, 0.25, FadeCurve::Linear);
+        player
+            .set_source_volume_envelope(playback_id, envelope)
+            .unwrap();
+        assert_eq!(output.render(4), vec![0.25; 4]);
+        player.clear_source_volume_envelope(playback_id).unwrap();
+        assert_eq!(output.render(4), vec![1.0; 4]);
+
+        let invalid = VolumeEnvelope::new().with_point(0, -1.0, FadeCurve::Linear);
+        assert!(player
+            .set_source_volume_envelope(playback_id, invalid)
+            .is_err());
+        assert!(player
+            .clear_source_volume_envelope(PlaybackId::MAX)
+            .is_err());
+    }
+}
@@ -2,11 +2,17 @@ use std::time::Instant;
 
 // -------------------------------------------------------------------------------------------------
 
+pub mod bus;
 pub mod converted;
 pub mod empty;
 pub mod file;
+#[cfg(feature = "cpal")]
+pub mod input;
 pub mod mapped;
 pub mod mixed;
+pub mod panned;
+#[cfg(feature = "hound")]
+pub mod recorder;
 pub mod resampled;
 pub mod synth;
 
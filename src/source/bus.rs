@@ -0,0 +1,104 @@
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+
+use crossbeam_queue::ArrayQueue;
+
+use crate::source::{
+    mixed::{MixedSource, MixedSourceMsg},
+    Source, SourceTime,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Gain of a [`BusSource`], which can be shared with and changed from other threads.
+#[derive(Clone)]
+pub(crate) struct BusGain(Arc<AtomicU32>);
+
+impl BusGain {
+    pub fn new(gain: f32) -> Self {
+        Self(Arc::new(AtomicU32::new(gain.to_bits())))
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, gain: f32) {
+        self.0.store(gain.to_bits(), Ordering::Relaxed);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A mixer bus: a [`MixedSource`] which mixes down all sources of a bus, followed by a gain
+/// stage which applies the bus' volume, mute and solo state.
+///
+/// Gain changes get applied with a linear ramp over one output buffer, to avoid clicks.
+pub(crate) struct BusSource {
+    mixer: MixedSource,
+    gain: BusGain,
+    current_gain: f32,
+}
+
+impl BusSource {
+    pub fn new(channel_count: usize, sample_rate: u32, gain: BusGain) -> Self {
+        let current_gain = gain.get();
+        Self {
+            mixer: MixedSource::new(channel_count, sample_rate),
+            gain,
+            current_gain,
+        }
+    }
+
+    /// Message queue of the bus' mixer.
+    pub fn event_queue(&self) -> Arc<ArrayQueue<MixedSourceMsg>> {
+        self.mixer.event_queue()
+    }
+}
+
+impl Source for BusSource {
+    fn write(&mut self, output: &mut [f32], time: &SourceTime) -> usize {
+        let written = self.mixer.write(output, time);
+
+        let target_gain = self.gain.get();
+        if (target_gain - self.current_gain).abs() > f32::EPSILON {
+            // ramp to the new gain
+            let channel_count = self.mixer.channel_count();
+            let frame_count = written / channel_count;
+            if frame_count > 0 {
+                let step = (target_gain - self.current_gain) / frame_count as f32;
+                for (index, frame) in output[..written]
+                    .chunks_exact_mut(channel_count)
+                    .enumerate()
+                {
+                    let gain = self.current_gain + step * (index + 1) as f32;
+                    for sample in frame {
+                        *sample *= gain;
+                    }
+                }
+            }
+            self.current_gain = target_gain;
+        } else if (1.0 - target_gain).abs() > f32::EPSILON {
+            // apply constant gain
+            for sample in output[..written].iter_mut() {
+                *sample *= target_gain;
+            }
+        }
+
+        written
+    }
+
+    fn channel_count(&self) -> usize {
+        self.mixer.channel_count()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.mixer.sample_rate()
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.mixer.is_exhausted()
+    }
+}
@@ -1,20 +1,316 @@
+pub mod buffer;
+pub mod playlist;
 pub mod preloaded;
+pub mod probe;
 pub mod streamed;
 
-use std::{sync::Arc, time::Duration};
+use std::{
+    io::{Read, Seek},
+    ops::Range,
+    sync::Arc,
+    time::Duration,
+};
 
+use basedrop::Owned;
 use crossbeam_channel::Sender;
 use crossbeam_queue::ArrayQueue;
 
 use crate::{
-    player::{PlaybackId, PlaybackStatusContext, PlaybackStatusEvent},
+    player::{BusId, PlaybackId, PlaybackStatusContext, PlaybackStatusEvent},
     source::{resampled::ResamplingQuality, Source},
-    utils::db_to_linear,
+    utils::{db_to_linear, fader::FadeCurve},
     Error, Player,
 };
 
 // -------------------------------------------------------------------------------------------------
 
+/// How a [`FileSource`] plays its loop region, when repeating.
+///
+/// Loop modes other than `Forward` are only supported by [`preloaded::PreloadedFileSource`]s.
+/// Streamed sources always loop forward.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum LoopMode {
+    /// Jump back to the loop start when reaching the loop end.
+    #[default]
+    Forward,
+    /// Alternate the playback direction at the loop end and start. Each repeat plays the loop
+    /// backwards and forwards again.
+    PingPong,
+    /// Loop forward and crossfade the given duration before the loop end with the audio at the
+    /// loop start, to avoid clicks at loops which are not sample-perfect. The crossfade length
+    /// is limited to half of the loop's length.
+    Crossfade(Duration),
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Selects the audio track of a [`FileSource`] in containers with multiple audio tracks, e.g.
+/// stems or commentary tracks in MP4 files. See [`probe::probe_tracks`] to list a file's tracks.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum TrackSelector {
+    /// The container's default track, usually the first audio track.
+    #[default]
+    Default,
+    /// The track with the given index in the container's track list.
+    Index(usize),
+    /// The first audio track with the given language code, e.g. "eng".
+    Language(String),
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Encoded audio, which can be decoded and played back by [`FileSource`]s: either a file path,
+/// an in-memory buffer, e.g. from `include_bytes!`, or any other seekable reader.
+///
+/// Paths, strings and byte buffers convert into encoded audio, so they can be passed to
+/// [`Player::play_file`] and the file source constructors directly.
+pub struct EncodedAudio {
+    pub(crate) data: EncodedAudioData,
+    pub(crate) hint: Option<String>,
+    pub(crate) name: String,
+    pub(crate) track: TrackSelector,
+}
+
+/// Data of an [`EncodedAudio`].
+pub(crate) enum EncodedAudioData {
+    Path(String),
+    Bytes(Arc<[u8]>),
+    Reader(Box<dyn ReadSeek>),
+}
+
+/// A `Read + Seek + Send` media source for [`EncodedAudio::from_reader`].
+pub(crate) trait ReadSeek: Read + Seek + Send {}
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+impl EncodedAudio {
+    /// Audio from a file path. The path's extension is used as format hint.
+    pub fn from_path(path: impl Into<String>) -> Self {
+        let path = path.into();
+        Self {
+            hint: None,
+            name: path.clone(),
+            track: TrackSelector::Default,
+            data: EncodedAudioData::Path(path),
+        }
+    }
+
+    /// Audio from an in-memory buffer.
+    pub fn from_bytes(bytes: impl Into<Arc<[u8]>>) -> Self {
+        Self {
+            data: EncodedAudioData::Bytes(bytes.into()),
+            hint: None,
+            name: String::new(),
+            track: TrackSelector::Default,
+        }
+    }
+
+    /// Audio from a seekable reader.
+    pub fn from_reader(reader: impl Read + Seek + Send + 'static) -> Self {
+        Self {
+            data: EncodedAudioData::Reader(Box::new(reader)),
+            hint: None,
+            name: String::new(),
+            track: TrackSelector::Default,
+        }
+    }
+
+    /// Set a format hint, either a file extension such as "mp3" or a MIME type such as
+    /// "audio/mpeg", which helps detecting the audio's format.
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// Set the name which is passed as `path` in [`PlaybackStatusEvent`]s. By default
+    /// this is the file path for files and empty for in-memory audio.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Select the audio track which should be played in containers with multiple audio tracks.
+    /// By default the container's default track is played.
+    pub fn with_track(mut self, track: TrackSelector) -> Self {
+        self.track = track;
+        self
+    }
+
+    /// The name which is passed as `path` in [`PlaybackStatusEvent`]s.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Copy paths and shared in-memory buffers, so the audio can be decoded again.
+    /// Returns None for readers, which can be decoded only once.
+    pub(crate) fn try_clone(&self) -> Option<Self> {
+        let data = match &self.data {
+            EncodedAudioData::Path(path) => EncodedAudioData::Path(path.clone()),
+            EncodedAudioData::Bytes(bytes) => EncodedAudioData::Bytes(Arc::clone(bytes)),
+            EncodedAudioData::Reader(_) => return None,
+        };
+        Some(Self {
+            data,
+            hint: self.hint.clone(),
+            name: self.name.clone(),
+            track: self.track.clone(),
+        })
+    }
+}
+
+impl From<&str> for EncodedAudio {
+    fn from(path: &str) -> Self {
+        Self::from_path(path)
+    }
+}
+
+impl From<String> for EncodedAudio {
+    fn from(path: String) -> Self {
+        Self::from_path(path)
+    }
+}
+
+impl From<&String> for EncodedAudio {
+    fn from(path: &String) -> Self {
+        Self::from_path(path.as_str())
+    }
+}
+
+impl From<Vec<u8>> for EncodedAudio {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl From<Arc<[u8]>> for EncodedAudio {
+    fn from(bytes: Arc<[u8]>) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl From<&'static [u8]> for EncodedAudio {
+    fn from(bytes: &'static [u8]) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl<const N: usize> From<&'static [u8; N]> for EncodedAudio {
+    fn from(bytes: &'static [u8; N]) -> Self {
+        Self::from_bytes(bytes.as_slice())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A position or length within a file, either in sample frames or as time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilePosition {
+    /// Position in sample frames of the file.
+    Frames(u64),
+    /// Position in time, relative to the file's start.
+    Time(Duration),
+}
+
+impl FilePosition {
+    /// Convert the position to sample frames, using the given file sample rate.
+    pub fn to_frames(self, sample_rate: u32) -> u64 {
+        match self {
+            Self::Frames(frames) => frames,
+            Self::Time(duration) => (duration.as_secs_f64() * sample_rate as f64).round() as u64,
+        }
+    }
+}
+
+impl From<u64> for FilePosition {
+    fn from(frames: u64) -> Self {
+        Self::Frames(frames)
+    }
+}
+
+impl From<Duration> for FilePosition {
+    fn from(duration: Duration) -> Self {
+        Self::Time(duration)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A named position in a file, e.g. a WAV cue point or a marker which got added at runtime via
+/// [`Player::add_source_marker`]. A [`PlaybackStatusEvent::MarkerReached`] event is sent when
+/// playback crosses a marker.
+///
+/// Only markers from WAV `cue ` and `labl` chunks and from FLAC cue sheets are read from files.
+/// Chapters of other containers, e.g. MP4 chapters, are not supported: symphonia's MP4 reader
+/// does not expose them. Such markers can be added manually via [`Player::add_source_marker`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileMarker {
+    /// Identifier of the marker, e.g. the WAV cue point id.
+    pub id: u32,
+    /// Position of the marker in sample frames of the file.
+    pub frame: u64,
+    /// Label of the marker, e.g. from WAV `labl` chunks. Empty when the marker has no label.
+    pub label: Arc<String>,
+}
+
+impl FileMarker {
+    pub fn new(id: u32, frame: u64, label: impl Into<String>) -> Self {
+        Self {
+            id,
+            frame,
+            label: Arc::new(label.into()),
+        }
+    }
+}
+
+/// Number of markers file sources preallocate, so markers can be added in real-time threads
+/// without allocating.
+pub(crate) const MARKER_CAPACITY: usize = 256;
+
+/// Copy the given markers into a list with at least [`MARKER_CAPACITY`] preallocated entries.
+pub(crate) fn preallocated_markers(markers: &[FileMarker]) -> Vec<FileMarker> {
+    let mut preallocated = Vec::with_capacity(MARKER_CAPACITY.max(markers.len()));
+    preallocated.extend_from_slice(markers);
+    preallocated
+}
+
+/// A marker which is passed to or from a playing file source. Markers which get replaced,
+/// removed or rejected by a source get moved into the slot, so they get dropped in the player's
+/// collector thread and not in the audio thread.
+pub(crate) type MarkerSlot = Owned<Option<FileMarker>>;
+
+/// Insert a new marker into the given markers, which are sorted by their position, replacing an
+/// existing marker with the same id. New markers get rejected when the markers' capacity is
+/// exhausted, to avoid allocations.
+///
+/// Returns the replaced marker, if any, or the marker itself as error, when it got rejected.
+pub(crate) fn insert_marker(
+    markers: &mut Vec<FileMarker>,
+    marker: FileMarker,
+) -> Result<Option<FileMarker>, FileMarker> {
+    let replaced = if let Some(index) = markers.iter().position(|m| m.id == marker.id) {
+        Some(markers.remove(index))
+    } else if markers.len() >= markers.capacity() {
+        log::warn!(
+            "can't add marker #{}: a file can have at most {} markers",
+            marker.id,
+            markers.capacity()
+        );
+        return Err(marker);
+    } else {
+        None
+    };
+    let index = markers.partition_point(|m| m.frame <= marker.frame);
+    markers.insert(index, marker);
+    Ok(replaced)
+}
+
+/// Remove the marker with the given id from the given markers. Returns the removed marker.
+pub(crate) fn remove_marker(markers: &mut Vec<FileMarker>, marker_id: u32) -> Option<FileMarker> {
+    let index = markers.iter().position(|m| m.id == marker_id)?;
+    Some(markers.remove(index))
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Options to control playback of a [`FileSource`].
 #[derive(Clone, Copy)]
 pub struct FilePlaybackOptions {
@@ -30,20 +326,64 @@ pub struct FilePlaybackOptions {
     /// See also `resampling_quality` property.
     pub speed: f64,
 
+    /// By default false: when true, the file plays backwards, from its end to its start.
+    /// Loop regions still are specified in file frames, while seek and playback positions are
+    /// in playback order. Only supported by [`preloaded::PreloadedFileSource`]s.
+    pub reverse: bool,
+
+    /// By default None: when set, playback starts at the given position in the file instead
+    /// of the file's start. Total frames, seek and playback positions of the source are relative
+    /// to the played region.
+    pub region_start: Option<FilePosition>,
+    /// By default None: when set, playback ends at the given position in the file (exclusive)
+    /// instead of the file's end.
+    pub region_end: Option<FilePosition>,
+    /// By default None: when set and no `region_end` is set, playback ends after playing the
+    /// given length from the region start.
+    pub region_length: Option<FilePosition>,
+
+    /// By default None: when set, plays the audio track with the given index in the container's
+    /// track list instead of the track which got selected in the [`EncodedAudio`], which by
+    /// default is the container's default track. See [`probe::probe_tracks`].
+    ///
+    /// Only track indices can be selected here, so the options stay `Copy`. To select a track
+    /// by its language, pass the audio with a [`TrackSelector::Language`] selector via
+    /// [`EncodedAudio::with_track`] and leave this unset.
+    pub track: Option<usize>,
+
     /// By default 0: when > 0 the number of times the file should be looped.
     /// Set to usize::MAX to repeat forever.
     pub repeat: usize,
 
+    /// By default None: when set, the first sample frame of the looped region. Repeats jump
+    /// back to this frame instead of the start of the file.
+    pub loop_start: Option<u64>,
+    /// By default None: when set, the sample frame (exclusive) at which the looped region ends.
+    /// Repeats happen at this frame instead of the end of the file.
+    pub loop_end: Option<u64>,
+    /// By default true: when no loop start or end is set, use the loop points which are embedded
+    /// in the file, if any. Currently only loops in WAV `smpl` chunks are supported.
+    pub use_embedded_loop: bool,
+    /// By default LoopMode::Forward: how the loop region gets played when repeating.
+    pub loop_mode: LoopMode,
+
     /// By default None: when set, the source should start playing at the given
     /// sample frame time in the audio output stream.
     pub start_time: Option<u64>,
 
+    /// By default None: when set, the source plays on the given mixer bus, else on the
+    /// player's main bus. See [`Player::add_bus`](crate::Player::add_bus).
+    pub bus: Option<BusId>,
+
     /// By default None: when set, the source's volume will fade in with the given
     /// amount when starting to play.
     pub fade_in_duration: Option<Duration>,
     /// By default 5ms: volume fade out duration, applied when the the source gets
     /// stopped before it finished playing.
     pub fade_out_duration: Option<Duration>,
+    /// By default FadeCurve::EqualPower: shape of the fade-in, fade-out and
+    /// [`Player::fade_source_volume`](crate::Player::fade_source_volume) volume fades.
+    pub fade_curve: FadeCurve,
 
     /// By default ResamplingQuality::Default: Quality mode of a applied resampler,
     /// either when the source is getting played back on a stream with a sample rate
@@ -63,10 +403,21 @@ impl Default for FilePlaybackOptions {
             stream: false,
             volume: 1.0,
             speed: 1.0,
+            reverse: false,
+            region_start: None,
+            region_end: None,
+            region_length: None,
+            track: None,
             repeat: 0,
+            loop_start: None,
+            loop_end: None,
+            use_embedded_loop: true,
+            loop_mode: LoopMode::Forward,
             start_time: None,
+            bus: None,
             fade_in_duration: None,
             fade_out_duration: Some(Duration::from_millis(50)),
+            fade_curve: FadeCurve::default(),
             resampling_quality: ResamplingQuality::Default,
             playback_pos_emit_rate: Some(Duration::from_secs(1)),
         }
@@ -100,12 +451,41 @@ impl FilePlaybackOptions {
         self.fade_out_duration = Some(duration);
         self
     }
+    pub fn fade_curve(mut self, curve: FadeCurve) -> Self {
+        self.fade_curve = curve;
+        self
+    }
 
     pub fn speed(mut self, speed: f64) -> Self {
         self.speed = speed;
         self
     }
 
+    pub fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    pub fn region_start(mut self, position: impl Into<FilePosition>) -> Self {
+        self.region_start = Some(position.into());
+        self
+    }
+    pub fn region_end(mut self, position: impl Into<FilePosition>) -> Self {
+        self.region_end = Some(position.into());
+        self
+    }
+    pub fn region_length(mut self, length: impl Into<FilePosition>) -> Self {
+        self.region_length = Some(length.into());
+        self
+    }
+
+    /// Play the audio track with the given index. Use [`EncodedAudio::with_track`] to select
+    /// tracks by language instead.
+    pub fn track(mut self, index: usize) -> Self {
+        self.track = Some(index);
+        self
+    }
+
     pub fn repeat(mut self, count: usize) -> Self {
         self.repeat = count;
         self
@@ -115,11 +495,30 @@ impl FilePlaybackOptions {
         self
     }
 
+    pub fn loop_range(mut self, range: Range<u64>) -> Self {
+        self.loop_start = Some(range.start);
+        self.loop_end = Some(range.end);
+        self
+    }
+    pub fn ignore_embedded_loop(mut self) -> Self {
+        self.use_embedded_loop = false;
+        self
+    }
+    pub fn loop_mode(mut self, mode: LoopMode) -> Self {
+        self.loop_mode = mode;
+        self
+    }
+
     pub fn start_at_time(mut self, sample_time: u64) -> Self {
         self.start_time = Some(sample_time);
         self
     }
 
+    pub fn bus(mut self, bus_id: BusId) -> Self {
+        self.bus = Some(bus_id);
+        self
+    }
+
     pub fn playback_pos_emit_rate(mut self, duration: std::time::Duration) -> Self {
         self.playback_pos_emit_rate = Some(duration);
         self
@@ -144,8 +543,67 @@ impl FilePlaybackOptions {
                 self.speed
             )));
         }
+        if let (Some(loop_start), Some(loop_end)) = (self.loop_start, self.loop_end) {
+            if loop_start >= loop_end {
+                return Err(Error::ParameterError(format!(
+                    "playback options 'loop_start' value '{}' must be smaller than 'loop_end' value '{}'",
+                    loop_start, loop_end
+                )));
+            }
+        }
+        let region_is_empty = match (self.region_start, self.region_end) {
+            (Some(FilePosition::Frames(start)), Some(FilePosition::Frames(end))) => start >= end,
+            (Some(FilePosition::Time(start)), Some(FilePosition::Time(end))) => start >= end,
+            _ => false,
+        };
+        if region_is_empty {
+            return Err(Error::ParameterError(format!(
+                "playback options 'region_start' value '{:?}' must be smaller than 'region_end' value '{:?}'",
+                self.region_start.unwrap(),
+                self.region_end.unwrap()
+            )));
+        }
         Ok(())
     }
+
+    /// Apply the `track` option to the given encoded audio.
+    pub(crate) fn select_track(&self, audio: EncodedAudio) -> EncodedAudio {
+        match self.track {
+            Some(index) => audio.with_track(TrackSelector::Index(index)),
+            None => audio,
+        }
+    }
+
+    /// Resolve the played region in sample frames, using the given file sample rate.
+    /// Open ended regions end at `u64::MAX`.
+    pub(crate) fn resolve_region(&self, sample_rate: u32) -> Range<u64> {
+        let start = self
+            .region_start
+            .map_or(0, |position| position.to_frames(sample_rate));
+        let end = if let Some(end) = self.region_end {
+            end.to_frames(sample_rate)
+        } else if let Some(length) = self.region_length {
+            start.saturating_add(length.to_frames(sample_rate))
+        } else {
+            u64::MAX
+        };
+        start..end.max(start)
+    }
+
+    /// Resolve the loop region in sample frames from the options and the given loop points,
+    /// which are embedded in the file. None means the whole file gets looped.
+    pub(crate) fn resolve_loop_range(
+        &self,
+        embedded_loop: Option<Range<u64>>,
+    ) -> Option<Range<u64>> {
+        if self.loop_start.is_some() || self.loop_end.is_some() {
+            Some(self.loop_start.unwrap_or(0)..self.loop_end.unwrap_or(u64::MAX))
+        } else if self.use_embedded_loop {
+            embedded_loop
+        } else {
+            None
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -154,6 +612,31 @@ impl FilePlaybackOptions {
 pub enum FilePlaybackMessage {
     /// Seek the file source to a new position
     Seek(Duration),
+    /// Seek the file source to a new position, crossfading the output of the old and new
+    /// position with the given duration.
+    SeekWithCrossfade {
+        position: Duration,
+        crossfade: Duration,
+    },
+    /// Smoothly change the source's volume to the given linear volume factor
+    SetVolume(f32),
+    /// Fade the source's fader volume to the given linear volume factor within exactly the
+    /// given duration, using the source's [`FilePlaybackOptions::fade_curve`].
+    FadeVolume { volume: f32, duration: Duration },
+    /// Change the source's playback speed, see [`FilePlaybackOptions::speed`]
+    SetSpeed(f64),
+    /// Change the source's loop region in sample frames. None loops the whole file.
+    /// See [`FilePlaybackOptions::loop_start`] and [`FilePlaybackOptions::loop_end`].
+    SetLoop(Option<Range<u64>>),
+    /// Add the slot's marker or replace an existing marker with the same id. The replaced or
+    /// rejected marker gets moved back into the slot.
+    AddMarker(MarkerSlot),
+    /// Remove the marker with the given id. The removed marker gets moved into the slot.
+    RemoveMarker(u32, MarkerSlot),
+    /// Pause the source with a short fade-out, keeping its playback position
+    Pause,
+    /// Resume a paused source with a short fade-in
+    Resume,
     /// Stop the source
     Stop,
 }
@@ -183,46 +666,108 @@ pub trait FileSource: Source {
 
     /// True when the source played through the entire file, else false.
     fn end_of_track(&self) -> bool;
+
+    /// Markers of the file, sorted by their position. Initially the markers which are embedded
+    /// in the file: WAV cue points or container cues, such as FLAC cue sheets.
+    fn markers(&self) -> Vec<FileMarker>;
+    /// Add a new marker or replace an existing marker with the same id.
+    fn add_marker(&mut self, marker: FileMarker);
 }
 
 // -------------------------------------------------------------------------------------------------
 
 impl Player {
-    /// Play a new file with the given file path and options. See [`FilePlaybackOptions`]
-    /// for more info on which options can be applied.
+    /// Play a new file or in-memory encoded audio with the given options.
+    /// See [`FilePlaybackOptions`] for more info on which options can be applied.
     pub fn play_file(
         &mut self,
-        file_path: &str,
+        audio: impl Into<EncodedAudio>,
         options: FilePlaybackOptions,
     ) -> Result<PlaybackId, Error> {
-        self.play_file_with_context(file_path, options, None)
+        self.play_file_with_context(audio, options, None)
     }
 
-    /// Play a new file with the given file path, options and context.
+    /// Play a new file or in-memory encoded audio with the given options and context.
     /// See [`FilePlaybackOptions`] for more info on which options can be applied.
     pub fn play_file_with_context(
         &mut self,
-        file_path: &str,
+        audio: impl Into<EncodedAudio>,
         options: FilePlaybackOptions,
         context: Option<PlaybackStatusContext>,
     ) -> Result<PlaybackId, Error> {
         // create a stremed or preloaded source, depending on the options and play it
         if options.stream {
             let streamed_source = streamed::StreamedFileSource::new(
-                file_path,
+                audio,
                 Some(self.playback_status_sender()),
                 options,
                 self.output_sample_rate(),
             )?;
-            self.play_file_source_with_context(streamed_source, options.start_time, context)
+            self.play_file_source_on_bus(
+                streamed_source,
+                options.bus.unwrap_or(Self::MAIN_BUS),
+                options.start_time,
+                context,
+            )
         } else {
             let preloaded_source = preloaded::PreloadedFileSource::new(
-                file_path,
+                audio,
                 Some(self.playback_status_sender()),
                 options,
                 self.output_sample_rate(),
             )?;
-            self.play_file_source_with_context(preloaded_source, options.start_time, context)
+            self.play_file_source_on_bus(
+                preloaded_source,
+                options.bus.unwrap_or(Self::MAIN_BUS),
+                options.start_time,
+                context,
+            )
         }
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    /// Encode the given interleaved samples into an in-memory WAV file with the given specs,
+    /// inserting the given raw chunk data in front of the data chunk. Samples get converted to
+    /// 16-bit integers for integer specs.
+    pub(crate) fn encode_wav(
+        spec: hound::WavSpec,
+        samples: &[f32],
+        extra_chunks: &[u8],
+    ) -> Vec<u8> {
+        let mut wav = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut wav, spec).unwrap();
+        for sample in samples {
+            match spec.sample_format {
+                hound::SampleFormat::Float => writer.write_sample(*sample).unwrap(),
+                hound::SampleFormat::Int => writer
+                    .write_sample((*sample * i16::MAX as f32) as i16)
+                    .unwrap(),
+            }
+        }
+        writer.finalize().unwrap();
+        let mut wav = wav.into_inner();
+
+        if !extra_chunks.is_empty() {
+            let data_pos = wav.windows(4).position(|w| w == b"data").unwrap();
+            wav.splice(data_pos..data_pos, extra_chunks.iter().copied());
+            let riff_size = (wav.len() - 8) as u32;
+            wav[4..8].copy_from_slice(&riff_size.to_le_bytes());
+        }
+        wav
+    }
+
+    /// Encode the given samples into an in-memory mono 32-bit float WAV file at 44100 Hz.
+    pub(crate) fn wav_file(samples: &[f32]) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 44100,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        encode_wav(spec, samples, &[])
+    }
+}
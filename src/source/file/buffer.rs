@@ -0,0 +1,243 @@
+use std::sync::Arc;
+
+use super::{preloaded::PreloadedFileSource, FilePlaybackOptions};
+use crate::{
+    player::{PlaybackId, PlaybackStatusContext},
+    Error, Player,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Sample formats which can be converted into a [`PcmBuffer`].
+pub trait PcmSample: Copy {
+    /// Convert the sample to a normalized f32 sample in range [-1, 1].
+    fn to_f32(self) -> f32;
+}
+
+impl PcmSample for f32 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
+
+impl PcmSample for i16 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self as f32 / 32768.0
+    }
+}
+
+impl PcmSample for i32 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        (self as f64 / 2147483648.0) as f32
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Raw, decoded PCM audio, which can be played back via [`Player::play_buffer`] or wrapped into
+/// a [`PreloadedFileSource`] via [`PreloadedFileSource::from_pcm_buffer`].
+///
+/// Samples get converted to interleaved f32 samples once when creating the buffer. Clones are
+/// cheap and share the sample data, so a buffer can be played many times without copying it.
+#[derive(Clone)]
+pub struct PcmBuffer {
+    samples: Arc<Vec<f32>>,
+    channel_count: usize,
+    sample_rate: u32,
+    name: String,
+}
+
+impl PcmBuffer {
+    /// Create a buffer from interleaved samples with the given channel layout and rate.
+    pub fn from_interleaved<S: PcmSample>(
+        samples: &[S],
+        channel_count: usize,
+        sample_rate: u32,
+    ) -> Result<Self, Error> {
+        Self::validate_specs(channel_count, sample_rate)?;
+        if !samples.len().is_multiple_of(channel_count) {
+            return Err(Error::ParameterError(format!(
+                "buffer length {} is not a multiple of the channel count {}",
+                samples.len(),
+                channel_count
+            )));
+        }
+        let mut interleaved = Vec::with_capacity(samples.len() + channel_count);
+        interleaved.extend(samples.iter().map(|s| s.to_f32()));
+        Ok(Self::with_samples(interleaved, channel_count, sample_rate))
+    }
+
+    /// Create a buffer from planar samples: one sample slice per channel, which all must have
+    /// the same length.
+    pub fn from_planar<S: PcmSample, C: AsRef<[S]>>(
+        channels: &[C],
+        sample_rate: u32,
+    ) -> Result<Self, Error> {
+        let channel_count = channels.len();
+        Self::validate_specs(channel_count, sample_rate)?;
+        let frame_count = channels[0].as_ref().len();
+        if channels.iter().any(|c| c.as_ref().len() != frame_count) {
+            return Err(Error::ParameterError(
+                "planar buffer channels must have the same length".to_string(),
+            ));
+        }
+        let mut interleaved = Vec::with_capacity((frame_count + 1) * channel_count);
+        for frame in 0..frame_count {
+            interleaved.extend(channels.iter().map(|c| c.as_ref()[frame].to_f32()));
+        }
+        Ok(Self::with_samples(interleaved, channel_count, sample_rate))
+    }
+
+    /// Set the name which is passed as `path` in [`PlaybackStatusEvent`]s. Empty by default.
+    ///
+    /// [`PlaybackStatusEvent`]: crate::PlaybackStatusEvent
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// The name which is passed as `path` in [`PlaybackStatusEvent`]s.
+    ///
+    /// [`PlaybackStatusEvent`]: crate::PlaybackStatusEvent
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Number of channels in the buffer.
+    pub fn channel_count(&self) -> usize {
+        self.channel_count
+    }
+
+    /// Sample rate of the buffer.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Number of sample frames in the buffer.
+    pub fn frame_count(&self) -> usize {
+        // NB: exclude the padding frame
+        self.samples.len() / self.channel_count - 1
+    }
+
+    /// Shared, interleaved f32 samples, including one extra silent frame at the end.
+    pub(crate) fn samples(&self) -> Arc<Vec<f32>> {
+        Arc::clone(&self.samples)
+    }
+
+    fn with_samples(mut samples: Vec<f32>, channel_count: usize, sample_rate: u32) -> Self {
+        // add one extra empty frame at the end for the cubic resamplers
+        samples.resize(samples.len() + channel_count, 0.0);
+        Self {
+            samples: Arc::new(samples),
+            channel_count,
+            sample_rate,
+            name: String::new(),
+        }
+    }
+
+    fn validate_specs(channel_count: usize, sample_rate: u32) -> Result<(), Error> {
+        if channel_count == 0 {
+            return Err(Error::ParameterError(
+                "buffer needs at least one channel".to_string(),
+            ));
+        }
+        if sample_rate == 0 {
+            return Err(Error::ParameterError(
+                "buffer sample rate must be > 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Player {
+    /// Play a raw PCM buffer with the given options. Buffers always play as preloaded sources,
+    /// so the `stream` option is ignored. All other [`FilePlaybackOptions`] get applied.
+    pub fn play_buffer(
+        &mut self,
+        buffer: &PcmBuffer,
+        options: FilePlaybackOptions,
+    ) -> Result<PlaybackId, Error> {
+        self.play_buffer_with_context(buffer, options, None)
+    }
+
+    /// Play a raw PCM buffer with the given options and context.
+    /// See [`FilePlaybackOptions`] for more info on which options can be applied.
+    pub fn play_buffer_with_context(
+        &mut self,
+        buffer: &PcmBuffer,
+        options: FilePlaybackOptions,
+        context: Option<PlaybackStatusContext>,
+    ) -> Result<PlaybackId, Error> {
+        let source = PreloadedFileSource::from_pcm_buffer(
+            buffer,
+            Some(self.playback_status_sender()),
+            options,
+            self.output_sample_rate(),
+        )?;
+        self.play_file_source_on_bus(
+            source,
+            options.bus.unwrap_or(Self::MAIN_BUS),
+            options.start_time,
+            context,
+        )
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ManualOutput, OutputDevice, PlaybackStatusEvent};
+
+    #[test]
+    fn conversions() {
+        let interleaved = PcmBuffer::from_interleaved(&[0i16, 16384, -32768, 0], 2, 44100).unwrap();
+        let planar = PcmBuffer::from_planar(&[[0i32, i32::MIN], [1 << 30, 0]], 44100).unwrap();
+        for buffer in [&interleaved, &planar] {
+            assert_eq!(buffer.frame_count(), 2);
+            assert_eq!(*buffer.samples(), [0.0, 0.5, -1.0, 0.0, 0.0, 0.0]);
+        }
+
+        assert!(PcmBuffer::from_interleaved(&[0.0f32; 3], 2, 44100).is_err());
+        assert!(PcmBuffer::from_planar(&[vec![0.0f32; 3], vec![0.0; 2]], 44100).is_err());
+        assert!(PcmBuffer::from_planar::<f32, Vec<f32>>(&[], 44100).is_err());
+    }
+
+    #[test]
+    fn play_buffer() {
+        let output = ManualOutput::new(1, 44100);
+        let (status_send, status_recv) = crossbeam_channel::unbounded();
+        let mut player = Player::new(output.sink(), Some(status_send));
+
+        let ramp = (0..64).map(|i| i as f32 / 64.0).collect::<Vec<_>>();
+        let buffer = PcmBuffer::from_planar(&[&ramp], 44100)
+            .unwrap()
+            .with_name("ramp");
+        player
+            .play_buffer(&buffer, FilePlaybackOptions::default().repeat(1))
+            .unwrap();
+
+        // repetitions are gapless
+        let rendered = output.render(128);
+        assert_eq!(rendered[..64], ramp);
+        assert_eq!(rendered[64..], ramp);
+
+        output.render(64);
+        let timeout = std::time::Duration::from_secs(1);
+        let stopped =
+            std::iter::from_fn(|| status_recv.recv_timeout(timeout).ok()).find_map(|event| {
+                match event {
+                    PlaybackStatusEvent::Stopped { path, .. } => Some(path),
+                    _ => None,
+                }
+            });
+        assert_eq!(stopped.as_deref().map(String::as_str), Some("ramp"));
+    }
+}
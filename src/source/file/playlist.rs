@@ -0,0 +1,887 @@
+use std::sync::Arc;
+
+use basedrop::{Handle, Owned};
+use crossbeam_channel::{Receiver, Sender};
+use crossbeam_queue::ArrayQueue;
+
+use super::{
+    preloaded::PreloadedFileSource, streamed::StreamedFileSource, EncodedAudio, EncodedAudioData,
+    FileMarker, FilePlaybackMessage, FilePlaybackOptions, FileSource,
+};
+use crate::{
+    error::Error,
+    player::{BusId, PlaybackId, PlaybackStatusContext, PlaybackStatusEvent},
+    source::{mapped::ChannelMappedSource, Source, SourceTime},
+    utils::{
+        actor::{Act, Actor, ActorHandle},
+        fader::VolumeFader,
+        smoothing::SmoothedValue,
+        unique_usize_id,
+    },
+    Player,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// A file in a [`PlaylistSource`], with the options it gets played with.
+///
+/// All [`FilePlaybackOptions`] except `start_time` and `bus` get applied to the item. Items
+/// need to be files or in-memory buffers, as readers can't be opened again when jumping back.
+pub struct PlaylistItem {
+    audio: EncodedAudio,
+    options: FilePlaybackOptions,
+}
+
+impl PlaylistItem {
+    pub fn new(audio: impl Into<EncodedAudio>, options: FilePlaybackOptions) -> Self {
+        Self {
+            audio: audio.into(),
+            options,
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if matches!(self.audio.data, EncodedAudioData::Reader(_)) {
+            return Err(Error::ParameterError(format!(
+                "playlist item '{}' is a reader, which can't be played more than once",
+                self.audio.name()
+            )));
+        }
+        self.options.validate()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Events to control the worker thread of a [`PlaylistSource`].
+pub(crate) enum PlaylistWorkerMessage {
+    /// The source started playing the track with the given index
+    TrackStarted(usize),
+    /// Jump to the track after the current one
+    Next,
+    /// Jump to the track before the current one
+    Previous,
+    /// Jump to the track with the given index
+    Jump(usize),
+    /// Add a new item to the end of the playlist
+    Append(Box<PlaylistItem>),
+    /// Drop a track which is no longer used by the source
+    Drop(PlaylistTrack),
+    /// Stop the worker
+    Stop,
+}
+
+/// Events from the worker thread to the [`PlaylistSource`].
+enum PlaylistTrackMessage {
+    /// The track which plays after the track with the given index, or None when the playlist
+    /// ends after it.
+    Prepared {
+        after: usize,
+        track: Option<PlaylistTrack>,
+    },
+    /// Stop the current track and continue with the given track.
+    Jump(PlaylistTrack),
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// An opened playlist item, converted to the playlist's channel layout.
+pub(crate) struct PlaylistTrack {
+    index: usize,
+    path: Arc<String>,
+    source: Box<dyn Source>,
+    message_queue: Arc<ArrayQueue<FilePlaybackMessage>>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A [`FileSource`] which plays a list of preloaded or streamed files gaplessly, one after
+/// another: the next track starts on the exact frame where the previous track ended.
+///
+/// Tracks get opened in a worker thread, so the next track's decoder is ready before the
+/// current track ends. Events of the tracks, such as positions or reached markers, get reported
+/// with the playlist's id and context. A [`PlaybackStatusEvent::PlaylistTrackChanged`] event is
+/// sent when a new track starts playing.
+///
+/// Volume changes apply to the whole playlist. Seeking, speed, loop and marker changes apply to
+/// the currently playing track. Use [`Player::play_playlist`] or [`Player::play_playlist_source`]
+/// to play playlists, so they can be controlled via [`Player::playlist_next`],
+/// [`Player::playlist_previous`], [`Player::playlist_jump`] and [`Player::playlist_append`].
+pub struct PlaylistSource {
+    worker: ActorHandle<PlaylistWorkerMessage>,
+    playlist_id: PlaybackId,
+    current: Option<PlaylistTrack>,
+    current_index: usize,
+    current_path: Arc<String>,
+    current_position: u64,
+    next: Option<PlaylistTrack>,
+    jump: Option<PlaylistTrack>,
+    ends_after_current: bool,
+    track_queue: Arc<ArrayQueue<PlaylistTrackMessage>>,
+    track_status_recv: Receiver<PlaybackStatusEvent>,
+    volume: SmoothedValue,
+    volume_fader: VolumeFader,
+    channel_count: usize,
+    output_sample_rate: u32,
+    playback_message_queue: Arc<ArrayQueue<FilePlaybackMessage>>,
+    playback_status_send: Option<Sender<PlaybackStatusEvent>>,
+    playback_status_context: Option<PlaybackStatusContext>,
+    pending_markers: Vec<FileMarker>,
+    is_started: bool,
+    is_paused: bool,
+    is_stopping: bool,
+    end_of_playlist: bool,
+    playback_finished: bool,
+}
+
+impl PlaylistSource {
+    /// Create a new playlist, which plays the given items in the given output channel layout
+    /// and sample rate. The first item gets opened right away, all following items while
+    /// playing.
+    pub fn new(
+        items: Vec<PlaylistItem>,
+        playback_status_send: Option<Sender<PlaybackStatusEvent>>,
+        output_channel_count: usize,
+        output_sample_rate: u32,
+    ) -> Result<Self, Error> {
+        // validate items
+        if items.is_empty() {
+            return Err(Error::ParameterError(
+                "playlists need at least one item".to_string(),
+            ));
+        }
+        for item in &items {
+            item.validate()?;
+        }
+        // create channels to receive track events and opened tracks
+        const TRACK_STATUS_CAPACITY: usize = 256;
+        let (track_status_send, track_status_recv) =
+            crossbeam_channel::bounded(TRACK_STATUS_CAPACITY);
+        let track_queue = Arc::new(ArrayQueue::new(16));
+        let playback_message_queue = Arc::new(ArrayQueue::new(128));
+
+        // volume fades apply to the whole playlist, using the first item's curve
+        let mut volume_fader = VolumeFader::new(output_channel_count, output_sample_rate);
+        volume_fader.set_curve(items[0].options.fade_curve);
+
+        // open the first track and spawn the worker, which opens all following tracks
+        let worker = PlaylistWorker {
+            items,
+            current_index: 0,
+            ends_after_current: false,
+            channel_count: output_channel_count,
+            output_sample_rate,
+            track_status_send,
+            track_queue: Arc::clone(&track_queue),
+            playback_message_queue: Arc::clone(&playback_message_queue),
+        };
+        let first_track = worker.open_track(0)?;
+        let current_path = Arc::clone(&first_track.path);
+        let worker = PlaylistWorker::spawn_with_default_cap("audio_playlist", move |_| worker);
+
+        Ok(Self {
+            worker,
+            playlist_id: unique_usize_id(),
+            current: Some(first_track),
+            current_index: 0,
+            current_path,
+            current_position: 0,
+            next: None,
+            jump: None,
+            ends_after_current: false,
+            track_queue,
+            track_status_recv,
+            volume: SmoothedValue::new(1.0, output_sample_rate),
+            volume_fader,
+            channel_count: output_channel_count,
+            output_sample_rate,
+            playback_message_queue,
+            playback_status_send,
+            playback_status_context: None,
+            pending_markers: Vec::new(),
+            is_started: false,
+            is_paused: false,
+            is_stopping: false,
+            end_of_playlist: false,
+            playback_finished: false,
+        })
+    }
+
+    /// Index of the currently playing item.
+    pub fn current_index(&self) -> usize {
+        self.current_index
+    }
+
+    /// Worker channel, which gets used by the player to control the playlist.
+    pub(crate) fn worker_sender(&self) -> Sender<PlaylistWorkerMessage> {
+        self.worker.sender()
+    }
+
+    /// Send markers, which got added before playing the playlist, to the current track.
+    /// Replaced or rejected markers get dropped in the given collector.
+    pub(crate) fn send_pending_markers(&mut self, collector_handle: &Handle) {
+        for marker in std::mem::take(&mut self.pending_markers) {
+            let slot = Owned::new(collector_handle, Some(marker));
+            self.send_to_current_track(FilePlaybackMessage::AddMarker(slot));
+        }
+    }
+
+    fn send_status_event(&self, event: PlaybackStatusEvent) {
+        if let Some(sender) = &self.playback_status_send {
+            if let Err(err) = sender.try_send(event) {
+                log::warn!("failed to send playback event: {}", err)
+            }
+        }
+    }
+
+    /// Forward status events of the tracks with the playlist's id and context.
+    fn relay_track_events(&mut self) {
+        while let Ok(event) = self.track_status_recv.try_recv() {
+            let id = self.playlist_id;
+            let context = self.playback_status_context.clone();
+            let event = match event {
+                PlaybackStatusEvent::Position { path, position, .. } => {
+                    PlaybackStatusEvent::Position {
+                        id,
+                        path,
+                        context,
+                        position,
+                    }
+                }
+                PlaybackStatusEvent::Paused { path, .. } => {
+                    PlaybackStatusEvent::Paused { id, path, context }
+                }
+                PlaybackStatusEvent::Resumed { path, .. } => {
+                    PlaybackStatusEvent::Resumed { id, path, context }
+                }
+                PlaybackStatusEvent::MarkerReached {
+                    path,
+                    marker_id,
+                    label,
+                    ..
+                } => PlaybackStatusEvent::MarkerReached {
+                    id,
+                    path,
+                    context,
+                    marker_id,
+                    label,
+                },
+                PlaybackStatusEvent::MetadataChanged { path, metadata, .. } => {
+                    PlaybackStatusEvent::MetadataChanged {
+                        id,
+                        path,
+                        context,
+                        metadata,
+                    }
+                }
+                // track changes and the playlist's end are reported by the playlist itself
+                PlaybackStatusEvent::Stopped { .. }
+                | PlaybackStatusEvent::PlaylistTrackChanged { .. } => continue,
+            };
+            self.send_status_event(event);
+        }
+    }
+
+    /// Pass a track which no longer is used to the worker, to avoid deallocating it here.
+    fn drop_track(&self, track: PlaylistTrack) {
+        if let Err(err) = self.worker.try_send(PlaylistWorkerMessage::Drop(track)) {
+            log::warn!("failed to send playlist drop event: {}", err);
+        }
+    }
+
+    fn start_track(&mut self, track: PlaylistTrack) {
+        if self.is_paused
+            && track
+                .message_queue
+                .push(FilePlaybackMessage::Pause)
+                .is_err()
+        {
+            log::warn!("failed to pause playlist track");
+        }
+        self.current_index = track.index;
+        self.current_path = Arc::clone(&track.path);
+        self.current_position = 0;
+        self.ends_after_current = false;
+        self.current = Some(track);
+        self.announce_track();
+    }
+
+    fn announce_track(&mut self) {
+        self.is_started = true;
+        if let Err(err) = self
+            .worker
+            .try_send(PlaylistWorkerMessage::TrackStarted(self.current_index))
+        {
+            log::warn!("failed to send playlist track event: {}", err);
+        }
+        self.send_status_event(PlaybackStatusEvent::PlaylistTrackChanged {
+            id: self.playlist_id,
+            context: self.playback_status_context.clone(),
+            path: Arc::clone(&self.current_path),
+            index: self.current_index,
+        });
+    }
+
+    /// Replace the finished current track with the next one. Returns true when a new track
+    /// started playing.
+    fn start_next_track(&mut self) -> bool {
+        self.relay_track_events();
+        if let Some(track) = self.current.take() {
+            self.drop_track(track);
+        }
+        if self.is_stopping {
+            self.finish(false);
+            return false;
+        }
+        let track = match self.jump.take() {
+            Some(track) => {
+                if let Some(next) = self.next.take() {
+                    self.drop_track(next);
+                }
+                Some(track)
+            }
+            None => self.next.take(),
+        };
+        match track {
+            Some(track) => {
+                self.start_track(track);
+                true
+            }
+            None => {
+                // else wait until the worker opened the next track
+                if self.ends_after_current {
+                    self.finish(true);
+                }
+                false
+            }
+        }
+    }
+
+    fn finish(&mut self, end_of_playlist: bool) {
+        self.relay_track_events();
+        self.end_of_playlist = end_of_playlist;
+        self.playback_finished = true;
+        self.send_status_event(PlaybackStatusEvent::Stopped {
+            id: self.playlist_id,
+            context: self.playback_status_context.clone(),
+            path: Arc::clone(&self.current_path),
+            exhausted: end_of_playlist,
+        });
+    }
+
+    /// Send the given playback message to the current track, if any.
+    fn send_to_current_track(&self, message: FilePlaybackMessage) {
+        if let Some(track) = &self.current {
+            if track.message_queue.push(message).is_err() {
+                log::warn!("failed to send playback event to playlist track");
+            }
+        }
+    }
+
+    fn process_playback_messages(&mut self) {
+        while let Some(message) = self.playback_message_queue.pop() {
+            match message {
+                FilePlaybackMessage::SetVolume(volume) => {
+                    self.volume.set_target(volume);
+                }
+                FilePlaybackMessage::FadeVolume { volume, duration } => {
+                    self.volume_fader.fade_to(volume, duration);
+                }
+                FilePlaybackMessage::Pause => {
+                    self.is_paused = true;
+                    self.send_to_current_track(message);
+                }
+                FilePlaybackMessage::Resume => {
+                    self.is_paused = false;
+                    self.send_to_current_track(message);
+                }
+                FilePlaybackMessage::Stop => {
+                    self.is_stopping = true;
+                    if self.current.is_some() {
+                        self.send_to_current_track(message);
+                    } else if !self.playback_finished {
+                        self.finish(false);
+                    }
+                }
+                FilePlaybackMessage::Seek(_)
+                | FilePlaybackMessage::SeekWithCrossfade { .. }
+                | FilePlaybackMessage::SetSpeed(_)
+                | FilePlaybackMessage::SetLoop(_)
+                | FilePlaybackMessage::AddMarker(_)
+                | FilePlaybackMessage::RemoveMarker(..) => {
+                    self.send_to_current_track(message);
+                }
+            }
+        }
+    }
+
+    fn process_track_messages(&mut self) {
+        while let Some(message) = self.track_queue.pop() {
+            match message {
+                PlaylistTrackMessage::Prepared { after, track } => {
+                    if after != self.current_index || self.playback_finished {
+                        // outdated: the current track changed in the meantime
+                        if let Some(track) = track {
+                            self.drop_track(track);
+                        }
+                        continue;
+                    }
+                    self.ends_after_current = track.is_none();
+                    if let Some(next) = self.next.take() {
+                        self.drop_track(next);
+                    }
+                    self.next = track;
+                    if self.current.is_none() && self.jump.is_none() {
+                        // the current track already ended
+                        self.start_next_track();
+                    }
+                }
+                PlaylistTrackMessage::Jump(track) => {
+                    if self.playback_finished || self.is_stopping {
+                        self.drop_track(track);
+                        continue;
+                    }
+                    if let Some(jump) = self.jump.replace(track) {
+                        self.drop_track(jump);
+                    }
+                    if self.current.is_some() {
+                        // stop the current track: the jump happens when it faded out
+                        self.send_to_current_track(FilePlaybackMessage::Stop);
+                    } else {
+                        self.start_next_track();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl FileSource for PlaylistSource {
+    fn playback_id(&self) -> PlaybackId {
+        self.playlist_id
+    }
+
+    fn playback_message_queue(&self) -> Arc<ArrayQueue<FilePlaybackMessage>> {
+        Arc::clone(&self.playback_message_queue)
+    }
+
+    fn playback_status_sender(&self) -> Option<Sender<PlaybackStatusEvent>> {
+        self.playback_status_send.clone()
+    }
+    fn set_playback_status_sender(&mut self, sender: Option<Sender<PlaybackStatusEvent>>) {
+        self.playback_status_send = sender;
+    }
+
+    fn playback_status_context(&self) -> Option<PlaybackStatusContext> {
+        self.playback_status_context.clone()
+    }
+    fn set_playback_status_context(&mut self, context: Option<PlaybackStatusContext>) {
+        self.playback_status_context = context;
+    }
+
+    /// The playlist's total length is not known in advance, so this always returns None.
+    fn total_frames(&self) -> Option<u64> {
+        None
+    }
+
+    /// Position in output sample frames within the currently playing track.
+    fn current_frame_position(&self) -> u64 {
+        self.current_position
+    }
+
+    fn end_of_track(&self) -> bool {
+        self.end_of_playlist
+    }
+
+    /// Playlists don't expose the markers of their tracks, but report the tracks' reached
+    /// markers, so this always returns an empty list.
+    fn markers(&self) -> Vec<FileMarker> {
+        Vec::new()
+    }
+    /// Adds the marker to the first track, when playing the playlist via
+    /// [`Player::play_playlist_source`].
+    fn add_marker(&mut self, marker: FileMarker) {
+        self.pending_markers.push(marker);
+    }
+}
+
+impl Source for PlaylistSource {
+    fn write(&mut self, output: &mut [f32], time: &SourceTime) -> usize {
+        // consume playback messages and tracks from the worker
+        self.process_playback_messages();
+        self.process_track_messages();
+
+        // return empty handed when playback finished
+        if self.playback_finished {
+            return 0;
+        }
+
+        // report the initial track
+        if !self.is_started {
+            self.announce_track();
+        }
+
+        // write the current track and continue with the next one when it ended
+        let mut total_written = 0;
+        while total_written < output.len() {
+            let Some(track) = &mut self.current else {
+                // waiting for the next track
+                break;
+            };
+            let track_time = time.with_added_frames((total_written / self.channel_count) as u64);
+            let written = track
+                .source
+                .write(&mut output[total_written..], &track_time);
+            total_written += written;
+            self.current_position += (written / self.channel_count) as u64;
+            if track.source.is_exhausted() {
+                if !self.start_next_track() {
+                    break;
+                }
+            } else if written == 0 {
+                // paused or not yet decoded
+                break;
+            }
+        }
+
+        // apply volume parameter
+        self.volume
+            .apply(&mut output[..total_written], self.channel_count);
+        self.volume_fader.process(&mut output[..total_written]);
+
+        // forward track events
+        self.relay_track_events();
+
+        total_written
+    }
+
+    fn channel_count(&self) -> usize {
+        self.channel_count
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.output_sample_rate
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.playback_finished
+    }
+}
+
+impl Drop for PlaylistSource {
+    fn drop(&mut self) {
+        // ignore error: channel maybe already is disconnected
+        let _ = self.worker.send(PlaylistWorkerMessage::Stop);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Opens the tracks of a [`PlaylistSource`], before they get played.
+struct PlaylistWorker {
+    items: Vec<PlaylistItem>,
+    current_index: usize,
+    ends_after_current: bool,
+    channel_count: usize,
+    output_sample_rate: u32,
+    track_status_send: Sender<PlaybackStatusEvent>,
+    track_queue: Arc<ArrayQueue<PlaylistTrackMessage>>,
+    playback_message_queue: Arc<ArrayQueue<FilePlaybackMessage>>,
+}
+
+impl Actor for PlaylistWorker {
+    type Message = PlaylistWorkerMessage;
+    type Error = Error;
+
+    fn handle(&mut self, msg: PlaylistWorkerMessage) -> Result<Act<Self>, Self::Error> {
+        match msg {
+            PlaylistWorkerMessage::TrackStarted(index) => {
+                self.current_index = index;
+                self.prepare_next_track();
+            }
+            PlaylistWorkerMessage::Next => {
+                if self.current_index + 1 < self.items.len() {
+                    self.jump(self.current_index + 1);
+                } else {
+                    // stop when skipping the last track
+                    if self
+                        .playback_message_queue
+                        .push(FilePlaybackMessage::Stop)
+                        .is_err()
+                    {
+                        log::warn!("failed to send playlist stop event");
+                    }
+                }
+            }
+            PlaylistWorkerMessage::Previous => {
+                self.jump(self.current_index.saturating_sub(1));
+            }
+            PlaylistWorkerMessage::Jump(index) => {
+                if index < self.items.len() {
+                    self.jump(index);
+                } else {
+                    log::warn!("playlist has no item #{index}");
+                }
+            }
+            PlaylistWorkerMessage::Append(item) => {
+                self.items.push(*item);
+                if self.ends_after_current {
+                    self.prepare_next_track();
+                }
+            }
+            PlaylistWorkerMessage::Drop(track) => {
+                drop(track);
+            }
+            PlaylistWorkerMessage::Stop => {
+                return Ok(Act::Shutdown);
+            }
+        }
+        Ok(Act::Continue)
+    }
+}
+
+impl PlaylistWorker {
+    fn open_track(&self, index: usize) -> Result<PlaylistTrack, Error> {
+        let item = &self.items[index];
+        let audio = item.audio.try_clone().ok_or_else(|| {
+            Error::ParameterError("playlist readers can't be opened again".to_string())
+        })?;
+        let status_send = Some(self.track_status_send.clone());
+        let track = if item.options.stream {
+            let source =
+                StreamedFileSource::new(audio, status_send, item.options, self.output_sample_rate)?;
+            self.new_track(index, source)
+        } else {
+            let source = PreloadedFileSource::new(
+                audio,
+                status_send,
+                item.options,
+                self.output_sample_rate,
+            )?;
+            self.new_track(index, source)
+        };
+        Ok(track)
+    }
+
+    /// Wrap the given file source into a track, mapping it to the playlist's channel layout.
+    fn new_track(&self, index: usize, source: impl FileSource) -> PlaylistTrack {
+        let path = Arc::new(self.items[index].audio.name().to_string());
+        let message_queue = source.playback_message_queue();
+        let source: Box<dyn Source> = if source.channel_count() != self.channel_count {
+            Box::new(ChannelMappedSource::new(source, self.channel_count))
+        } else {
+            Box::new(source)
+        };
+        PlaylistTrack {
+            index,
+            path,
+            source,
+            message_queue,
+        }
+    }
+
+    /// Open the first playable item after the given index. Broken items get skipped.
+    fn open_track_after(&self, index: usize) -> Option<PlaylistTrack> {
+        for next_index in index + 1..self.items.len() {
+            match self.open_track(next_index) {
+                Ok(track) => return Some(track),
+                Err(err) => log::warn!("failed to open playlist item #{next_index}: {err}"),
+            }
+        }
+        None
+    }
+
+    fn prepare_next_track(&mut self) {
+        let track = self.open_track_after(self.current_index);
+        self.ends_after_current = track.is_none();
+        self.push_track_message(PlaylistTrackMessage::Prepared {
+            after: self.current_index,
+            track,
+        });
+    }
+
+    fn jump(&mut self, index: usize) {
+        match self.open_track(index) {
+            Ok(track) => self.push_track_message(PlaylistTrackMessage::Jump(track)),
+            Err(err) => log::warn!("failed to open playlist item #{index}: {err}"),
+        }
+    }
+
+    fn push_track_message(&self, message: PlaylistTrackMessage) {
+        if self.track_queue.push(message).is_err() {
+            log::warn!("playlist track queue is full. track event got skipped!");
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Player {
+    /// Play the given items as a gapless playlist on the main bus. See [`PlaylistSource`].
+    pub fn play_playlist(&mut self, items: Vec<PlaylistItem>) -> Result<PlaybackId, Error> {
+        let playlist = PlaylistSource::new(
+            items,
+            Some(self.playback_status_sender()),
+            self.output_channel_count(),
+            self.output_sample_rate(),
+        )?;
+        self.play_playlist_source(playlist, None)
+    }
+
+    /// Play a self created playlist source.
+    pub fn play_playlist_source(
+        &mut self,
+        playlist: PlaylistSource,
+        start_time: Option<u64>,
+    ) -> Result<PlaybackId, Error> {
+        self.play_playlist_source_on_bus(playlist, Self::MAIN_BUS, start_time, None)
+    }
+
+    /// Play a self created playlist source on the given mixer bus, with the given playback
+    /// status context.
+    pub fn play_playlist_source_on_bus(
+        &mut self,
+        mut playlist: PlaylistSource,
+        bus_id: BusId,
+        start_time: Option<u64>,
+        context: Option<PlaybackStatusContext>,
+    ) -> Result<PlaybackId, Error> {
+        playlist.send_pending_markers(self.collector_handle());
+        let worker_sender = playlist.worker_sender();
+        let playback_id = self.play_file_source_on_bus(playlist, bus_id, start_time, context)?;
+        self.set_playlist_sender(playback_id, worker_sender);
+        Ok(playback_id)
+    }
+
+    /// Skip to the next track of a playing playlist. Skipping the last track stops the playlist.
+    pub fn playlist_next(&mut self, playback_id: PlaybackId) -> Result<(), Error> {
+        self.send_playlist_message(playback_id, PlaylistWorkerMessage::Next)
+    }
+
+    /// Jump to the previous track of a playing playlist. Restarts the first track, when the
+    /// first track is playing.
+    pub fn playlist_previous(&mut self, playback_id: PlaybackId) -> Result<(), Error> {
+        self.send_playlist_message(playback_id, PlaylistWorkerMessage::Previous)
+    }
+
+    /// Jump to the track with the given index of a playing playlist. Invalid indices are
+    /// ignored.
+    pub fn playlist_jump(&mut self, playback_id: PlaybackId, index: usize) -> Result<(), Error> {
+        self.send_playlist_message(playback_id, PlaylistWorkerMessage::Jump(index))
+    }
+
+    /// Add a new item to the end of a playing playlist.
+    pub fn playlist_append(
+        &mut self,
+        playback_id: PlaybackId,
+        item: PlaylistItem,
+    ) -> Result<(), Error> {
+        item.validate()?;
+        self.send_playlist_message(playback_id, PlaylistWorkerMessage::Append(Box::new(item)))
+    }
+
+    fn send_playlist_message(
+        &mut self,
+        playback_id: PlaybackId,
+        message: PlaylistWorkerMessage,
+    ) -> Result<(), Error> {
+        let sender = self.playlist_sender(playback_id)?;
+        if sender.try_send(message).is_err() {
+            log::warn!("failed to send playlist command");
+            return Err(Error::SendError);
+        }
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{source::file::test_utils::wav_file, ManualOutput, OutputDevice};
+
+    #[test]
+    fn gapless() {
+        let output = ManualOutput::new(1, 44100);
+        let (status_send, status_recv) = crossbeam_channel::unbounded();
+        let mut player = Player::new(output.sink(), Some(status_send));
+
+        let ramp = (1..=3000).map(|i| i as f32 / 4096.0).collect::<Vec<_>>();
+        let items = vec![
+            PlaylistItem::new(wav_file(&ramp[..1000]), Default::default()),
+            PlaylistItem::new(
+                wav_file(&ramp[1000..2000]),
+                FilePlaybackOptions::default().streamed(),
+            ),
+            PlaylistItem::new(wav_file(&ramp[2000..]), Default::default()),
+        ];
+        let playlist_id = player.play_playlist(items).unwrap();
+
+        let mut rendered = Vec::new();
+        for _ in 0..40 {
+            rendered.extend(output.render(100));
+            // give the worker some time to open the next tracks
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(rendered[..ramp.len()], ramp);
+        assert!(rendered[ramp.len()..].iter().all(|s| *s == 0.0));
+
+        let timeout = Duration::from_secs(1);
+        let events = std::iter::from_fn(|| status_recv.recv_timeout(timeout).ok())
+            .filter_map(|event| match event {
+                PlaybackStatusEvent::PlaylistTrackChanged { id, index, .. } => {
+                    assert_eq!(id, playlist_id);
+                    Some(index as isize)
+                }
+                PlaybackStatusEvent::Stopped { id, exhausted, .. } => {
+                    assert_eq!(id, playlist_id);
+                    assert!(exhausted);
+                    Some(-1)
+                }
+                _ => None,
+            })
+            .take(4)
+            .collect::<Vec<_>>();
+        assert_eq!(events, [0, 1, 2, -1]);
+    }
+
+    #[test]
+    fn commands() {
+        let output = ManualOutput::new(1, 44100);
+        let (status_send, status_recv) = crossbeam_channel::unbounded();
+        let mut player = Player::new(output.sink(), Some(status_send));
+
+        let items = (0..3)
+            .map(|_| PlaylistItem::new(wav_file(&[0.5; 44100]), Default::default()))
+            .collect();
+        let playlist_id = player.play_playlist(items).unwrap();
+        let next_event = |output: &ManualOutput| {
+            for _ in 0..1000 {
+                output.render(256);
+                match status_recv.recv_timeout(Duration::from_millis(1)) {
+                    Ok(PlaybackStatusEvent::PlaylistTrackChanged { index, .. }) => {
+                        return Some(index)
+                    }
+                    Ok(PlaybackStatusEvent::Stopped { exhausted, .. }) => {
+                        assert!(!exhausted);
+                        return None;
+                    }
+                    _ => continue,
+                }
+            }
+            panic!("playlist did not change its track");
+        };
+        assert_eq!(next_event(&output), Some(0));
+        player.playlist_jump(playlist_id, 2).unwrap();
+        assert_eq!(next_event(&output), Some(2));
+        player.playlist_previous(playlist_id).unwrap();
+        assert_eq!(next_event(&output), Some(1));
+        player.playlist_next(playlist_id).unwrap();
+        assert_eq!(next_event(&output), Some(2));
+        player.playlist_next(playlist_id).unwrap();
+        assert_eq!(next_event(&output), None);
+    }
+}
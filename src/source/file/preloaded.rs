@@ -1,4 +1,6 @@
 use std::{
+    f32::consts::FRAC_PI_2,
+    ops::Range,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -7,7 +9,10 @@ use crossbeam_channel::Sender;
 use crossbeam_queue::ArrayQueue;
 use symphonia::core::audio::SampleBuffer;
 
-use super::{FilePlaybackMessage, FilePlaybackOptions, FileSource};
+use super::{
+    buffer::PcmBuffer, insert_marker, preallocated_markers, remove_marker, EncodedAudio,
+    FileMarker, FilePlaybackMessage, FilePlaybackOptions, FileSource, LoopMode,
+};
 use crate::{
     error::Error,
     source::{
@@ -18,10 +23,11 @@ use crate::{
     utils::{
         buffer::TempBuffer,
         decoder::AudioDecoder,
-        fader::{FaderState, VolumeFader},
+        fader::{PauseFader, SeekFader, VolumeFader},
         resampler::{
             cubic::CubicResampler, rubato::RubatoResampler, AudioResampler, ResamplingSpecs,
         },
+        smoothing::SmoothedValue,
         unique_usize_id,
     },
 };
@@ -37,10 +43,19 @@ use crate::{
 pub struct PreloadedFileSource {
     file_id: PlaybackId,
     file_path: Arc<String>,
-    volume: f32,
+    volume: SmoothedValue,
     volume_fader: VolumeFader,
+    pause_fader: PauseFader,
+    seek_fader: SeekFader,
     fade_out_duration: Option<Duration>,
     repeat: usize,
+    loop_range: Option<Range<u64>>,
+    loop_mode: LoopMode,
+    playing_backwards: bool,
+    reverse: bool,
+    region: Range<usize>,
+    padding_frame_excluded: bool,
+    markers: Vec<FileMarker>,
     buffer: Arc<Vec<f32>>,
     buffer_sample_rate: u32,
     buffer_channel_count: usize,
@@ -58,15 +73,17 @@ pub struct PreloadedFileSource {
 
 impl PreloadedFileSource {
     pub fn new(
-        file_path: &str,
+        audio: impl Into<EncodedAudio>,
         playback_status_send: Option<Sender<PlaybackStatusEvent>>,
         options: FilePlaybackOptions,
         output_sample_rate: u32,
     ) -> Result<Self, Error> {
         // validate options
         options.validate()?;
+        let audio = options.select_track(audio.into());
+        let file_path = audio.name().to_string();
         // create decoder and get buffe rsignal specs
-        let mut audio_decoder = AudioDecoder::new(file_path.to_string())?;
+        let mut audio_decoder = AudioDecoder::new(audio)?;
         let buffer_sample_rate = audio_decoder.signal_spec().rate;
         let buffer_channel_count = audio_decoder.signal_spec().channels.count();
 
@@ -100,15 +117,20 @@ impl PreloadedFileSource {
             }
         }
 
-        Self::with_buffer(
+        let mut source = Self::with_buffer(
             buffer,
             buffer_sample_rate,
             buffer_channel_count,
-            file_path,
+            &file_path,
             playback_status_send,
             options,
             output_sample_rate,
-        )
+        )?;
+        // don't play the padding frame, e.g. when playing in reverse or gaplessly
+        source.exclude_padding_frame();
+        source.loop_range = options.resolve_loop_range(audio_decoder.loop_points());
+        source.markers = preallocated_markers(&audio_decoder.markers());
+        Ok(source)
     }
 
     /// Create a new preloaded file source with the given decoded and possibly shared file buffer.
@@ -127,7 +149,8 @@ impl PreloadedFileSource {
         let playback_message_queue = Arc::new(ArrayQueue::new(128));
 
         // create new volume fader
-        let mut volume_fader = VolumeFader::new(buffer_channel_count, buffer_sample_rate);
+        let mut volume_fader = VolumeFader::new(buffer_channel_count, output_sample_rate);
+        volume_fader.set_curve(options.fade_curve);
         if let Some(duration) = options.fade_in_duration {
             if !duration.is_zero() {
                 volume_fader.start_fade_in(duration);
@@ -147,14 +170,27 @@ impl PreloadedFileSource {
             ResamplingQuality::HighQuality => Box::new(RubatoResampler::new(resampler_specs)?),
             ResamplingQuality::Default => Box::new(CubicResampler::new(resampler_specs)?),
         };
-        let resample_input_buffer_size = resampler.max_input_buffer_size().unwrap_or(0);
+        const DEFAULT_CHUNK_FRAMES: usize = 256;
+        let resample_input_buffer_size = resampler
+            .max_input_buffer_size()
+            .unwrap_or(DEFAULT_CHUNK_FRAMES * buffer_channel_count);
         let resampler_input_buffer = TempBuffer::new(resample_input_buffer_size);
 
+        // resolve played region in buffer samples
+        let region = {
+            let region = options.resolve_region(buffer_sample_rate);
+            let channel_count = buffer_channel_count as u64;
+            let buffer_len = buffer.len() as u64;
+            let start = region.start.saturating_mul(channel_count).min(buffer_len);
+            let end = region.end.saturating_mul(channel_count).min(buffer_len);
+            start as usize..end as usize
+        };
+
         // create new unique file id
         let file_id = unique_usize_id();
 
         // copy remaining options which are applied while playback
-        let volume = options.volume;
+        let volume = SmoothedValue::new(options.volume, output_sample_rate);
         let fade_out_duration = options.fade_out_duration;
         let playback_pos_emit_rate = options.playback_pos_emit_rate;
 
@@ -163,8 +199,17 @@ impl PreloadedFileSource {
             file_path: Arc::new(file_path.into()),
             volume,
             volume_fader,
+            pause_fader: PauseFader::new(output_sample_rate),
+            seek_fader: SeekFader::new(buffer_channel_count, output_sample_rate),
             fade_out_duration,
             repeat: options.repeat,
+            loop_range: options.resolve_loop_range(None),
+            loop_mode: options.loop_mode,
+            playing_backwards: false,
+            reverse: options.reverse,
+            region,
+            padding_frame_excluded: false,
+            markers: preallocated_markers(&[]),
             buffer,
             buffer_sample_rate,
             buffer_channel_count,
@@ -181,13 +226,35 @@ impl PreloadedFileSource {
         })
     }
 
+    /// Create a new preloaded source which plays the given raw PCM buffer. The buffer's
+    /// samples are shared and not copied.
+    pub fn from_pcm_buffer(
+        buffer: &PcmBuffer,
+        playback_status_send: Option<Sender<PlaybackStatusEvent>>,
+        options: FilePlaybackOptions,
+        output_sample_rate: u32,
+    ) -> Result<Self, Error> {
+        let mut source = Self::with_buffer(
+            buffer.samples(),
+            buffer.sample_rate(),
+            buffer.channel_count(),
+            buffer.name(),
+            playback_status_send,
+            options,
+            output_sample_rate,
+        )?;
+        // don't play the padding frame, e.g. when playing in reverse or gaplessly
+        source.exclude_padding_frame();
+        Ok(source)
+    }
+
     /// Create a copy of this preloaded source with the given playback options.
     pub fn clone(
         &self,
         options: FilePlaybackOptions,
         output_sample_rate: u32,
     ) -> Result<Self, Error> {
-        Self::with_buffer(
+        let mut source = Self::with_buffer(
             self.buffer(),
             self.buffer_sample_rate(),
             self.buffer_channel_count(),
@@ -195,16 +262,39 @@ impl PreloadedFileSource {
             self.playback_status_send.clone(),
             options,
             output_sample_rate,
-        )
+        )?;
+        if self.padding_frame_excluded {
+            source.exclude_padding_frame();
+        }
+        source.markers = preallocated_markers(&self.markers);
+        Ok(source)
+    }
+
+    /// Exclude the silent padding frame, which [`Self::new`] and [`PcmBuffer`] append to their
+    /// sample buffers for the resamplers, from the played region.
+    fn exclude_padding_frame(&mut self) {
+        let buffer_end = self.buffer.len().saturating_sub(self.buffer_channel_count);
+        self.region.end = self.region.end.min(buffer_end);
+        self.region.start = self.region.start.min(self.region.end);
+        self.padding_frame_excluded = true;
     }
 
     /// Access to the playback volume option
     pub fn volume(&self) -> f32 {
-        self.volume
+        self.volume.target()
     }
     /// Set a new  playback volume option
     pub fn set_volume(&mut self, volume: f32) {
-        self.volume = volume
+        self.volume.reset(volume)
+    }
+
+    /// Loop region in sample frames, which is used when repeating. None loops the whole file.
+    pub fn loop_range(&self) -> Option<Range<u64>> {
+        self.loop_range.clone()
+    }
+    /// Set a new loop region in sample frames. None loops the whole file.
+    pub fn set_loop_range(&mut self, range: Option<Range<u64>>) {
+        self.loop_range = range;
     }
 
     /// Get sample rate of our raw preloaded file's buffer
@@ -228,6 +318,195 @@ impl PreloadedFileSource {
         }
     }
 
+    fn send_status_event(&self, event: PlaybackStatusEvent) {
+        if let Some(event_send) = &self.playback_status_send {
+            if let Err(err) = event_send.try_send(event) {
+                log::warn!("Failed to send playback event: {}", err)
+            }
+        }
+    }
+
+    /// True when all buffer samples got consumed.
+    fn end_of_file(&self) -> bool {
+        self.buffer_pos >= self.region.len() && self.resampler_input_buffer.is_empty()
+    }
+
+    /// Loop region in samples relative to the played region, clamped to the region's size.
+    /// When playing in reverse, the loop region is mirrored, so it's in playback order.
+    fn loop_sample_range(&self) -> Range<usize> {
+        let buffer_len = self.region.len();
+        if let Some(loop_range) = &self.loop_range {
+            let channel_count = self.buffer_channel_count as u64;
+            let region_start = self.region.start as u64;
+            let start = loop_range.start.saturating_mul(channel_count);
+            let end = loop_range.end.saturating_mul(channel_count);
+            let start = start.saturating_sub(region_start).min(buffer_len as u64) as usize;
+            let end = end.saturating_sub(region_start).min(buffer_len as u64) as usize;
+            if start < end {
+                if self.reverse {
+                    return buffer_len - end..buffer_len - start;
+                }
+                return start..end;
+            }
+        }
+        0..buffer_len
+    }
+
+    /// Loop region and mode in buffer samples.
+    fn loop_region(&self) -> LoopRegion {
+        let range = self.loop_sample_range();
+        let channel_count = self.buffer_channel_count;
+        let crossfade_len = match self.loop_mode {
+            LoopMode::Crossfade(duration) => {
+                let frames = (duration.as_secs_f64() * self.buffer_sample_rate as f64) as usize;
+                frames.min(range.len() / channel_count / 2) * channel_count
+            }
+            LoopMode::Forward | LoopMode::PingPong => 0,
+        };
+        LoopRegion {
+            range,
+            mode: self.loop_mode,
+            crossfade_len,
+            channel_count,
+            reverse: self.reverse,
+        }
+    }
+
+    /// Resample the next chunk of buffer samples into the given output, applying loops.
+    /// Returns None when the resampler produced no more output.
+    fn resample_next(&mut self, output: &mut [f32]) -> Option<usize> {
+        // NB: while repeating, the input ends at the loop end
+        let loop_region = self.loop_region();
+        let looping = self.repeat > 0 && self.buffer_pos < loop_region.range.end;
+        let input_end = if looping {
+            loop_region.range.end
+        } else {
+            self.region.len()
+        };
+        let region_buffer = &self.buffer[self.region.clone()];
+        // send events for markers within played buffer ranges
+        let markers = &self.markers;
+        let region = &self.region;
+        let (channel_count, reverse) = (self.buffer_channel_count, self.reverse);
+        let (file_id, file_path) = (self.file_id, &self.file_path);
+        let (status_send, status_context) =
+            (&self.playback_status_send, &self.playback_status_context);
+        let mut on_play = |played: Range<usize>| {
+            let Some(event_send) = status_send else {
+                return;
+            };
+            for marker in markers {
+                if marker_buffer_pos(marker.frame, region, channel_count, reverse)
+                    .is_some_and(|pos| played.contains(&pos))
+                {
+                    if let Err(err) = event_send.try_send(PlaybackStatusEvent::MarkerReached {
+                        id: file_id,
+                        context: status_context.clone(),
+                        path: file_path.clone(),
+                        marker_id: marker.id,
+                        label: marker.label.clone(),
+                    }) {
+                        log::warn!("Failed to send playback event: {}", err)
+                    }
+                }
+            }
+        };
+        let remaining_input_buffer = &region_buffer[self.buffer_pos..input_end];
+        let required_input_len = self.resampler.required_input_buffer_size().unwrap_or(0);
+        let plays_buffer_as_is = (self.loop_mode == LoopMode::Forward || self.repeat == 0)
+            && !self.playing_backwards
+            && !self.reverse;
+        let output_written = if plays_buffer_as_is
+            && self.resampler_input_buffer.is_empty()
+            && remaining_input_buffer.len() >= required_input_len
+        {
+            // resample directly from the buffer
+            let (input_consumed, output_written) = self
+                .resampler
+                .process(remaining_input_buffer, output)
+                .expect("PreloadedFile resampling failed");
+            on_play(self.buffer_pos..self.buffer_pos + input_consumed);
+            self.buffer_pos += input_consumed;
+            if looping && self.buffer_pos >= loop_region.range.end {
+                if self.repeat != usize::MAX {
+                    self.repeat -= 1;
+                }
+                self.buffer_pos = loop_region.range.start;
+            }
+            output_written
+        } else {
+            // resample from the input buffer, when the resampler has input size constrains
+            // (should only happen at the loop end or in the last process calls) or when the
+            // loop mode does not play the buffer as it is
+            if self.resampler_input_buffer.is_empty() {
+                self.resampler_input_buffer.reset_range();
+                let input_len = if required_input_len > 0 {
+                    required_input_len
+                } else {
+                    self.resampler_input_buffer.len() / self.buffer_channel_count
+                        * self.buffer_channel_count
+                };
+                let mut cursor = LoopCursor {
+                    pos: self.buffer_pos,
+                    repeat: self.repeat,
+                    backwards: self.playing_backwards,
+                };
+                let read = loop_region.read(
+                    region_buffer,
+                    &mut cursor,
+                    &mut self.resampler_input_buffer.get_mut()[..input_len],
+                    &mut on_play,
+                );
+                self.buffer_pos = cursor.pos;
+                self.repeat = cursor.repeat;
+                self.playing_backwards = cursor.backwards;
+                // pad input with zeros for resamplers with input size constrains
+                let input_len = if required_input_len > 0 {
+                    input_len
+                } else {
+                    read
+                };
+                self.resampler_input_buffer.set_range(0, input_len);
+            }
+            let (input_consumed, output_written) = self
+                .resampler
+                .process(self.resampler_input_buffer.get(), output)
+                .expect("PreloadedFile resampling failed");
+            self.resampler_input_buffer.consume(input_consumed);
+            if input_consumed == 0 && output_written == 0 {
+                return None;
+            }
+            output_written
+        };
+        Some(output_written)
+    }
+
+    /// Seek to the given position, relative to the played region.
+    fn seek(&mut self, position: Duration) {
+        let frame = (position.as_secs_f64() * self.buffer_sample_rate as f64).round() as usize;
+        let buffer_pos = frame * self.buffer_channel_count;
+        self.buffer_pos = buffer_pos.clamp(0, self.region.len());
+        self.playing_backwards = false;
+        self.resampler_input_buffer.set_range(0, 0);
+        self.resampler.reset();
+    }
+
+    /// Render the output at the current position into the seek fader's tail buffer and start
+    /// crossfading it with the output after seeking.
+    fn start_seek_crossfade(&mut self, duration: Duration) {
+        let mut seek_fader = std::mem::take(&mut self.seek_fader);
+        let tail = seek_fader.tail_mut(duration);
+        let mut tail_len = 0;
+        while tail_len < tail.len() && !self.end_of_file() {
+            match self.resample_next(&mut tail[tail_len..]) {
+                Some(written) if written > 0 => tail_len += written,
+                _ => break,
+            }
+        }
+        seek_fader.start(tail_len);
+        self.seek_fader = seek_fader;
+    }
+
     fn samples_to_duration(&self, samples: usize) -> Duration {
         let frames = samples / self.buffer_channel_count;
         let seconds = frames as f64 / self.output_sample_rate as f64;
@@ -235,6 +514,182 @@ impl PreloadedFileSource {
     }
 }
 
+// -------------------------------------------------------------------------------------------------
+
+/// Buffer position of the marker at the given file frame in playback order, relative to the
+/// played region. None when the marker is outside of the played region.
+fn marker_buffer_pos(
+    frame: u64,
+    region: &Range<usize>,
+    channel_count: usize,
+    reverse: bool,
+) -> Option<usize> {
+    let sample = usize::try_from(frame.checked_mul(channel_count as u64)?).ok()?;
+    if !region.contains(&sample) {
+        return None;
+    }
+    let pos = sample - region.start;
+    if reverse {
+        Some(region.len() - pos - channel_count)
+    } else {
+        Some(pos)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Read position of a [`PreloadedFileSource`] in its buffer.
+struct LoopCursor {
+    pos: usize,
+    repeat: usize,
+    backwards: bool,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Loop region of a [`PreloadedFileSource`] in buffer samples.
+///
+/// When playing in reverse, all buffer positions, including the loop range, are mirrored: they
+/// are in playback order, starting at the end of the buffer.
+struct LoopRegion {
+    range: Range<usize>,
+    mode: LoopMode,
+    crossfade_len: usize,
+    channel_count: usize,
+    reverse: bool,
+}
+
+impl LoopRegion {
+    /// Copy samples at the given buffer position into the given output in playback order.
+    #[inline]
+    fn copy(&self, buffer: &[f32], pos: usize, output: &mut [f32]) {
+        if self.reverse {
+            let channel_count = self.channel_count;
+            for (frame, output) in output.chunks_exact_mut(channel_count).enumerate() {
+                let buffer_pos = buffer.len() - pos - (frame + 1) * channel_count;
+                output.copy_from_slice(&buffer[buffer_pos..buffer_pos + channel_count]);
+            }
+        } else {
+            output.copy_from_slice(&buffer[pos..pos + output.len()]);
+        }
+    }
+
+    /// Get a single sample at the given buffer position in playback order.
+    #[inline]
+    fn sample(&self, buffer: &[f32], pos: usize) -> f32 {
+        if self.reverse {
+            let channel_count = self.channel_count;
+            let frame = pos / channel_count;
+            buffer[buffer.len() - (frame + 1) * channel_count + pos % channel_count]
+        } else {
+            buffer[pos]
+        }
+    }
+
+    /// Copy buffer samples at the cursor into the given output, applying the loop region and
+    /// mode while there are repeats left. The output gets padded with zeros when reaching the end
+    /// of the buffer. Returns the number of samples which got read from the buffer.
+    ///
+    /// All played ranges of buffer positions get passed to the given `on_play` function.
+    fn read(
+        &self,
+        buffer: &[f32],
+        cursor: &mut LoopCursor,
+        output: &mut [f32],
+        on_play: &mut impl FnMut(Range<usize>),
+    ) -> usize {
+        let channel_count = self.channel_count;
+        let mut filled = 0;
+        while filled < output.len() {
+            if cursor.backwards {
+                // play backwards, frame by frame, until reaching the loop start
+                let played_end = cursor.pos;
+                while filled < output.len() && cursor.pos > self.range.start {
+                    cursor.pos -= channel_count;
+                    self.copy(
+                        buffer,
+                        cursor.pos,
+                        &mut output[filled..filled + channel_count],
+                    );
+                    filled += channel_count;
+                }
+                on_play(cursor.pos..played_end);
+                if cursor.pos <= self.range.start {
+                    // turn around, skipping the frame we've just played
+                    if cursor.repeat != usize::MAX {
+                        cursor.repeat -= 1;
+                    }
+                    cursor.backwards = false;
+                    cursor.pos = (self.range.start + channel_count).min(self.range.end);
+                }
+                continue;
+            }
+            let looping = cursor.repeat > 0 && cursor.pos < self.range.end;
+            if !looping {
+                // play until the end of the buffer
+                let len = (buffer.len() - cursor.pos).min(output.len() - filled);
+                self.copy(buffer, cursor.pos, &mut output[filled..filled + len]);
+                on_play(cursor.pos..cursor.pos + len);
+                filled += len;
+                cursor.pos += len;
+                if cursor.pos >= buffer.len() {
+                    output[filled..].fill(0.0);
+                    break;
+                }
+                continue;
+            }
+            let crossfade_start = self.range.end - self.crossfade_len;
+            if cursor.pos < crossfade_start {
+                // play until the loop end or crossfade start
+                let len = (crossfade_start - cursor.pos).min(output.len() - filled);
+                self.copy(buffer, cursor.pos, &mut output[filled..filled + len]);
+                on_play(cursor.pos..cursor.pos + len);
+                filled += len;
+                cursor.pos += len;
+            } else {
+                // crossfade the loop end with the loop start, using an equal power fade
+                let crossfade_frames = (self.crossfade_len / channel_count) as f32;
+                let played_start = cursor.pos;
+                while filled < output.len() && cursor.pos < self.range.end {
+                    let frame = (cursor.pos - crossfade_start) / channel_count;
+                    let fade = (frame as f32 + 0.5) / crossfade_frames * FRAC_PI_2;
+                    let (fade_in, fade_out) = fade.sin_cos();
+                    let loop_pos = self.range.start + frame * channel_count;
+                    for channel in 0..channel_count {
+                        output[filled + channel] = self.sample(buffer, cursor.pos + channel)
+                            * fade_out
+                            + self.sample(buffer, loop_pos + channel) * fade_in;
+                    }
+                    filled += channel_count;
+                    cursor.pos += channel_count;
+                }
+                // both, the loop end and start got played
+                on_play(played_start..cursor.pos);
+                on_play(
+                    self.range.start + played_start - crossfade_start
+                        ..self.range.start + cursor.pos - crossfade_start,
+                );
+            }
+            if cursor.pos >= self.range.end {
+                if self.mode == LoopMode::PingPong {
+                    // turn around, skipping the frame we've just played
+                    cursor.backwards = true;
+                    cursor.pos = self.range.end - channel_count;
+                } else {
+                    // jump to the loop start, continuing after the crossfaded part
+                    if cursor.repeat != usize::MAX {
+                        cursor.repeat -= 1;
+                    }
+                    cursor.pos = self.range.start + self.crossfade_len;
+                }
+            }
+        }
+        filled
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 impl FileSource for PreloadedFileSource {
     fn playback_id(&self) -> PlaybackId {
         self.file_id
@@ -259,13 +714,20 @@ impl FileSource for PreloadedFileSource {
     }
 
     fn total_frames(&self) -> Option<u64> {
-        Some(self.buffer.len() as u64 / self.channel_count() as u64)
+        Some(self.region.len() as u64 / self.channel_count() as u64)
     }
 
     fn current_frame_position(&self) -> u64 {
         self.buffer_pos as u64 / self.channel_count() as u64
     }
 
+    fn markers(&self) -> Vec<FileMarker> {
+        self.markers.clone()
+    }
+    fn add_marker(&mut self, marker: FileMarker) {
+        let _ = insert_marker(&mut self.markers, marker);
+    }
+
     fn end_of_track(&self) -> bool {
         self.playback_finished
     }
@@ -277,14 +739,60 @@ impl Source for PreloadedFileSource {
         while let Some(msg) = self.playback_message_queue.pop() {
             match msg {
                 FilePlaybackMessage::Seek(position) => {
-                    let buffer_pos = position.as_secs_f64()
-                        * self.buffer_sample_rate as f64
-                        * self.buffer_channel_count as f64;
-                    self.buffer_pos = (buffer_pos as usize).clamp(0, self.buffer.len());
-                    self.resampler.reset();
+                    self.seek(position);
+                }
+                FilePlaybackMessage::SeekWithCrossfade {
+                    position,
+                    crossfade,
+                } => {
+                    if !self.pause_fader.is_paused() {
+                        self.start_seek_crossfade(crossfade);
+                    }
+                    self.seek(position);
+                }
+                FilePlaybackMessage::SetVolume(volume) => {
+                    self.volume.set_target(volume);
+                }
+                FilePlaybackMessage::FadeVolume { volume, duration } => {
+                    self.volume_fader.fade_to(volume, duration);
+                }
+                FilePlaybackMessage::SetSpeed(speed) => {
+                    let output_rate = (self.output_sample_rate as f64 / speed) as u32;
+                    if let Err(err) = self.resampler.set_output_rate(output_rate) {
+                        log::warn!("failed to change playback speed: {}", err);
+                    }
+                }
+                FilePlaybackMessage::SetLoop(range) => {
+                    self.loop_range = range;
+                }
+                FilePlaybackMessage::AddMarker(mut slot) => {
+                    if let Some(marker) = slot.take() {
+                        *slot = match insert_marker(&mut self.markers, marker) {
+                            Ok(replaced) => replaced,
+                            Err(rejected) => Some(rejected),
+                        };
+                    }
+                }
+                FilePlaybackMessage::RemoveMarker(marker_id, mut slot) => {
+                    *slot = remove_marker(&mut self.markers, marker_id);
+                }
+                FilePlaybackMessage::Pause => {
+                    self.pause_fader.pause();
+                }
+                FilePlaybackMessage::Resume => {
+                    if self.pause_fader.resume() {
+                        self.send_status_event(PlaybackStatusEvent::Resumed {
+                            id: self.file_id,
+                            context: self.playback_status_context.clone(),
+                            path: self.file_path.clone(),
+                        });
+                    }
                 }
                 FilePlaybackMessage::Stop => {
-                    if let Some(duration) = self.fade_out_duration {
+                    if self.pause_fader.is_paused() {
+                        // we're silent already: stop without fading out
+                        self.volume_fader.start_fade_out(Duration::ZERO);
+                    } else if let Some(duration) = self.fade_out_duration {
                         if !duration.is_zero() {
                             self.volume_fader.start_fade_out(duration);
                         } else {
@@ -303,60 +811,47 @@ impl Source for PreloadedFileSource {
         }
 
         // write from buffer at current position and apply volume, fadeout and repeats
+        // NB: paused sources don't produce any output and keep their position
         let mut total_written = 0_usize;
-        while total_written < output.len() {
+        while total_written < output.len() && !self.pause_fader.is_paused() {
             // write from resampled buffer into output and apply volume
-            let remaining_input_len = self.buffer.len() - self.buffer_pos;
-            let remaining_input_buffer =
-                &self.buffer[self.buffer_pos..self.buffer_pos + remaining_input_len];
-            let remaining_target = &mut output[total_written..];
-            // pad input with zeros if resampler has input size constrains (should only happen in the last process calls)
-            let required_input_len = self.resampler.required_input_buffer_size().unwrap_or(0);
-            let (input_consumed, output_written) =
-                if remaining_input_buffer.len() < required_input_len {
-                    self.resampler_input_buffer.reset_range();
-                    self.resampler_input_buffer
-                        .copy_from(remaining_input_buffer);
-                    for o in &mut self.resampler_input_buffer.get_mut()[remaining_input_len..] {
-                        *o = 0.0;
-                    }
-                    let (_, output_written) = self
-                        .resampler
-                        .process(self.resampler_input_buffer.get(), remaining_target)
-                        .expect("PreloadedFile resampling failed");
-                    (remaining_input_len, output_written)
-                } else {
-                    self.resampler
-                        .process(remaining_input_buffer, remaining_target)
-                        .expect("PreloadedFile resampling failed")
-                };
-
-            // apply volume
-            if (self.volume - 1.0).abs() > 0.0001 {
-                for o in remaining_target.iter_mut() {
-                    *o *= self.volume;
+            let mut remaining_target = &mut output[total_written..];
+            if let Some(frames) = self.pause_fader.frames_until_paused() {
+                let samples = frames * self.buffer_channel_count;
+                if samples < remaining_target.len() {
+                    remaining_target = &mut remaining_target[..samples];
                 }
             }
+            let Some(output_written) = self.resample_next(remaining_target) else {
+                // got no more output from the resampler
+                break;
+            };
 
-            // apply volume fading
+            // apply seek crossfades
+            let output_written = self.seek_fader.process(remaining_target, output_written);
+
+            // apply volume and volume fading
             let written_target = &mut output[total_written..total_written + output_written];
+            self.volume.apply(written_target, self.buffer_channel_count);
             self.volume_fader.process(written_target);
 
-            // maintain buffer pos
-            self.buffer_pos += input_consumed;
+            // apply pause fading
+            if self
+                .pause_fader
+                .process(written_target, self.buffer_channel_count)
+            {
+                self.send_status_event(PlaybackStatusEvent::Paused {
+                    id: self.file_id,
+                    context: self.playback_status_context.clone(),
+                    path: self.file_path.clone(),
+                });
+            }
+
             total_written += output_written;
 
-            // loop or stop when reaching end of file
-            let end_of_file = self.buffer_pos >= self.buffer.len();
-            if end_of_file {
-                if self.repeat > 0 {
-                    if self.repeat != usize::MAX {
-                        self.repeat -= 1;
-                    }
-                    self.buffer_pos = 0;
-                } else {
-                    break;
-                }
+            // stop when reaching end of file
+            if self.end_of_file() {
+                break;
             }
         }
 
@@ -377,16 +872,15 @@ impl Source for PreloadedFileSource {
         }
 
         // check if we've finished playing and send Stopped events
-        let end_of_file = self.buffer_pos >= self.buffer.len();
-        let fade_out_completed = self.volume_fader.state() == FaderState::Finished
-            && self.volume_fader.target_volume() == 0.0;
+        let end_of_file = self.end_of_file();
+        let fade_out_completed = self.volume_fader.fade_out_finished();
         if end_of_file || fade_out_completed {
             if let Some(event_send) = &self.playback_status_send {
                 if let Err(err) = event_send.try_send(PlaybackStatusEvent::Stopped {
                     id: self.file_id,
                     context: self.playback_status_context.clone(),
                     path: self.file_path.clone(),
-                    exhausted: self.buffer_pos >= self.buffer.len(),
+                    exhausted: end_of_file,
                 }) {
                     log::warn!("Failed to send playback event: {}", err)
                 }
@@ -416,6 +910,8 @@ impl Source for PreloadedFileSource {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::source::file::test_utils::wav_file;
+    use basedrop::Owned;
 
     #[test]
     fn resampling() {
@@ -459,4 +955,293 @@ mod tests {
         assert!((output.iter().sum::<f32>() - buffer.iter().sum::<f32>()).abs() < 0.2);
         assert!(output[3..].iter().sum::<f32>() < 0.1);
     }
+
+    #[test]
+    fn loop_range() {
+        // add one extra zero sample for cubic resampling
+        let buffer = Arc::new(vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 0.0]);
+        let expected = [
+            0.0, 1.0, 2.0, 3.0, 4.0, 2.0, 3.0, 4.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0,
+        ];
+
+        for quality in [ResamplingQuality::Default, ResamplingQuality::HighQuality] {
+            let mut preloaded = PreloadedFileSource::with_buffer(
+                buffer.clone(),
+                44100,
+                1,
+                "temp_file",
+                None,
+                FilePlaybackOptions::default()
+                    .resampling_quality(quality)
+                    .repeat(2)
+                    .loop_range(2..5),
+                44100,
+            )
+            .unwrap();
+            let mut output = vec![0.0; 1024];
+            let written = preloaded.write(&mut output, &SourceTime::default());
+            assert!(written >= expected.len());
+            assert_eq!(output[..expected.len()], expected);
+        }
+    }
+
+    #[test]
+    fn markers() {
+        let buffer = Arc::new((0..64).map(|i| i as f32).collect::<Vec<_>>());
+        let (status_send, status_recv) = crossbeam_channel::unbounded();
+        let mut preloaded = PreloadedFileSource::with_buffer(
+            buffer,
+            44100,
+            1,
+            "temp_file",
+            Some(status_send),
+            FilePlaybackOptions::default().repeat(1).loop_range(16..48),
+            44100,
+        )
+        .unwrap();
+        preloaded.add_marker(FileMarker::new(1, 20, "loop"));
+        preloaded.add_marker(FileMarker::new(2, 60, "end"));
+        let reached_markers = || {
+            status_recv
+                .try_iter()
+                .filter_map(|event| match event {
+                    PlaybackStatusEvent::MarkerReached { marker_id, .. } => Some(marker_id),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut output = vec![0.0; 16];
+        preloaded.write(&mut output, &SourceTime::default());
+        assert!(reached_markers().is_empty());
+        preloaded.write(&mut output, &SourceTime::default());
+        assert_eq!(reached_markers(), [1]);
+
+        // markers can be added and removed while playing
+        let collector = basedrop::Collector::new();
+        let handle = collector.handle();
+        let queue = preloaded.playback_message_queue();
+        let marker = Some(FileMarker::new(3, 40, ""));
+        let _ = queue.push(FilePlaybackMessage::AddMarker(Owned::new(&handle, marker)));
+        let _ = queue.push(FilePlaybackMessage::RemoveMarker(
+            2,
+            Owned::new(&handle, None),
+        ));
+        let mut output = vec![0.0; 128];
+        preloaded.write(&mut output, &SourceTime::default());
+        assert_eq!(reached_markers(), [3, 1, 3]);
+    }
+
+    #[cfg(feature = "assert_no_alloc")]
+    #[test]
+    fn marker_changes_without_deallocations() {
+        let buffer = Arc::new(vec![0.0; 64]);
+        let mut preloaded = PreloadedFileSource::with_buffer(
+            buffer,
+            44100,
+            1,
+            "temp_file",
+            None,
+            FilePlaybackOptions::default(),
+            44100,
+        )
+        .unwrap();
+        preloaded.add_marker(FileMarker::new(1, 10, "removed"));
+        preloaded.add_marker(FileMarker::new(2, 20, "replaced"));
+
+        // the source holds the only copy of its markers: they must not get freed in `write`
+        let collector = basedrop::Collector::new();
+        let handle = collector.handle();
+        let queue = preloaded.playback_message_queue();
+        let marker = Some(FileMarker::new(2, 30, "replacement"));
+        let _ = queue.push(FilePlaybackMessage::AddMarker(Owned::new(&handle, marker)));
+        let _ = queue.push(FilePlaybackMessage::RemoveMarker(
+            1,
+            Owned::new(&handle, None),
+        ));
+        let mut output = vec![0.0; 16];
+        assert_no_alloc::reset_violation_count();
+        assert_no_alloc::assert_no_alloc(|| preloaded.write(&mut output, &SourceTime::default()));
+        assert_eq!(assert_no_alloc::violation_count(), 0);
+        assert_eq!(preloaded.markers(), [FileMarker::new(2, 30, "replacement")]);
+    }
+
+    #[test]
+    fn loop_modes() {
+        let buffer = Arc::new(vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 0.0]);
+        let crossfade = |frame: f32, from: f32, to: f32| {
+            let (fade_in, fade_out) = ((frame + 0.5) / 2.0 * FRAC_PI_2).sin_cos();
+            from * fade_out + to * fade_in
+        };
+        let tests = [
+            (
+                LoopMode::PingPong,
+                2..5,
+                vec![0.0, 1.0, 2.0, 3.0, 4.0, 3.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0],
+            ),
+            (
+                LoopMode::Crossfade(Duration::from_millis(2)),
+                2..6,
+                vec![
+                    0.0,
+                    1.0,
+                    2.0,
+                    3.0,
+                    crossfade(0.0, 4.0, 2.0),
+                    crossfade(1.0, 5.0, 3.0),
+                    4.0,
+                    5.0,
+                    6.0,
+                    7.0,
+                ],
+            ),
+        ];
+        for (mode, range, expected) in tests {
+            for quality in [ResamplingQuality::Default, ResamplingQuality::HighQuality] {
+                let mut preloaded = PreloadedFileSource::with_buffer(
+                    buffer.clone(),
+                    1000,
+                    1,
+                    "temp_file",
+                    None,
+                    FilePlaybackOptions::default()
+                        .resampling_quality(quality)
+                        .repeat(1)
+                        .loop_range(range.clone())
+                        .loop_mode(mode),
+                    1000,
+                )
+                .unwrap();
+                let mut output = vec![0.0; 1024];
+                let written = preloaded.write(&mut output, &SourceTime::default());
+                assert!(written >= expected.len());
+                for (output, expected) in output.iter().zip(&expected) {
+                    assert!((output - expected).abs() < 0.0001, "{output} != {expected}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reverse() {
+        let buffer = Arc::new(vec![0.0, 10.0, 1.0, 11.0, 2.0, 12.0]);
+        for quality in [ResamplingQuality::Default, ResamplingQuality::HighQuality] {
+            let mut preloaded = PreloadedFileSource::with_buffer(
+                buffer.clone(),
+                1000,
+                2,
+                "temp_file",
+                None,
+                FilePlaybackOptions::default()
+                    .resampling_quality(quality)
+                    .reverse()
+                    .repeat(1),
+                1000,
+            )
+            .unwrap();
+            let mut output = vec![0.0; 1024];
+            let written = preloaded.write(&mut output, &SourceTime::default());
+            assert!(written >= 12);
+            assert_eq!(
+                output[..12],
+                [2.0, 12.0, 1.0, 11.0, 0.0, 10.0, 2.0, 12.0, 1.0, 11.0, 0.0, 10.0]
+            );
+
+            // seek positions are in playback order
+            let mut preloaded = preloaded
+                .clone(
+                    FilePlaybackOptions::default()
+                        .resampling_quality(quality)
+                        .reverse(),
+                    1000,
+                )
+                .unwrap();
+            preloaded
+                .playback_message_queue()
+                .push(FilePlaybackMessage::Seek(Duration::from_millis(1)))
+                .ok();
+            let mut output = vec![0.0; 1024];
+            let written = preloaded.write(&mut output, &SourceTime::default());
+            assert!(written >= 4);
+            assert_eq!(output[..4], [1.0, 11.0, 0.0, 10.0]);
+        }
+    }
+
+    #[test]
+    fn reverse_padded_buffers() {
+        let samples = [1.0, 2.0, 3.0, 4.0];
+        let options = FilePlaybackOptions::default().reverse();
+        let pcm_buffer = PcmBuffer::from_interleaved(&samples, 1, 44100).unwrap();
+        let sources = [
+            PreloadedFileSource::from_pcm_buffer(&pcm_buffer, None, options, 44100).unwrap(),
+            PreloadedFileSource::new(wav_file(&samples), None, options, 44100).unwrap(),
+        ];
+        for mut preloaded in sources {
+            assert_eq!(preloaded.total_frames(), Some(4));
+            let mut output = vec![0.0; 1024];
+            let written = preloaded.write(&mut output, &SourceTime::default());
+            assert!(written >= 4);
+            assert_eq!(output[..4], [4.0, 3.0, 2.0, 1.0]);
+
+            // seek positions are in playback order
+            let mut preloaded = preloaded.clone(options, 44100).unwrap();
+            preloaded
+                .playback_message_queue()
+                .push(FilePlaybackMessage::Seek(Duration::from_secs_f64(
+                    1.0 / 44100.0,
+                )))
+                .ok();
+            let mut output = vec![0.0; 1024];
+            let written = preloaded.write(&mut output, &SourceTime::default());
+            assert!(written >= 3);
+            assert_eq!(output[..3], [3.0, 2.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn region() {
+        let buffer = Arc::new(vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 0.0]);
+        let mut preloaded = PreloadedFileSource::with_buffer(
+            buffer.clone(),
+            1000,
+            1,
+            "temp_file",
+            None,
+            FilePlaybackOptions::default()
+                .region_start(Duration::from_millis(2))
+                .region_length(4)
+                .repeat(1)
+                .loop_range(3..5),
+            1000,
+        )
+        .unwrap();
+        assert_eq!(preloaded.total_frames(), Some(4));
+
+        let mut output = vec![0.0; 1024];
+        let written = preloaded.write(&mut output, &SourceTime::default());
+        assert_eq!(written, 6);
+        assert_eq!(output[..written], [2.0, 3.0, 4.0, 3.0, 4.0, 5.0]);
+        assert_eq!(preloaded.current_frame_position(), 4);
+        assert!(preloaded.end_of_track());
+    }
+
+    #[test]
+    fn encoded_audio() {
+        // encode a ramp into an in-memory WAV file
+        let samples = (0..64).map(|i| i as f32 / 64.0).collect::<Vec<_>>();
+        let wav = wav_file(&samples);
+
+        for audio in [
+            EncodedAudio::from(wav.clone()).with_hint("wav"),
+            EncodedAudio::from_reader(std::io::Cursor::new(wav.clone())).with_name("ramp"),
+        ] {
+            let name = audio.name().to_string();
+            let preloaded =
+                PreloadedFileSource::new(audio, None, FilePlaybackOptions::default(), 44100)
+                    .unwrap();
+            assert_eq!(preloaded.file_path.as_str(), name);
+            // NB: decoded buffer has one extra zero frame for resampling
+            assert_eq!(preloaded.buffer()[..samples.len()], samples);
+        }
+    }
 }
@@ -0,0 +1,286 @@
+use std::{sync::Arc, time::Duration};
+
+use symphonia::core::{
+    formats::Track,
+    meta::{MetadataRevision, StandardTagKey, StandardVisualKey, Value},
+};
+
+use super::{EncodedAudio, FileMarker, TrackSelector};
+use crate::{
+    utils::decoder::{probe_audio, read_markers, read_metadata, select_track},
+    Error,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Describes a single track of an audio file's container, as returned by [`probe_tracks`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackInfo {
+    /// Index of the track in the container's track list. Use it with
+    /// [`TrackSelector::Index`](super::TrackSelector::Index) or
+    /// [`FilePlaybackOptions::track`](super::FilePlaybackOptions::track) to play the track.
+    pub index: usize,
+    /// The container's unique id of the track.
+    pub id: u32,
+    /// Short name of the track's codec, e.g. "mp3" or "aac".
+    pub codec: String,
+    /// Number of channels, when known.
+    pub channel_count: Option<usize>,
+    /// Names of the channel positions, e.g. `["FRONT_LEFT", "FRONT_RIGHT"]`. Empty when the
+    /// channel layout is unknown.
+    pub channel_layout: Vec<String>,
+    /// Sample rate, when known.
+    pub sample_rate: Option<u32>,
+    /// Bit depth of the decoded samples, when known. Lossy codecs usually have no bit depth.
+    pub bits_per_sample: Option<u32>,
+    /// Total number of sample frames, when known.
+    pub frame_count: Option<u64>,
+    /// Total duration, when known.
+    pub duration: Option<Duration>,
+    /// Language code of the track, e.g. "eng", when known.
+    pub language: Option<String>,
+    /// True for the track which gets played by default.
+    pub is_default: bool,
+}
+
+impl TrackInfo {
+    fn new(index: usize, track: &Track, default_track_id: Option<u32>) -> Self {
+        let params = &track.codec_params;
+        let codec = symphonia::default::get_codecs()
+            .get_codec(params.codec)
+            .map(|descriptor| descriptor.short_name.to_string())
+            .unwrap_or_else(|| params.codec.to_string());
+        let duration = match (params.n_frames, params.sample_rate, params.time_base) {
+            (Some(frames), Some(rate), _) => {
+                Some(Duration::from_secs_f64(frames as f64 / rate as f64))
+            }
+            (Some(frames), None, Some(time_base)) => {
+                let time = time_base.calc_time(frames);
+                Some(Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac))
+            }
+            _ => None,
+        };
+        Self {
+            index,
+            id: track.id,
+            codec,
+            channel_count: params.channels.map(|channels| channels.count()),
+            channel_layout: params.channels.map_or(Vec::new(), |channels| {
+                channels
+                    .iter()
+                    .map(|channel| format!("{:?}", channel))
+                    .collect()
+            }),
+            sample_rate: params.sample_rate,
+            bits_per_sample: params.bits_per_sample,
+            frame_count: params.n_frames,
+            duration,
+            language: track.language.clone(),
+            is_default: Some(track.id) == default_track_id,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// An embedded picture of an audio file, e.g. an album cover.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverArt {
+    /// MIME type of the image data, e.g. "image/jpeg".
+    pub media_type: String,
+    /// Encoded image data.
+    pub data: Arc<[u8]>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Tags of an audio file or stream. All fields are optional, as most files only contain a few
+/// or none of them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub date: Option<String>,
+    pub track_number: Option<String>,
+    /// The front cover or, when there's none, the first picture in the file.
+    pub cover_art: Option<CoverArt>,
+}
+
+impl FileMetadata {
+    /// Overwrite fields with the tags and pictures of the given metadata revision.
+    pub(crate) fn merge_revision(&mut self, revision: &MetadataRevision) {
+        for tag in revision.tags() {
+            let field = match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => &mut self.title,
+                Some(StandardTagKey::Artist) => &mut self.artist,
+                Some(StandardTagKey::Album) => &mut self.album,
+                Some(StandardTagKey::AlbumArtist) => &mut self.album_artist,
+                Some(StandardTagKey::Genre) => &mut self.genre,
+                Some(StandardTagKey::Date) => &mut self.date,
+                Some(StandardTagKey::TrackNumber) => &mut self.track_number,
+                _ => continue,
+            };
+            if !matches!(tag.value, Value::Binary(_) | Value::Flag) {
+                // NB: RIFF INFO strings are NUL terminated
+                *field = Some(tag.value.to_string().trim_end_matches('\0').to_string());
+            }
+        }
+        let visuals = revision.visuals();
+        if let Some(visual) = visuals
+            .iter()
+            .find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+            .or_else(|| visuals.first().filter(|_| self.cover_art.is_none()))
+        {
+            self.cover_art = Some(CoverArt {
+                media_type: visual.media_type.clone(),
+                data: Arc::from(&visual.data[..]),
+            });
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Information about an audio file, as returned by [`probe_file`].
+///
+/// Codec related fields describe the track which gets played by default, or the track which
+/// got selected in the probed [`EncodedAudio`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileInfo {
+    /// The file's tags and cover art.
+    pub metadata: FileMetadata,
+    /// All tracks in the file's container.
+    pub tracks: Vec<TrackInfo>,
+    /// Markers of the file from WAV cue points or FLAC cue sheets, sorted by their position.
+    /// See [`FileMarker`] for supported marker formats.
+    pub markers: Vec<FileMarker>,
+    /// Short name of the played track's codec, e.g. "mp3" or "aac".
+    pub codec: String,
+    /// Total duration of the played track, when known.
+    pub duration: Option<Duration>,
+    /// Sample rate of the played track, when known.
+    pub sample_rate: Option<u32>,
+    /// Bit depth of the played track, when known.
+    pub bits_per_sample: Option<u32>,
+    /// Number of channels of the played track, when known.
+    pub channel_count: Option<usize>,
+    /// Names of the played track's channel positions. Empty when unknown.
+    pub channel_layout: Vec<String>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Read the tags, cover art, tracks and signal specs of the given file or in-memory encoded
+/// audio without decoding it.
+pub fn probe_file(audio: impl Into<EncodedAudio>) -> Result<FileInfo, Error> {
+    let audio = audio.into();
+    let track_selector = audio.track.clone();
+    let (mut probed, wav_info) = probe_audio(audio)?;
+    let metadata = read_metadata(&mut probed);
+    let markers = read_markers(&probed, wav_info.markers);
+    let format = probed.format;
+    let default_track_id = format.default_track().map(|track| track.id);
+    let tracks = format
+        .tracks()
+        .iter()
+        .enumerate()
+        .map(|(index, track)| TrackInfo::new(index, track, default_track_id))
+        .collect::<Vec<_>>();
+    let selected_track_id = select_track(format.as_ref(), &track_selector)?.id;
+    let selected = tracks
+        .iter()
+        .find(|track| track.id == selected_track_id)
+        .cloned()
+        .expect("selected track should be listed");
+    Ok(FileInfo {
+        metadata,
+        markers,
+        codec: selected.codec,
+        duration: selected.duration,
+        sample_rate: selected.sample_rate,
+        bits_per_sample: selected.bits_per_sample,
+        channel_count: selected.channel_count,
+        channel_layout: selected.channel_layout,
+        tracks,
+    })
+}
+
+/// List all tracks of the given file or in-memory encoded audio, without decoding it.
+/// See also [`probe_file`].
+pub fn probe_tracks(audio: impl Into<EncodedAudio>) -> Result<Vec<TrackInfo>, Error> {
+    let audio = audio.into().with_track(TrackSelector::Default);
+    probe_file(audio).map(|info| info.tracks)
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{source::file::test_utils::encode_wav, FilePlaybackOptions, PreloadedFileSource};
+
+    /// 100ms of silence as stereo 16-bit WAV file, with the given extra chunk in front of
+    /// the data chunk.
+    fn wav_file(extra_chunk: &[u8]) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 48000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        encode_wav(spec, &[0.0; 4800 * 2], extra_chunk)
+    }
+
+    #[test]
+    fn tracks() {
+        let wav = wav_file(&[]);
+
+        let tracks = probe_tracks(wav.clone()).unwrap();
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].index, 0);
+        assert_eq!(tracks[0].codec, "pcm_s16le");
+        assert_eq!(tracks[0].channel_count, Some(2));
+        assert_eq!(tracks[0].sample_rate, Some(48000));
+        assert_eq!(tracks[0].frame_count, Some(4800));
+        assert_eq!(tracks[0].duration, Some(Duration::from_millis(100)));
+        assert!(tracks[0].is_default);
+
+        let options = FilePlaybackOptions::default();
+        assert!(PreloadedFileSource::new(wav.clone(), None, options.track(0), 48000).is_ok());
+        assert!(PreloadedFileSource::new(wav.clone(), None, options.track(1), 48000).is_err());
+        let audio = EncodedAudio::from(wav).with_track(TrackSelector::Language("eng".into()));
+        assert!(PreloadedFileSource::new(audio, None, options, 48000).is_err());
+    }
+
+    #[test]
+    fn file_info() {
+        let mut info_chunk = b"INFO".to_vec();
+        for (id, value) in [(b"INAM", &b"Title\0"[..]), (b"IART", &b"Artist\0"[..])] {
+            info_chunk.extend_from_slice(id);
+            info_chunk.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            info_chunk.extend_from_slice(value);
+            if value.len() % 2 != 0 {
+                info_chunk.push(0); // padding
+            }
+        }
+        let mut list_chunk = b"LIST".to_vec();
+        list_chunk.extend_from_slice(&(info_chunk.len() as u32).to_le_bytes());
+        list_chunk.extend_from_slice(&info_chunk);
+
+        let info = probe_file(wav_file(&list_chunk)).unwrap();
+        assert_eq!(info.metadata.title.as_deref(), Some("Title"));
+        assert_eq!(info.metadata.artist.as_deref(), Some("Artist"));
+        assert_eq!(info.metadata.album, None);
+        assert_eq!(info.metadata.cover_art, None);
+        assert_eq!(info.tracks.len(), 1);
+        assert_eq!(info.codec, "pcm_s16le");
+        assert_eq!(info.duration, Some(Duration::from_millis(100)));
+        assert_eq!(info.sample_rate, Some(48000));
+        assert_eq!(info.bits_per_sample, Some(16));
+        assert_eq!(info.channel_count, Some(2));
+        assert_eq!(info.channel_layout, ["FRONT_LEFT", "FRONT_RIGHT"]);
+    }
+}
@@ -1,7 +1,7 @@
 use std::{
     ops::Range,
     sync::{
-        atomic::{AtomicBool, AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
     time::{Duration, Instant},
@@ -9,10 +9,13 @@ use std::{
 
 use crossbeam_channel::Sender;
 use crossbeam_queue::ArrayQueue;
-use rb::{Consumer, Producer, RbConsumer, RbProducer, SpscRb, RB};
+use rb::{Consumer, Producer, RbConsumer, RbInspector, RbProducer, SpscRb, RB};
 use symphonia::core::audio::{SampleBuffer, SignalSpec};
 
-use super::{FilePlaybackMessage, FilePlaybackOptions, FileSource};
+use super::{
+    insert_marker, preallocated_markers, probe::FileMetadata, remove_marker, EncodedAudio,
+    FileMarker, FilePlaybackMessage, FilePlaybackOptions, FileSource, LoopMode,
+};
 use crate::{
     error::Error,
     player::{PlaybackId, PlaybackStatusContext, PlaybackStatusEvent},
@@ -21,10 +24,11 @@ use crate::{
         actor::{Act, Actor, ActorHandle},
         buffer::TempBuffer,
         decoder::AudioDecoder,
-        fader::{FaderState, VolumeFader},
+        fader::{PauseFader, SeekFader, VolumeFader},
         resampler::{
             cubic::CubicResampler, rubato::RubatoResampler, AudioResampler, ResamplingSpecs,
         },
+        smoothing::SmoothedValue,
         unique_usize_id,
     },
 };
@@ -35,6 +39,20 @@ use crate::{
 pub enum StreamedFileSourceMessage {
     /// Seek the decoder to a new position
     Seek(Duration),
+    /// Change the loop region in sample frames
+    SetLoop(Option<Range<u64>>),
+    /// Add or replace a marker
+    AddMarker(FileMarker),
+    /// Remove the marker with the given id
+    RemoveMarker(u32),
+    /// Send metadata change events of the source with the given id and path to the given
+    /// channel, using the given context
+    SetPlaybackStatus {
+        id: PlaybackId,
+        path: Arc<String>,
+        sender: Option<Sender<PlaybackStatusEvent>>,
+        context: Option<PlaybackStatusContext>,
+    },
     /// Start reading streamed source
     Read,
     /// Stop the decoder
@@ -44,16 +62,26 @@ pub enum StreamedFileSourceMessage {
 // -------------------------------------------------------------------------------------------------
 
 /// A [`FileSource`] which streams & decodes an audio file asynchromiously in a worker thread.
+///
+/// Seeking is not gap-free: the source plays silence until the worker decoded the new position.
+/// Use a [`PreloadedFileSource`](super::preloaded::PreloadedFileSource) for sample accurate
+/// seeks.
 pub struct StreamedFileSource {
     actor: ActorHandle<StreamedFileSourceMessage>,
     event_queue: Arc<ArrayQueue<FilePlaybackMessage>>,
     file_id: usize,
     file_path: Arc<String>,
-    volume: f32,
+    volume: SmoothedValue,
     volume_fader: VolumeFader,
+    pause_fader: PauseFader,
+    seek_fader: SeekFader,
     fade_out_duration: Option<Duration>,
     consumer: Consumer<f32>,
+    consumed_samples: u64,
+    requested_seeks: usize,
     worker_state: SharedFileWorkerState,
+    markers: Vec<FileMarker>,
+    next_reached_marker: Option<ReachedMarker>,
     signal_spec: SignalSpec,
     resampler: Box<dyn AudioResampler>,
     resampler_input_buffer: TempBuffer,
@@ -67,19 +95,35 @@ pub struct StreamedFileSource {
 
 impl StreamedFileSource {
     pub fn new(
-        file_path: &str,
+        audio: impl Into<EncodedAudio>,
         playback_status_send: Option<Sender<PlaybackStatusEvent>>,
         options: FilePlaybackOptions,
         output_sample_rate: u32,
     ) -> Result<Self, Error> {
         // validate options
         options.validate()?;
+        let audio = options.select_track(audio.into());
+        let file_path = audio.name().to_string();
         // create decoder
-        let decoder = AudioDecoder::new(file_path.to_string())?;
+        let mut decoder = AudioDecoder::new(audio)?;
+        let loop_range = options.resolve_loop_range(decoder.loop_points());
+        let markers = decoder.markers();
+        if options.loop_mode != LoopMode::Forward {
+            log::warn!("streamed file sources only support forward loops");
+        }
+        if options.reverse {
+            log::warn!("streamed file sources can't be played in reverse");
+        }
         // Gather the source signal parameters and compute how often we should report
         // the play-head position.
         let signal_spec = decoder.signal_spec();
 
+        // seek to the start of the played region
+        let region = options.resolve_region(signal_spec.rate);
+        if region.start > 0 {
+            decoder.seek_frame(region.start)?;
+        }
+
         // Create a ring-buffer for the decoded samples. Worker thread is producing,
         // we are consuming in the `Source` impl.
         let buffer = StreamedFileWorker::default_buffer();
@@ -108,21 +152,39 @@ impl StreamedFileSource {
             )),
             // True when the worker received a fadeout stop request
             is_fading_out: Arc::new(AtomicBool::new(false)),
+            // Markers which got written into the ring-buffer
+            reached_markers: Arc::new(ArrayQueue::new(64)),
+            // Number of seek requests which the worker handled
+            handled_seeks: Arc::new(AtomicUsize::new(0)),
         };
 
         // Spawn the worker and kick-start the decoding. The buffer will start filling now.
         let actor = StreamedFileWorker::spawn_with_default_cap("audio_decoding", {
             let shared_state = worker_state.clone();
             let repeat = options.repeat;
-            move |this| StreamedFileWorker::new(this, decoder, buffer, shared_state, repeat)
+            move |this| {
+                StreamedFileWorker::new(
+                    this,
+                    decoder,
+                    buffer,
+                    shared_state,
+                    repeat,
+                    loop_range,
+                    region,
+                )
+            }
         });
+        for marker in &markers {
+            actor.send(StreamedFileSourceMessage::AddMarker(marker.clone()))?;
+        }
         actor.send(StreamedFileSourceMessage::Read)?;
 
         // create event queue for the player
         let event_queue = Arc::new(ArrayQueue::new(128));
 
         // create volume fader
-        let mut volume_fader = VolumeFader::new(signal_spec.channels.count(), signal_spec.rate);
+        let mut volume_fader = VolumeFader::new(signal_spec.channels.count(), output_sample_rate);
+        volume_fader.set_curve(options.fade_curve);
         if let Some(duration) = options.fade_in_duration {
             if !duration.is_zero() {
                 volume_fader.start_fade_in(duration);
@@ -152,19 +214,25 @@ impl StreamedFileSource {
         let playback_status_context = None;
 
         // copy remaining options which are applied while playback
-        let volume = options.volume;
+        let volume = SmoothedValue::new(options.volume, output_sample_rate);
         let fade_out_duration = options.fade_out_duration;
         let playback_pos_emit_rate = options.playback_pos_emit_rate;
 
-        Ok(Self {
+        let source = Self {
             actor,
             event_queue,
             file_id,
-            file_path: Arc::new(file_path.into()),
+            file_path: Arc::new(file_path),
             volume,
             volume_fader,
+            pause_fader: PauseFader::new(output_sample_rate),
+            seek_fader: SeekFader::new(signal_spec.channels.count(), output_sample_rate),
             fade_out_duration,
             consumer,
+            consumed_samples: 0,
+            requested_seeks: 0,
+            markers: preallocated_markers(&markers),
+            next_reached_marker: None,
             signal_spec,
             resampler,
             resampler_input_buffer,
@@ -175,7 +243,9 @@ impl StreamedFileSource {
             playback_pos_report_instant: Instant::now(),
             playback_pos_emit_rate,
             playback_finished: false,
-        })
+        };
+        source.send_playback_status_to_worker();
+        Ok(source)
     }
 
     pub(crate) fn total_samples(&self) -> Option<u64> {
@@ -202,6 +272,144 @@ impl StreamedFileSource {
         }
     }
 
+    /// True while the worker did not yet handle all requested seeks, so the ring-buffer still
+    /// contains samples from before seeking.
+    fn is_seeking(&self) -> bool {
+        self.requested_seeks != self.worker_state.handled_seeks.load(Ordering::Acquire)
+    }
+
+    /// Ask the worker to seek to the given position. Stale samples are no longer read until the
+    /// worker handled the seek: optionally crossfade them with the output after seeking.
+    fn request_seek(&mut self, position: Duration, crossfade: Option<Duration>) {
+        match crossfade {
+            // NB: keep playing the old tail when a seek still is pending
+            Some(duration) if !self.pause_fader.is_paused() && !self.is_seeking() => {
+                self.start_seek_crossfade(duration);
+            }
+            _ => {
+                // drop remaining old input
+                self.resampler_input_buffer.set_range(0, 0);
+                self.resampler.reset();
+            }
+        }
+        self.next_reached_marker = None;
+        match self
+            .actor
+            .try_send(StreamedFileSourceMessage::Seek(position))
+        {
+            Ok(()) => self.requested_seeks += 1,
+            Err(err) => log::warn!("failed to send playback seek event: {}", err),
+        }
+    }
+
+    /// Read and resample decoded samples from the ring-buffer into the given output.
+    /// Returns the number of written samples.
+    fn read_resampled(&mut self, output: &mut [f32]) -> usize {
+        if self.is_seeking() {
+            // wait until the worker discarded all samples from before seeking
+            return 0;
+        }
+        let mut written = 0;
+        while written < output.len() {
+            if self.resampler_input_buffer.is_empty() {
+                self.resampler_input_buffer.reset_range();
+                let read_samples = self
+                    .consumer
+                    .read(self.resampler_input_buffer.get_mut())
+                    .unwrap_or(0);
+                self.consumed_samples += read_samples as u64;
+                self.resampler_input_buffer.set_range(0, read_samples);
+
+                // pad with zeros if resampler has input size constrains
+                let required_input_len = self.resampler.required_input_buffer_size().unwrap_or(0);
+                if self.resampler_input_buffer.len() < required_input_len
+                    // stop filling up empty input buffers when we've reached the end of file
+                    && (read_samples != 0
+                        || !self.worker_state.end_of_file.load(Ordering::Relaxed))
+                {
+                    self.resampler_input_buffer.set_range(0, required_input_len);
+                    for o in &mut self.resampler_input_buffer.get_mut()[read_samples..] {
+                        *o = 0.0;
+                    }
+                }
+            }
+            let input = self.resampler_input_buffer.get();
+            let target = &mut output[written..];
+            let (input_consumed, output_written) = self
+                .resampler
+                .process(input, target)
+                .expect("StreamedFile resampling failed");
+            self.resampler_input_buffer.consume(input_consumed);
+            written += output_written;
+            if output_written == 0 {
+                // got no more output from file or resampler
+                break;
+            }
+        }
+        written
+    }
+
+    /// Render the already decoded output at the current position into the seek fader's tail
+    /// buffer and start crossfading it with the output after seeking.
+    fn start_seek_crossfade(&mut self, duration: Duration) {
+        let mut seek_fader = std::mem::take(&mut self.seek_fader);
+        let tail = seek_fader.tail_mut(duration);
+        let tail_len = self.read_resampled(tail);
+        seek_fader.start(tail_len);
+        self.seek_fader = seek_fader;
+        // drop remaining old input
+        self.resampler_input_buffer.set_range(0, 0);
+        self.resampler.reset();
+    }
+
+    /// Send events for all markers which got consumed from the ring-buffer.
+    fn send_marker_events(&mut self) {
+        loop {
+            if self.next_reached_marker.is_none() {
+                self.next_reached_marker = self.worker_state.reached_markers.pop();
+            }
+            match self.next_reached_marker.take() {
+                Some(reached) if reached.stream_sample < self.consumed_samples => {
+                    self.send_status_event(PlaybackStatusEvent::MarkerReached {
+                        id: self.file_id,
+                        context: self.playback_status_context.clone(),
+                        path: self.file_path.clone(),
+                        marker_id: reached.marker.id,
+                        label: reached.marker.label,
+                    });
+                }
+                reached => {
+                    self.next_reached_marker = reached;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Let the worker send metadata change events to our status channel. Metadata holds strings
+    /// and cover art, so it should not get passed through (and maybe dropped in) the audio thread.
+    fn send_playback_status_to_worker(&self) {
+        if let Err(err) = self
+            .actor
+            .send(StreamedFileSourceMessage::SetPlaybackStatus {
+                id: self.file_id,
+                path: self.file_path.clone(),
+                sender: self.playback_status_send.clone(),
+                context: self.playback_status_context.clone(),
+            })
+        {
+            log::warn!("failed to send playback status to file worker: {}", err)
+        }
+    }
+
+    fn send_status_event(&self, event: PlaybackStatusEvent) {
+        if let Some(event_send) = &self.playback_status_send {
+            if let Err(err) = event_send.try_send(event) {
+                log::warn!("failed to send playback event: {}", err)
+            }
+        }
+    }
+
     fn samples_to_duration(&self, samples: u64) -> Duration {
         let frames = samples / self.signal_spec.channels.count() as u64;
         let seconds = frames as f64 / self.output_sample_rate as f64;
@@ -223,6 +431,7 @@ impl FileSource for StreamedFileSource {
     }
     fn set_playback_status_sender(&mut self, sender: Option<Sender<PlaybackStatusEvent>>) {
         self.playback_status_send = sender;
+        self.send_playback_status_to_worker();
     }
 
     fn playback_status_context(&self) -> Option<PlaybackStatusContext> {
@@ -230,6 +439,7 @@ impl FileSource for StreamedFileSource {
     }
     fn set_playback_status_context(&mut self, context: Option<PlaybackStatusContext>) {
         self.playback_status_context = context;
+        self.send_playback_status_to_worker();
     }
 
     fn current_frame_position(&self) -> u64 {
@@ -244,6 +454,21 @@ impl FileSource for StreamedFileSource {
     fn end_of_track(&self) -> bool {
         self.playback_finished && self.worker_state.end_of_file.load(Ordering::Relaxed)
     }
+
+    fn markers(&self) -> Vec<FileMarker> {
+        self.markers.clone()
+    }
+    fn add_marker(&mut self, marker: FileMarker) {
+        if insert_marker(&mut self.markers, marker.clone()).is_err() {
+            return;
+        }
+        if let Err(err) = self
+            .actor
+            .send(StreamedFileSourceMessage::AddMarker(marker))
+        {
+            log::warn!("failed to send add marker event: {}", err)
+        }
+    }
 }
 
 impl Source for StreamedFileSource {
@@ -251,9 +476,73 @@ impl Source for StreamedFileSource {
         // consume playback messages
         while let Some(event) = self.event_queue.pop() {
             match event {
-                FilePlaybackMessage::Seek(pos) => {
-                    if let Err(err) = self.actor.try_send(StreamedFileSourceMessage::Seek(pos)) {
-                        log::warn!("failed to send playback seek event: {}", err)
+                FilePlaybackMessage::Seek(position) => {
+                    self.request_seek(position, None);
+                }
+                FilePlaybackMessage::SeekWithCrossfade {
+                    position,
+                    crossfade,
+                } => {
+                    self.request_seek(position, Some(crossfade));
+                }
+                FilePlaybackMessage::SetVolume(volume) => {
+                    self.volume.set_target(volume);
+                }
+                FilePlaybackMessage::FadeVolume { volume, duration } => {
+                    self.volume_fader.fade_to(volume, duration);
+                }
+                FilePlaybackMessage::SetSpeed(speed) => {
+                    let output_rate = (self.output_sample_rate as f64 / speed) as u32;
+                    if let Err(err) = self.resampler.set_output_rate(output_rate) {
+                        log::warn!("failed to change playback speed: {}", err);
+                    }
+                }
+                FilePlaybackMessage::SetLoop(range) => {
+                    if let Err(err) = self
+                        .actor
+                        .try_send(StreamedFileSourceMessage::SetLoop(range))
+                    {
+                        log::warn!("failed to send playback loop event: {}", err)
+                    }
+                }
+                FilePlaybackMessage::AddMarker(mut slot) => {
+                    let Some(marker) = slot.take() else {
+                        continue;
+                    };
+                    // NB: our copy of the marker keeps its label alive when sending fails
+                    match insert_marker(&mut self.markers, marker.clone()) {
+                        Ok(replaced) => *slot = replaced,
+                        Err(rejected) => {
+                            *slot = Some(rejected);
+                            continue;
+                        }
+                    }
+                    if let Err(err) = self
+                        .actor
+                        .try_send(StreamedFileSourceMessage::AddMarker(marker))
+                    {
+                        log::warn!("failed to send add marker event: {}", err)
+                    }
+                }
+                FilePlaybackMessage::RemoveMarker(marker_id, mut slot) => {
+                    *slot = remove_marker(&mut self.markers, marker_id);
+                    if let Err(err) = self
+                        .actor
+                        .try_send(StreamedFileSourceMessage::RemoveMarker(marker_id))
+                    {
+                        log::warn!("failed to send remove marker event: {}", err)
+                    }
+                }
+                FilePlaybackMessage::Pause => {
+                    self.pause_fader.pause();
+                }
+                FilePlaybackMessage::Resume => {
+                    if self.pause_fader.resume() {
+                        self.send_status_event(PlaybackStatusEvent::Resumed {
+                            id: self.file_id,
+                            context: self.playback_status_context.clone(),
+                            path: self.file_path.clone(),
+                        });
                     }
                 }
                 FilePlaybackMessage::Stop => {
@@ -269,64 +558,59 @@ impl Source for StreamedFileSource {
             return 0;
         }
 
+        // send marker events for the previously consumed input
+        self.send_marker_events();
+
         // fetch input from our ring-buffer and resample it
-        let mut written = 0;
-        while written < output.len() {
-            if self.resampler_input_buffer.is_empty() {
-                self.resampler_input_buffer.reset_range();
-                let read_samples = self
-                    .consumer
-                    .read(self.resampler_input_buffer.get_mut())
-                    .unwrap_or(0);
-                self.resampler_input_buffer.set_range(0, read_samples);
+        // NB: paused sources don't produce any output and keep their position
+        let is_paused = self.pause_fader.is_paused();
+        let output_len = if is_paused {
+            0
+        } else if let Some(frames) = self.pause_fader.frames_until_paused() {
+            (frames * self.signal_spec.channels.count()).min(output.len())
+        } else {
+            output.len()
+        };
+        let is_seeking = self.is_seeking();
+        let read = self.read_resampled(&mut output[..output_len]);
 
-                // pad with zeros if resampler has input size constrains
-                let required_input_len = self.resampler.required_input_buffer_size().unwrap_or(0);
-                if self.resampler_input_buffer.len() < required_input_len
-                    // stop filling up empty input buffers when we've reached the end of file
-                    && (read_samples != 0
-                        || !self.worker_state.end_of_file.load(Ordering::Relaxed))
-                {
-                    self.resampler_input_buffer.set_range(0, required_input_len);
-                    for o in &mut self.resampler_input_buffer.get_mut()[read_samples..] {
-                        *o = 0.0;
-                    }
-                }
-            }
-            let input = self.resampler_input_buffer.get();
-            let target = &mut output[written..];
-            let (input_consumed, output_written) = self
-                .resampler
-                .process(input, target)
-                .expect("StreamedFile resampling failed");
-            self.resampler_input_buffer.consume(input_consumed);
-            written += output_written;
-            if output_written == 0 {
-                // got no more output from file or resampler
-                break;
-            }
-        }
+        // apply seek crossfades
+        let written = self.seek_fader.process(&mut output[..output_len], read);
 
-        // update position counters
-        let position = self.written_samples(written as u64);
+        // update position counters: the crossfaded tail belongs to the old position
+        let position = self.written_samples(read as u64);
 
         // apply volume parameter
-        if (1.0 - self.volume).abs() > 0.0001 {
-            for o in output[0..written].as_mut() {
-                *o *= self.volume;
-            }
-        }
+        self.volume
+            .apply(&mut output[0..written], self.signal_spec.channels.count());
 
         // start fade-out when this got signaled in our worker state
         let is_fading_out = self.worker_state.is_fading_out.load(Ordering::Relaxed);
-        if is_fading_out && self.volume_fader.target_volume() != 0.0 {
-            self.volume_fader
-                .start_fade_out(self.fade_out_duration.unwrap_or(Duration::ZERO));
+        if is_fading_out && !self.volume_fader.is_fading_out() {
+            if is_paused {
+                // we're silent already: stop without fading out
+                self.volume_fader.start_fade_out(Duration::ZERO);
+            } else {
+                self.volume_fader
+                    .start_fade_out(self.fade_out_duration.unwrap_or(Duration::ZERO));
+            }
         }
 
         // apply fade-in or fade-out
         self.volume_fader.process(&mut output[0..written]);
 
+        // apply pause fading
+        if self
+            .pause_fader
+            .process(&mut output[0..written], self.signal_spec.channels.count())
+        {
+            self.send_status_event(PlaybackStatusEvent::Paused {
+                id: self.file_id,
+                context: self.playback_status_context.clone(),
+                path: self.file_path.clone(),
+            });
+        }
+
         // send position change events
         if let Some(event_send) = &self.playback_status_send {
             if self.should_report_pos() {
@@ -345,8 +629,11 @@ impl Source for StreamedFileSource {
 
         // check if playback finished and send Stopped events
         let is_playing = self.worker_state.is_playing.load(Ordering::Relaxed);
-        let is_exhausted = written == 0 && self.worker_state.end_of_file.load(Ordering::Relaxed);
-        let fadeout_completed = is_fading_out && self.volume_fader.state() == FaderState::Finished;
+        let is_exhausted = !is_paused
+            && !is_seeking
+            && written == 0
+            && self.worker_state.end_of_file.load(Ordering::Relaxed);
+        let fadeout_completed = self.volume_fader.fade_out_finished();
         if !is_playing || is_exhausted || fadeout_completed {
             // we're reached end of file or got stopped: send stop message
             if let Some(event_send) = &self.playback_status_send {
@@ -406,6 +693,18 @@ struct SharedFileWorkerState {
     fade_out_on_stop: Arc<AtomicBool>,
     /// True when a stop fadeout was requested.
     is_fading_out: Arc<AtomicBool>,
+    /// Markers which got written into the ring-buffer.
+    reached_markers: Arc<ArrayQueue<ReachedMarker>>,
+    /// Number of seek requests which the worker handled. Ring-buffer content is stale until
+    /// this matches the number of seeks the source requested.
+    handled_seeks: Arc<AtomicUsize>,
+}
+
+/// A marker which got written into the ring-buffer of a [`StreamedFileSource`].
+struct ReachedMarker {
+    /// Position of the marker in all samples which got written into the ring-buffer.
+    stream_sample: u64,
+    marker: FileMarker,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -433,6 +732,24 @@ struct StreamedFileWorker {
     is_reading: bool,
     /// Number of times we should repeat the source
     repeat: usize,
+    /// Loop region in sample frames. None loops the whole file.
+    loop_range: Option<Range<u64>>,
+    /// Frame to skip decoded samples to after seeking.
+    skip_to_frame: Option<u64>,
+    /// Played region of the file in sample frames.
+    region: Range<u64>,
+    /// Markers of the file, sorted by their position.
+    markers: Vec<FileMarker>,
+    /// Number of samples which got written into the ring-buffer and not discarded.
+    samples_produced: u64,
+    /// Id of the source, for metadata change events.
+    file_id: PlaybackId,
+    /// Path of the source, for metadata change events.
+    file_path: Arc<String>,
+    /// Channel to send metadata change events to.
+    playback_status_send: Option<Sender<PlaybackStatusEvent>>,
+    /// Context of metadata change events.
+    playback_status_context: Option<PlaybackStatusContext>,
 }
 
 impl StreamedFileWorker {
@@ -447,6 +764,8 @@ impl StreamedFileWorker {
         output: SpscRb<f32>,
         shared_state: SharedFileWorkerState,
         repeat: usize,
+        loop_range: Option<Range<u64>>,
+        region: Range<u64>,
     ) -> Self {
         const DEFAULT_MAX_FRAMES: u64 = 8 * 1024;
 
@@ -465,6 +784,7 @@ impl StreamedFileWorker {
             );
         }
 
+        let samples_written = region.start * input.signal_spec().channels.count() as u64;
         Self {
             output_producer: output.producer(),
             input_packet: SampleBuffer::new(max_input_frames, input.signal_spec()),
@@ -473,10 +793,19 @@ impl StreamedFileWorker {
             this,
             output,
             shared_state,
-            samples_written: 0,
+            samples_written,
             samples_to_write: 0..0,
             is_reading: false,
             repeat,
+            loop_range,
+            skip_to_frame: (region.start > 0).then_some(region.start),
+            region,
+            markers: preallocated_markers(&[]),
+            samples_produced: 0,
+            file_id: 0,
+            file_path: Arc::default(),
+            playback_status_send: None,
+            playback_status_context: None,
         }
     }
 }
@@ -488,6 +817,30 @@ impl Actor for StreamedFileWorker {
     fn handle(&mut self, msg: StreamedFileSourceMessage) -> Result<Act<Self>, Self::Error> {
         match msg {
             StreamedFileSourceMessage::Seek(time) => self.on_seek(time),
+            StreamedFileSourceMessage::SetLoop(range) => {
+                self.loop_range = range;
+                Ok(Act::Continue)
+            }
+            StreamedFileSourceMessage::AddMarker(marker) => {
+                let _ = insert_marker(&mut self.markers, marker);
+                Ok(Act::Continue)
+            }
+            StreamedFileSourceMessage::RemoveMarker(marker_id) => {
+                self.markers.retain(|marker| marker.id != marker_id);
+                Ok(Act::Continue)
+            }
+            StreamedFileSourceMessage::SetPlaybackStatus {
+                id,
+                path,
+                sender,
+                context,
+            } => {
+                self.file_id = id;
+                self.file_path = path;
+                self.playback_status_send = sender;
+                self.playback_status_context = context;
+                Ok(Act::Continue)
+            }
             StreamedFileSourceMessage::Read => self.on_read(),
             StreamedFileSourceMessage::Stop => self.on_stop(),
         }
@@ -514,25 +867,47 @@ impl StreamedFileWorker {
         }
     }
 
+    fn send_metadata_event(&self, metadata: FileMetadata) {
+        if let Some(event_send) = &self.playback_status_send {
+            if let Err(err) = event_send.try_send(PlaybackStatusEvent::MetadataChanged {
+                id: self.file_id,
+                path: self.file_path.clone(),
+                context: self.playback_status_context.clone(),
+                metadata,
+            }) {
+                log::warn!("failed to send metadata event: {}", err)
+            }
+        }
+    }
+
     fn on_seek(&mut self, time: Duration) -> Result<Act<Self>, Error> {
-        match self.input.seek(time) {
-            Ok(timestamp) => {
+        // seek positions are relative to the played region
+        let frame = self.region.start + (time.as_secs_f64() * self.input_spec.rate as f64) as u64;
+        match self.input.seek_frame(frame) {
+            Ok(_) => {
                 if self.is_reading {
                     self.samples_to_write = 0..0;
                 } else {
                     self.this.send(StreamedFileSourceMessage::Read)?;
                 }
-                let position = timestamp * self.input_spec.channels.count() as u64;
-                self.samples_written = position;
-                self.shared_state
-                    .position
-                    .store(position, Ordering::Relaxed);
+                self.skip_to_frame = Some(frame);
+                self.set_position(frame);
+                // discard buffered samples and markers
+                self.samples_produced -= self.output.count() as u64;
                 self.output.clear();
+                while self.shared_state.reached_markers.pop().is_some() {}
+                self.shared_state
+                    .end_of_file
+                    .store(false, Ordering::Relaxed);
             }
             Err(err) => {
                 log::error!("failed to seek: {}", err);
             }
         }
+        // let the source read from the ring-buffer again
+        self.shared_state
+            .handled_seeks
+            .fetch_add(1, Ordering::Release);
         Ok(Act::Continue)
     }
 
@@ -543,11 +918,28 @@ impl StreamedFileWorker {
         }
         // check if we need to fetch more input samples
         if !self.samples_to_write.is_empty() {
-            let input = &self.input_packet.samples()[self.samples_to_write.clone()];
+            // don't write past the loop end while repeating or past the region end
+            let channel_count = self.input_spec.channels.count() as u64;
+            let loop_end = self.loop_end_sample();
+            let region_end = self.region.end.saturating_mul(channel_count);
+            let write_end = loop_end.unwrap_or(region_end).min(region_end);
+            let remaining = write_end.saturating_sub(self.samples_written);
+            let mut samples_to_write = self.samples_to_write.clone();
+            samples_to_write.end = samples_to_write.end.min(
+                samples_to_write.start + remaining.min(samples_to_write.len() as u64) as usize,
+            );
+            if samples_to_write.is_empty() {
+                return self.on_end_of_region();
+            }
+            let input = &self.input_packet.samples()[samples_to_write];
             // TODO: self.output_fader.process(&mut input_mut.borrow_mut());
             if let Ok(written) = self.output_producer.write(input) {
+                self.push_reached_markers(written as u64);
                 self.samples_written += written as u64;
                 self.samples_to_write.start += written;
+                if self.samples_written >= write_end {
+                    return self.on_end_of_region();
+                }
                 self.is_reading = true;
                 self.this.send(StreamedFileSourceMessage::Read)?;
                 Ok(Act::Continue)
@@ -564,36 +956,179 @@ impl StreamedFileWorker {
         } else {
             // fetch more input samples
             match self.input.read_packet(&mut self.input_packet) {
-                Some(_) => {
+                Some(timestamp) => {
+                    // report changed tags
+                    if let Some(metadata) = self.input.take_metadata_update() {
+                        self.send_metadata_event(metadata);
+                    }
                     // continue reading
                     self.samples_to_write = 0..self.input_packet.samples().len();
+                    if let Some(skip_to_frame) = self.skip_to_frame {
+                        // skip samples before the seek target: seeking may end up in front of it
+                        let channel_count = self.input_spec.channels.count();
+                        let packet_frames = (self.samples_to_write.end / channel_count) as u64;
+                        let skip_frames = skip_to_frame.saturating_sub(timestamp);
+                        if skip_frames < packet_frames {
+                            self.samples_to_write.start = skip_frames as usize * channel_count;
+                            self.skip_to_frame = None;
+                        } else {
+                            self.samples_to_write.start = self.samples_to_write.end;
+                        }
+                    }
                     self.is_reading = true;
                     self.this.send(StreamedFileSourceMessage::Read)?;
+                    Ok(Act::Continue)
                 }
                 None => {
                     // reached EOF
-                    if self.repeat > 0 {
-                        if self.repeat != usize::MAX {
-                            self.repeat -= 1;
-                        }
-                        // seek to start and continue reading
-                        self.input.seek(Duration::ZERO)?;
-                        self.samples_written = 0;
-                        self.samples_to_write = 0..0;
-                        self.shared_state.position.store(0, Ordering::Relaxed);
-                        self.is_reading = true;
-                        self.this.send(StreamedFileSourceMessage::Read)?;
-                    } else {
-                        // stop reading and mark as exhausted
-                        self.is_reading = false;
-                        self.shared_state.end_of_file.store(true, Ordering::Relaxed);
-                        self.shared_state
-                            .total_samples
-                            .store(self.samples_written, Ordering::Relaxed);
+                    if self.skip_to_frame.is_some() {
+                        log::warn!("seek or loop position is beyond the end of the file");
+                        self.repeat = 0;
+                        self.skip_to_frame = None;
                     }
+                    self.on_end_of_region()
                 }
             }
-            Ok(Act::Continue)
+        }
+    }
+
+    /// Repeat the loop region when reaching the loop end, or the end of the played region or
+    /// file, or stop reading and mark the source as exhausted.
+    fn on_end_of_region(&mut self) -> Result<Act<Self>, Error> {
+        if self.repeat > 0 {
+            // seek to loop start and continue reading
+            self.seek_to_loop_start()?;
+            self.is_reading = true;
+            self.this.send(StreamedFileSourceMessage::Read)?;
+        } else {
+            // stop reading and mark as exhausted
+            self.is_reading = false;
+            self.samples_to_write = 0..0;
+            self.shared_state.end_of_file.store(true, Ordering::Relaxed);
+            let region_start = self.region.start * self.input_spec.channels.count() as u64;
+            self.shared_state
+                .total_samples
+                .store(self.samples_written - region_start, Ordering::Relaxed);
+        }
+        Ok(Act::Continue)
+    }
+
+    /// Loop end in samples, when the loop end should be applied at the current position.
+    fn loop_end_sample(&self) -> Option<u64> {
+        let loop_range = self.loop_range.as_ref()?;
+        let channel_count = self.input_spec.channels.count() as u64;
+        let loop_end = loop_range.end.saturating_mul(channel_count);
+        if self.repeat > 0 && self.samples_written < loop_end {
+            Some(loop_end)
+        } else {
+            None
+        }
+    }
+
+    /// Use up one repeat and seek to the start of the loop region.
+    fn seek_to_loop_start(&mut self) -> Result<(), Error> {
+        if self.repeat != usize::MAX {
+            self.repeat -= 1;
+        }
+        let loop_start = self.loop_range.as_ref().map_or(self.region.start, |range| {
+            range.start.max(self.region.start)
+        });
+        self.input.seek_frame(loop_start)?;
+        self.skip_to_frame = Some(loop_start);
+        self.samples_to_write = 0..0;
+        self.set_position(loop_start);
+        Ok(())
+    }
+
+    /// Pass markers within the given number of samples, which just got written into the
+    /// ring-buffer at the current position, to our parent source.
+    fn push_reached_markers(&mut self, written: u64) {
+        let channel_count = self.input_spec.channels.count() as u64;
+        let written_range = self.samples_written..self.samples_written + written;
+        for marker in &self.markers {
+            let marker_sample = marker.frame.saturating_mul(channel_count);
+            if written_range.contains(&marker_sample) {
+                let reached = ReachedMarker {
+                    stream_sample: self.samples_produced + marker_sample - written_range.start,
+                    marker: marker.clone(),
+                };
+                if self.shared_state.reached_markers.push(reached).is_err() {
+                    log::warn!("failed to pass reached marker: queue is full");
+                }
+            }
+        }
+        self.samples_produced += written;
+    }
+
+    /// Set the written samples counter and the shared, region relative position to the given
+    /// file frame.
+    fn set_position(&mut self, frame: u64) {
+        let channel_count = self.input_spec.channels.count() as u64;
+        self.samples_written = frame * channel_count;
+        let position = frame.saturating_sub(self.region.start) * channel_count;
+        self.shared_state
+            .position
+            .store(position, Ordering::Relaxed);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::file::test_utils::wav_file;
+
+    #[test]
+    fn seek() {
+        // sample values are the frame numbers
+        let samples = (0..44100).map(|i| i as f32).collect::<Vec<_>>();
+        for crossfade in [None, Some(Duration::from_millis(1))] {
+            let mut streamed = StreamedFileSource::new(
+                wav_file(&samples),
+                None,
+                FilePlaybackOptions::default(),
+                44100,
+            )
+            .unwrap();
+            let mut output = vec![0.0; 64];
+            let mut write = |streamed: &mut StreamedFileSource| loop {
+                let written = streamed.write(&mut output, &SourceTime::default());
+                if written > 0 {
+                    return output[..written].to_vec();
+                }
+                std::thread::sleep(Duration::from_millis(1));
+            };
+            let before_seek = write(&mut streamed);
+            assert!(before_seek.iter().all(|sample| *sample < 1000.0));
+
+            // seek into the half of the file
+            let message = match crossfade {
+                Some(crossfade) => FilePlaybackMessage::SeekWithCrossfade {
+                    position: Duration::from_millis(500),
+                    crossfade,
+                },
+                None => FilePlaybackMessage::Seek(Duration::from_millis(500)),
+            };
+            assert!(streamed.event_queue.push(message).is_ok());
+            let mut after_seek = Vec::new();
+            while after_seek.len() < 256 {
+                after_seek.append(&mut write(&mut streamed));
+            }
+
+            // skip the crossfaded tail and the dropout until the worker seeked: no stale samples
+            // must get played
+            let crossfade_len = crossfade.map_or(0, |_| 44);
+            let seeked = after_seek[crossfade_len..]
+                .iter()
+                .copied()
+                .filter(|sample| *sample != 0.0)
+                .collect::<Vec<_>>();
+            assert!(seeked[0] >= 22050.0);
+            if crossfade.is_none() {
+                assert_eq!(seeked[0], 22050.0);
+            }
+            assert!(seeked.windows(2).all(|window| window[1] == window[0] + 1.0));
         }
     }
 }
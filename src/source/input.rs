@@ -0,0 +1,410 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::Sender;
+use crossbeam_queue::ArrayQueue;
+use rb::{Consumer, Producer, RbConsumer, RbInspector, RbProducer, SpscRb, RB};
+
+use crate::{
+    player::{PlaybackId, PlaybackStatusContext, PlaybackStatusEvent},
+    source::{
+        synth::{SynthPlaybackMessage, SynthPlaybackOptions, SynthSource},
+        Source, SourceTime,
+    },
+    utils::{
+        fader::{PauseFader, VolumeFader},
+        smoothing::SmoothedValue,
+        unique_usize_id,
+    },
+    Error,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// State which is shared between an [`InputSource`] and its [`InputSourceProducer`].
+struct InputSourceState {
+    source_alive: AtomicBool,
+    producer_alive: AtomicBool,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Writing end of an [`InputSource`]'s ring buffer. Gets fed by an audio input device's
+/// capture callback.
+pub(crate) struct InputSourceProducer {
+    producer: Producer<f32>,
+    state: Arc<InputSourceState>,
+}
+
+impl InputSourceProducer {
+    /// True as long as the input source, which consumes the samples, is alive.
+    pub fn is_connected(&self) -> bool {
+        self.state.source_alive.load(Ordering::Relaxed)
+    }
+
+    /// Create a handle which tracks if the input source is alive, without owning the producer.
+    pub fn connection(&self) -> InputSourceConnection {
+        InputSourceConnection {
+            state: Arc::clone(&self.state),
+        }
+    }
+
+    /// Push captured interleaved samples into the ring buffer, without blocking. Samples which
+    /// don't fit into the buffer, because the source doesn't consume them fast enough, are
+    /// dropped.
+    pub fn write(&self, samples: &[f32]) {
+        let _ = self.producer.write(samples);
+    }
+}
+
+impl Drop for InputSourceProducer {
+    fn drop(&mut self) {
+        self.state.producer_alive.store(false, Ordering::Relaxed);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Tracks if an [`InputSource`] is alive, from outside of the capture callback.
+pub(crate) struct InputSourceConnection {
+    state: Arc<InputSourceState>,
+}
+
+impl InputSourceConnection {
+    /// True as long as the input source is alive.
+    pub fn is_connected(&self) -> bool {
+        self.state.source_alive.load(Ordering::Relaxed)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A source which plays back audio, which got captured by an audio input device, such as a
+/// [`CpalInput`](crate::CpalInput).
+///
+/// Captured samples are passed from the device via a lock-free ring buffer. When the device
+/// delivers more samples than the source consumes, e.g. because the input and output device
+/// clocks drift apart, the oldest buffered samples get skipped to keep the latency bounded.
+/// When the source consumes faster than the device delivers, missing samples are rendered as
+/// silence.
+///
+/// Input sources are [`SynthSource`]s: they can be played, faded and stopped via the player
+/// like any other synth source. They are exhausted when they got stopped or when the input
+/// device got closed.
+pub struct InputSource {
+    buffer: SpscRb<f32>,
+    consumer: Consumer<f32>,
+    state: Arc<InputSourceState>,
+    channel_count: usize,
+    sample_rate: u32,
+    max_buffered_samples: usize,
+    volume: SmoothedValue,
+    volume_fader: VolumeFader,
+    pause_fader: PauseFader,
+    playback_message_queue: Arc<ArrayQueue<SynthPlaybackMessage>>,
+    playback_status_send: Option<Sender<PlaybackStatusEvent>>,
+    playback_status_context: Option<PlaybackStatusContext>,
+    playback_id: PlaybackId,
+    playback_name: Arc<String>,
+    playback_options: SynthPlaybackOptions,
+    playback_pos: u64,
+    playback_pos_report_instant: Instant,
+    playback_finished: bool,
+}
+
+impl InputSource {
+    /// Create a new input source and the producer which feeds it. `max_latency` is the maximum
+    /// duration of buffered input, before old samples get skipped.
+    pub(crate) fn new(
+        input_name: &str,
+        channel_count: usize,
+        sample_rate: u32,
+        max_latency: Duration,
+        options: SynthPlaybackOptions,
+        event_send: Option<Sender<PlaybackStatusEvent>>,
+    ) -> Result<(Self, InputSourceProducer), Error> {
+        // validate options
+        options.validate()?;
+        // create ring buffer which holds up to 4 times the max latency, to avoid that
+        // writes from the device fail before we got a chance to skip old samples
+        let max_buffered_frames = (max_latency.as_secs_f64() * sample_rate as f64) as usize;
+        let max_buffered_samples = max_buffered_frames.max(1) * channel_count;
+        let buffer = SpscRb::new(max_buffered_samples * 4);
+        let state = Arc::new(InputSourceState {
+            source_alive: AtomicBool::new(true),
+            producer_alive: AtomicBool::new(true),
+        });
+        let producer = InputSourceProducer {
+            producer: buffer.producer(),
+            state: Arc::clone(&state),
+        };
+        // create volume fader
+        let mut volume_fader = VolumeFader::new(channel_count, sample_rate);
+        volume_fader.set_curve(options.fade_curve);
+        if let Some(duration) = options.fade_in_duration {
+            volume_fader.start_fade_in(duration);
+        }
+        let playback_message_queue = Arc::new(ArrayQueue::new(128));
+        let source = Self {
+            consumer: buffer.consumer(),
+            buffer,
+            state,
+            channel_count,
+            sample_rate,
+            max_buffered_samples,
+            volume: SmoothedValue::new(options.volume, sample_rate),
+            volume_fader,
+            pause_fader: PauseFader::new(sample_rate),
+            playback_message_queue,
+            playback_status_send: event_send,
+            playback_status_context: None,
+            playback_id: unique_usize_id(),
+            playback_name: Arc::new(input_name.to_string()),
+            playback_options: options,
+            playback_pos: 0,
+            playback_pos_report_instant: Instant::now(),
+            playback_finished: false,
+        };
+        Ok((source, producer))
+    }
+
+    fn should_report_pos(&mut self) -> bool {
+        if let Some(report_duration) = self.playback_options.playback_pos_emit_rate {
+            let should_report = self.playback_pos_report_instant.elapsed() >= report_duration;
+            self.playback_pos_report_instant = Instant::now();
+            should_report
+        } else {
+            false
+        }
+    }
+
+    fn send_status_event(&self, event: PlaybackStatusEvent) {
+        if let Some(event_send) = &self.playback_status_send {
+            if let Err(err) = event_send.try_send(event) {
+                log::warn!("failed to send input playback status event: {}", err);
+            }
+        }
+    }
+
+    fn samples_to_duration(&self, samples: u64) -> Duration {
+        let frames = samples / self.channel_count as u64;
+        let seconds = frames as f64 / self.sample_rate as f64;
+        Duration::from_millis((seconds * 1000.0) as u64)
+    }
+}
+
+impl Drop for InputSource {
+    fn drop(&mut self) {
+        self.state.source_alive.store(false, Ordering::Relaxed);
+    }
+}
+
+impl SynthSource for InputSource {
+    fn playback_id(&self) -> PlaybackId {
+        self.playback_id
+    }
+
+    fn playback_message_queue(&self) -> Arc<ArrayQueue<SynthPlaybackMessage>> {
+        self.playback_message_queue.clone()
+    }
+
+    fn playback_status_sender(&self) -> Option<Sender<PlaybackStatusEvent>> {
+        self.playback_status_send.clone()
+    }
+    fn set_playback_status_sender(&mut self, sender: Option<Sender<PlaybackStatusEvent>>) {
+        self.playback_status_send = sender;
+    }
+
+    fn playback_status_context(&self) -> Option<PlaybackStatusContext> {
+        self.playback_status_context.clone()
+    }
+    fn set_playback_status_context(&mut self, context: Option<PlaybackStatusContext>) {
+        self.playback_status_context = context;
+    }
+}
+
+impl Source for InputSource {
+    fn write(&mut self, output: &mut [f32], _time: &SourceTime) -> usize {
+        // receive playback events
+        let mut stop_playing = false;
+        while let Some(msg) = self.playback_message_queue.pop() {
+            match msg {
+                SynthPlaybackMessage::SetVolume(volume) => {
+                    self.volume.set_target(volume);
+                }
+                SynthPlaybackMessage::FadeVolume { volume, duration } => {
+                    self.volume_fader.fade_to(volume, duration);
+                }
+                SynthPlaybackMessage::Pause => {
+                    self.pause_fader.pause();
+                }
+                SynthPlaybackMessage::Resume => {
+                    if self.pause_fader.resume() {
+                        self.send_status_event(PlaybackStatusEvent::Resumed {
+                            id: self.playback_id,
+                            context: self.playback_status_context.clone(),
+                            path: self.playback_name.clone(),
+                        });
+                    }
+                }
+                SynthPlaybackMessage::Stop => {
+                    if self.pause_fader.is_paused() {
+                        // we're silent already: stop without fading out
+                        stop_playing = true;
+                    } else if let Some(duration) = self.playback_options.fade_out_duration {
+                        if !duration.is_zero() {
+                            self.volume_fader.start_fade_out(duration);
+                        } else {
+                            stop_playing = true;
+                        }
+                    } else {
+                        stop_playing = true;
+                    }
+                }
+            }
+        }
+
+        // return empty handed when playback finished
+        if self.playback_finished {
+            return 0;
+        }
+
+        // skip all captured samples while paused
+        if self.pause_fader.is_paused() {
+            let buffered = self.buffer.count();
+            let _ = self.consumer.skip(buffered - buffered % self.channel_count);
+            let is_exhausted = !self.state.producer_alive.load(Ordering::Relaxed);
+            if stop_playing || is_exhausted {
+                self.playback_finished = true;
+                self.send_status_event(PlaybackStatusEvent::Stopped {
+                    id: self.playback_id,
+                    context: self.playback_status_context.clone(),
+                    path: self.playback_name.clone(),
+                    exhausted: is_exhausted,
+                });
+            }
+            return 0;
+        }
+
+        // skip old samples when the input runs ahead of us
+        let buffered = self.buffer.count();
+        if buffered > self.max_buffered_samples {
+            let skip = buffered - self.max_buffered_samples;
+            let _ = self.consumer.skip(skip - skip % self.channel_count);
+        }
+
+        // read captured samples and fill up missing ones with silence
+        let read = self.consumer.read(output).unwrap_or(0);
+        output[read..].iter_mut().for_each(|s| *s = 0.0);
+        let written = output.len();
+
+        // apply volume
+        self.volume.apply(output, self.channel_count);
+        // apply volume fader
+        self.volume_fader.process(output);
+        // apply pause fader
+        if self.pause_fader.process(output, self.channel_count) {
+            self.send_status_event(PlaybackStatusEvent::Paused {
+                id: self.playback_id,
+                context: self.playback_status_context.clone(),
+                path: self.playback_name.clone(),
+            });
+        }
+
+        // update playback pos
+        self.playback_pos += written as u64;
+
+        // send Position Event
+        if self.should_report_pos() {
+            if let Some(event_send) = &self.playback_status_send {
+                // NB: try_send: we want to ignore full channels on playback pos events and don't want to block
+                if let Err(err) = event_send.try_send(PlaybackStatusEvent::Position {
+                    id: self.playback_id,
+                    context: self.playback_status_context.clone(),
+                    path: self.playback_name.clone(),
+                    position: self.samples_to_duration(self.playback_pos),
+                }) {
+                    log::warn!("Failed to send playback event: {}", err)
+                }
+            }
+        }
+
+        // check if the input got closed and send Stopped event
+        let is_exhausted =
+            !self.state.producer_alive.load(Ordering::Relaxed) && self.buffer.is_empty();
+        let fade_out_finished = self.volume_fader.fade_out_finished();
+        if stop_playing || is_exhausted || fade_out_finished {
+            self.playback_finished = true;
+            if let Some(event_send) = &self.playback_status_send {
+                if let Err(err) = event_send.try_send(PlaybackStatusEvent::Stopped {
+                    id: self.playback_id,
+                    context: self.playback_status_context.clone(),
+                    path: self.playback_name.clone(),
+                    exhausted: is_exhausted,
+                }) {
+                    log::warn!("failed to send input playback status event: {}", err);
+                }
+            }
+        }
+
+        written
+    }
+
+    fn channel_count(&self) -> usize {
+        self.channel_count
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.playback_finished
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_captured_samples() {
+        let options = SynthPlaybackOptions {
+            fade_out_duration: None,
+            ..Default::default()
+        };
+        let (mut source, producer) =
+            InputSource::new("input", 1, 1000, Duration::from_millis(4), options, None).unwrap();
+        let time = SourceTime::new();
+
+        // missing samples are silent
+        producer.write(&[0.1, 0.2]);
+        let mut output = [1.0; 4];
+        assert_eq!(source.write(&mut output, &time), 4);
+        assert_eq!(output, [0.1, 0.2, 0.0, 0.0]);
+
+        // old samples get skipped when exceeding the max latency
+        producer.write(&[0.1, 0.2, 0.3, 0.4, 0.5, 0.6]);
+        assert_eq!(source.write(&mut output, &time), 4);
+        assert_eq!(output, [0.3, 0.4, 0.5, 0.6]);
+
+        // exhausted when the producer got closed
+        assert!(producer.is_connected());
+        drop(producer);
+        source.write(&mut output, &time);
+        assert!(source.is_exhausted());
+
+        // producers notice dropped sources
+        let (source, producer) =
+            InputSource::new("input", 1, 1000, Duration::from_millis(4), options, None).unwrap();
+        drop(source);
+        assert!(!producer.is_connected());
+    }
+}
@@ -1,12 +1,13 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use basedrop::Owned;
 use crossbeam_queue::ArrayQueue;
 use sort::bubble_sort_cmp;
 
 use crate::{
-    player::PlaybackMessageSender,
+    player::{BusId, PlaybackMessageSender},
     source::{Source, SourceTime},
+    utils::{automation::VolumeEnvelope, fader::FadeCurve},
     PlaybackId,
 };
 
@@ -16,10 +17,74 @@ use crate::{
 struct MixedPlayingSource {
     is_active: bool,
     playback_id: PlaybackId,
-    playback_message_queue: PlaybackMessageSender,
+    playback_message_queue: Option<PlaybackMessageSender>,
     source: Owned<Box<dyn Source>>,
     start_time: u64,
     stop_time: Option<u64>,
+    seek: Option<MixedSourceSeek>,
+    fade: Option<MixedSourceFade>,
+    envelope: Option<Owned<VolumeEnvelope>>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Mixer internal struct to keep track of a scheduled seek of a playing source.
+struct MixedSourceSeek {
+    sample_time: u64,
+    position: Duration,
+    crossfade: Option<Duration>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Mixer internal struct to keep track of a scheduled volume fade of a playing source.
+#[derive(Clone, Copy)]
+struct MixedSourceFade {
+    sample_time: u64,
+    length: u64,
+    curve: FadeCurve,
+    fade_in: bool,
+}
+
+impl MixedSourceFade {
+    /// Volume of the source at the given absolute sample frame time.
+    #[inline]
+    fn volume_at(&self, sample_time: u64) -> f32 {
+        let position = if sample_time < self.sample_time {
+            0.0
+        } else if sample_time >= self.sample_time + self.length {
+            1.0
+        } else {
+            (sample_time - self.sample_time) as f32 / self.length as f32
+        };
+        if self.fade_in {
+            self.curve.fade_in_volume(position)
+        } else {
+            self.curve.fade_in_volume(1.0 - position)
+        }
+    }
+
+    /// Apply the fade to the given interleaved output, which starts at the given sample time.
+    fn process(&self, output: &mut [f32], channel_count: usize, sample_time: u64) {
+        let frame_count = (output.len() / channel_count) as u64;
+        if self.fade_in && sample_time >= self.sample_time + self.length {
+            return; // fully faded in
+        }
+        if !self.fade_in && sample_time + frame_count <= self.sample_time {
+            return; // not yet fading out
+        }
+        for (index, frame) in output.chunks_exact_mut(channel_count).enumerate() {
+            let volume = self.volume_at(sample_time + index as u64);
+            for sample in frame {
+                *sample *= volume;
+            }
+        }
+    }
+
+    /// True when the fade-in completed before the given sample time.
+    fn is_finished(&self, sample_time: u64) -> bool {
+        self.fade_in && sample_time >= self.sample_time + self.length
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -32,10 +97,39 @@ pub enum MixedSourceMsg {
         source: Owned<Box<dyn Source>>,
         sample_time: u64,
     },
+    /// Add a nested mixer bus, which plays until it gets removed via `RemoveBus`.
+    AddBus {
+        bus_id: BusId,
+        source: Owned<Box<dyn Source>>,
+    },
+    /// Remove a nested mixer bus, dropping all sources which still play in it.
+    RemoveBus {
+        bus_id: BusId,
+    },
     StopSource {
         playback_id: PlaybackId,
         sample_time: u64,
     },
+    SeekSource {
+        playback_id: PlaybackId,
+        position: Duration,
+        crossfade: Option<Duration>,
+        sample_time: u64,
+    },
+    /// Fade a source in or out with the given length in frames, starting at the given sample
+    /// time. Sources which fade out get stopped when the fade completed.
+    FadeSource {
+        playback_id: PlaybackId,
+        sample_time: u64,
+        length: u64,
+        curve: FadeCurve,
+        fade_in: bool,
+    },
+    /// Replace or, when None, clear the volume automation envelope of a source.
+    SetVolumeEnvelope {
+        playback_id: PlaybackId,
+        envelope: Option<Owned<VolumeEnvelope>>,
+    },
     #[allow(dead_code)]
     RemoveAllSources,
     RemoveAllPendingSources,
@@ -122,12 +216,38 @@ impl Source for MixedSource {
                     self.playing_sources.push(MixedPlayingSource {
                         is_active: true,
                         playback_id,
-                        playback_message_queue,
+                        playback_message_queue: Some(playback_message_queue),
                         source,
                         start_time: sample_time,
                         stop_time: None,
+                        seek: None,
+                        fade: None,
+                        envelope: None,
                     });
                 }
+                MixedSourceMsg::AddBus { bus_id, source } => {
+                    debug_assert_eq!(
+                        source.channel_count(),
+                        self.channel_count,
+                        "adjust bus' channel layout before adding it"
+                    );
+                    got_new_sources = true;
+                    self.playing_sources.push(MixedPlayingSource {
+                        is_active: true,
+                        playback_id: bus_id,
+                        playback_message_queue: None,
+                        source,
+                        start_time: 0,
+                        stop_time: None,
+                        seek: None,
+                        fade: None,
+                        envelope: None,
+                    });
+                }
+                MixedSourceMsg::RemoveBus { bus_id } => {
+                    // NB: the removed bus gets dropped by the player's collector
+                    self.remove_matching_sources(|source| source.playback_id == bus_id);
+                }
                 MixedSourceMsg::StopSource {
                     playback_id,
                     sample_time,
@@ -139,6 +259,59 @@ impl Source for MixedSource {
                         }
                     }
                 }
+                MixedSourceMsg::SeekSource {
+                    playback_id,
+                    position,
+                    crossfade,
+                    sample_time,
+                } => {
+                    for source in self.playing_sources.iter_mut() {
+                        if source.playback_id == playback_id {
+                            source.seek = Some(MixedSourceSeek {
+                                sample_time,
+                                position,
+                                crossfade,
+                            });
+                            break;
+                        }
+                    }
+                }
+                MixedSourceMsg::FadeSource {
+                    playback_id,
+                    sample_time,
+                    length,
+                    curve,
+                    fade_in,
+                } => {
+                    // NB: fades which should have started already start now
+                    let sample_time = sample_time.max(time.pos_in_frames);
+                    for source in self.playing_sources.iter_mut() {
+                        if source.playback_id == playback_id {
+                            source.fade = Some(MixedSourceFade {
+                                sample_time,
+                                length,
+                                curve,
+                                fade_in,
+                            });
+                            if !fade_in {
+                                source.stop_time = Some(sample_time + length);
+                            }
+                            break;
+                        }
+                    }
+                }
+                MixedSourceMsg::SetVolumeEnvelope {
+                    playback_id,
+                    envelope,
+                } => {
+                    // NB: replaced envelopes get dropped by the player's collector
+                    for source in self.playing_sources.iter_mut() {
+                        if source.playback_id == playback_id {
+                            source.envelope = envelope;
+                            break;
+                        }
+                    }
+                }
                 MixedSourceMsg::RemoveAllPendingSources => {
                     // remove all sources which are not yet playing
                     self.remove_matching_sources(|source| source.start_time > time.pos_in_frames);
@@ -197,20 +370,54 @@ impl Source for MixedSource {
                     }
                 }
                 if samples_until_stop == 0 {
-                    let sender = &playing_source.playback_message_queue;
-                    if let Err(err) = sender.send_stop() {
-                        log::warn!("failed to send stop event: {}", err)
+                    if let Some(sender) = &playing_source.playback_message_queue {
+                        if let Err(err) = sender.send_stop() {
+                            log::warn!("failed to send stop event: {}", err)
+                        }
                     }
                     samples_until_stop = u64::MAX;
                 }
+                // check if there's a pending seek command for the source
+                let mut samples_until_seek = u64::MAX;
+                if let Some(seek) = &playing_source.seek {
+                    if seek.sample_time > source_time.pos_in_frames {
+                        samples_until_seek = (seek.sample_time - source_time.pos_in_frames)
+                            * self.channel_count as u64;
+                    } else {
+                        if let Some(sender) = &playing_source.playback_message_queue {
+                            if let Err(err) = sender.send_seek(seek.position, seek.crossfade) {
+                                log::warn!("failed to send seek event: {}", err)
+                            }
+                        }
+                        playing_source.seek = None;
+                    }
+                }
                 // run source on temp_out until we've filled up the whole final output
-                let remaining = (output.len() - total_written).min(samples_until_stop as usize);
+                let remaining = (output.len() - total_written)
+                    .min(samples_until_stop as usize)
+                    .min(samples_until_seek as usize);
                 let to_write = remaining.min(self.temp_out.len());
                 let written = source.write(&mut self.temp_out[..to_write], &source_time);
-                if source.is_exhausted() {
-                    // source no longer is playing: mark it as inactive
-                    playing_source.is_active = false;
-                    break 'source;
+                // apply scheduled volume fades
+                if let Some(fade) = &playing_source.fade {
+                    fade.process(
+                        &mut self.temp_out[..written],
+                        self.channel_count,
+                        source_time.pos_in_frames,
+                    );
+                    let end_time =
+                        source_time.pos_in_frames + (written / self.channel_count) as u64;
+                    if fade.is_finished(end_time) {
+                        playing_source.fade = None;
+                    }
+                }
+                // apply volume automation
+                if let Some(envelope) = &mut playing_source.envelope {
+                    envelope.process(
+                        &mut self.temp_out[..written],
+                        self.channel_count,
+                        source_time.pos_in_frames,
+                    );
                 }
                 // add output of the source to the final output
                 let remaining_out = &mut output[total_written..];
@@ -219,6 +426,15 @@ impl Source for MixedSource {
                     *o += *i;
                 }
                 total_written += written;
+                if source.is_exhausted() {
+                    // source no longer is playing: mark it as inactive
+                    playing_source.is_active = false;
+                    break 'source;
+                }
+                if written == 0 {
+                    // source has no more output for now: don't wait for it
+                    break 'source;
+                }
             }
             max_written = max_written.max(total_written);
         }
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use crossbeam_queue::ArrayQueue;
+
+use super::{Source, SourceTime};
+use crate::utils::smoothing::SmoothedValue;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Events to control the panning of a [`PannedSource`].
+pub(crate) enum PannedSourceMessage {
+    /// Set a new panning value in range [-1, 1].
+    SetPanning(f32),
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A source which applies stereo panning to the first two channels of some other source.
+///
+/// Panning is applied as balance: a value of -1 plays the left channel only, 1 the right channel
+/// only and 0 (the default) passes through the signal unmodified. Panning changes get smoothed
+/// to avoid zipper noise. Mono sources are passed through as they are.
+pub(crate) struct PannedSource {
+    source: Box<dyn Source>,
+    panning: SmoothedValue,
+    message_queue: Arc<ArrayQueue<PannedSourceMessage>>,
+}
+
+impl PannedSource {
+    pub fn new<InputSource>(source: InputSource, panning: f32) -> Self
+    where
+        InputSource: Source,
+    {
+        let panning = SmoothedValue::new(panning.clamp(-1.0, 1.0), source.sample_rate());
+        Self {
+            source: Box::new(source),
+            panning,
+            message_queue: Arc::new(ArrayQueue::new(128)),
+        }
+    }
+
+    /// Message queue to control the panning.
+    pub fn message_queue(&self) -> Arc<ArrayQueue<PannedSourceMessage>> {
+        self.message_queue.clone()
+    }
+}
+
+impl Source for PannedSource {
+    fn write(&mut self, output: &mut [f32], time: &SourceTime) -> usize {
+        // consume messages
+        while let Some(msg) = self.message_queue.pop() {
+            match msg {
+                PannedSourceMessage::SetPanning(panning) => {
+                    self.panning.set_target(panning.clamp(-1.0, 1.0));
+                }
+            }
+        }
+
+        let written = self.source.write(output, time);
+
+        // apply panning to the first two channels
+        let channel_count = self.source.channel_count();
+        if channel_count >= 2 && (self.panning.is_ramping() || self.panning.target() != 0.0) {
+            for frame in output[..written].chunks_exact_mut(channel_count) {
+                let panning = self.panning.next_value();
+                frame[0] *= (1.0 - panning).min(1.0);
+                frame[1] *= (1.0 + panning).min(1.0);
+            }
+        }
+
+        written
+    }
+
+    fn channel_count(&self) -> usize {
+        self.source.channel_count()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.source.is_exhausted()
+    }
+}
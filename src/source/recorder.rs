@@ -0,0 +1,369 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use basedrop::{Handle, Owned};
+use crossbeam_queue::ArrayQueue;
+use rb::{Consumer, Producer, RbConsumer, RbProducer, SpscRb, RB};
+
+use crate::{
+    error::Error,
+    source::{Source, SourceTime},
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Duration of the ring buffer which passes recorded samples to the writer thread.
+const RECORDING_BUFFER_DURATION: Duration = Duration::from_secs(2);
+
+/// Interval in which the writer thread polls the ring buffer for new samples.
+const WRITER_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// -------------------------------------------------------------------------------------------------
+
+/// State which is shared between a [`RecorderSource`]'s active recording and its writer thread.
+pub(crate) struct RecordingState {
+    finished: AtomicBool,
+    dropped_samples: AtomicU64,
+}
+
+/// The ring buffer producer and state of a recording. Gets passed to the audio thread as
+/// [`Owned`], so the ring buffer never gets freed in the audio thread, even when the writer
+/// thread already finished.
+pub(crate) struct RecordingTarget {
+    producer: Producer<f32>,
+    state: Arc<RecordingState>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Events to control recording of a [`RecorderSource`].
+pub(crate) enum RecorderMsg {
+    /// Start recording at the given sample frame time.
+    Start {
+        target: Owned<RecordingTarget>,
+        sample_time: u64,
+    },
+    /// Stop recording at the given sample frame time.
+    Stop { sample_time: u64 },
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// An active recording in the [`RecorderSource`].
+struct Recording {
+    target: Owned<RecordingTarget>,
+    start_time: u64,
+    stop_time: Option<u64>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A source which passes through another source's output and taps it for recordings: While a
+/// recording is active, all written samples are pushed into a ring buffer without blocking,
+/// which is consumed by a [`WavRecorder`]'s writer thread.
+pub(crate) struct RecorderSource {
+    source: Box<dyn Source>,
+    event_queue: Arc<ArrayQueue<RecorderMsg>>,
+    recording: Option<Recording>,
+}
+
+impl RecorderSource {
+    pub fn new(source: impl Source) -> Self {
+        Self {
+            source: Box::new(source),
+            event_queue: Arc::new(ArrayQueue::new(16)),
+            recording: None,
+        }
+    }
+
+    /// Message queue to control recording.
+    pub fn event_queue(&self) -> Arc<ArrayQueue<RecorderMsg>> {
+        self.event_queue.clone()
+    }
+
+    fn finish_recording(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            recording
+                .target
+                .state
+                .finished
+                .store(true, Ordering::Release);
+        }
+    }
+}
+
+impl Source for RecorderSource {
+    fn write(&mut self, output: &mut [f32], time: &SourceTime) -> usize {
+        // process events
+        while let Some(msg) = self.event_queue.pop() {
+            match msg {
+                RecorderMsg::Start {
+                    target,
+                    sample_time,
+                } => {
+                    self.finish_recording();
+                    self.recording = Some(Recording {
+                        target,
+                        start_time: sample_time,
+                        stop_time: None,
+                    });
+                }
+                RecorderMsg::Stop { sample_time } => {
+                    if let Some(recording) = &mut self.recording {
+                        recording.stop_time = Some(sample_time);
+                    }
+                }
+            }
+        }
+
+        // run source and fill up the rest with silence, so recordings don't get out of sync
+        let written = self.source.write(output, time);
+        output[written..].iter_mut().for_each(|s| *s = 0.0);
+
+        // forget recordings which got finished by the writer
+        if self
+            .recording
+            .as_ref()
+            .is_some_and(|recording| recording.target.state.finished.load(Ordering::Relaxed))
+        {
+            self.recording = None;
+        }
+
+        // push the recorded range of the output into the recording's ring buffer
+        let mut finished = false;
+        if let Some(recording) = &self.recording {
+            let channel_count = self.source.channel_count();
+            let buffer_start = time.pos_in_frames;
+            let buffer_end = buffer_start + (output.len() / channel_count) as u64;
+            let record_start = recording.start_time.max(buffer_start);
+            let record_end = recording.stop_time.unwrap_or(u64::MAX).min(buffer_end);
+            if record_start < record_end {
+                let samples = &output[(record_start - buffer_start) as usize * channel_count
+                    ..(record_end - buffer_start) as usize * channel_count];
+                let pushed = recording.target.producer.write(samples).unwrap_or(0);
+                if pushed < samples.len() {
+                    recording
+                        .target
+                        .state
+                        .dropped_samples
+                        .fetch_add((samples.len() - pushed) as u64, Ordering::Relaxed);
+                }
+            }
+            finished = recording
+                .stop_time
+                .is_some_and(|stop_time| stop_time <= buffer_end);
+        }
+        if finished {
+            self.finish_recording();
+        }
+
+        output.len()
+    }
+
+    fn channel_count(&self) -> usize {
+        self.source.channel_count()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.source.is_exhausted()
+    }
+}
+
+impl Drop for RecorderSource {
+    fn drop(&mut self) {
+        self.finish_recording();
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Writes recordings of a [`RecorderSource`] into a 32-bit float WAV file on a background
+/// thread. The file gets finalized when the recording got stopped, or when the recorder
+/// gets dropped.
+pub(crate) struct WavRecorder {
+    state: Arc<RecordingState>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WavRecorder {
+    /// Create a new WAV file at the given path and start a writer thread for it. Returns the
+    /// recorder and the message which starts recording in the [`RecorderSource`]. The recording's
+    /// ring buffer gets dropped via the given collector handle.
+    pub fn new(
+        collector_handle: &Handle,
+        file_path: impl AsRef<Path>,
+        channel_count: usize,
+        sample_rate: u32,
+        sample_time: u64,
+    ) -> Result<(Self, RecorderMsg), Error> {
+        let spec = hound::WavSpec {
+            channels: channel_count as u16,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let writer = hound::WavWriter::create(file_path, spec)?;
+
+        let buffer_size =
+            (RECORDING_BUFFER_DURATION.as_secs_f64() * sample_rate as f64) as usize * channel_count;
+        let buffer = SpscRb::new(buffer_size);
+        let state = Arc::new(RecordingState {
+            finished: AtomicBool::new(false),
+            dropped_samples: AtomicU64::new(0),
+        });
+
+        let thread = thread::Builder::new()
+            .name("audio_recorder".to_string())
+            .spawn({
+                let consumer = buffer.consumer();
+                let state = Arc::clone(&state);
+                move || Self::write_samples(writer, consumer, &state)
+            })?;
+
+        let start_msg = RecorderMsg::Start {
+            target: Owned::new(
+                collector_handle,
+                RecordingTarget {
+                    producer: buffer.producer(),
+                    state: Arc::clone(&state),
+                },
+            ),
+            sample_time,
+        };
+        Ok((
+            Self {
+                state,
+                thread: Some(thread),
+            },
+            start_msg,
+        ))
+    }
+
+    /// True when the recording stopped and the file got finalized.
+    pub fn is_finished(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_none_or(|thread| thread.is_finished())
+    }
+
+    fn write_samples(
+        mut writer: hound::WavWriter<BufWriter<File>>,
+        consumer: Consumer<f32>,
+        state: &RecordingState,
+    ) {
+        let mut buffer = vec![0.0; 4096];
+        loop {
+            // NB: check the finished state before reading, so we don't miss the last samples
+            let finished = state.finished.load(Ordering::Acquire);
+            match consumer.read(&mut buffer) {
+                Ok(read) => {
+                    for sample in &buffer[..read] {
+                        if let Err(err) = writer.write_sample(*sample) {
+                            log::error!("failed to write recording: {}", err);
+                            return;
+                        }
+                    }
+                }
+                Err(_) => {
+                    if finished {
+                        break;
+                    }
+                    thread::sleep(WRITER_POLL_INTERVAL);
+                }
+            }
+        }
+        let dropped_samples = state.dropped_samples.load(Ordering::Relaxed);
+        if dropped_samples > 0 {
+            log::warn!(
+                "recording dropped {} samples: the writer thread is too slow",
+                dropped_samples
+            );
+        }
+        if let Err(err) = writer.finalize() {
+            log::error!("failed to finalize recording: {}", err);
+        }
+    }
+}
+
+impl Drop for WavRecorder {
+    fn drop(&mut self) {
+        // stop recording and wait until the file got finalized
+        self.state.finished.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        utils::unique_usize_id, FilePlaybackOptions, ManualOutput, OutputDevice, Player,
+        PreloadedFileSource,
+    };
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn record_range() {
+        let file_name = format!(
+            "phonic_recorder_test_{}_{}.wav",
+            std::process::id(),
+            unique_usize_id()
+        );
+        let file_path = std::env::temp_dir().join(file_name);
+        let output = ManualOutput::new(1, 44100);
+        let mut player = Player::new(output.sink(), None);
+
+        let buffer = (0..64).map(|i| i as f32 / 64.0).collect::<Vec<_>>();
+        let source = PreloadedFileSource::with_buffer(
+            Arc::new(buffer.clone()),
+            44100,
+            1,
+            "ramp",
+            None,
+            FilePlaybackOptions::default(),
+            44100,
+        )
+        .unwrap();
+        player.play_file_source(source, None).unwrap();
+
+        player.start_recording(&file_path, Some(4)).unwrap();
+        assert!(player.start_recording(&file_path, None).is_err());
+        player.stop_recording(Some(20)).unwrap();
+        output.render(16);
+        output.render(16);
+
+        // wait until the file got finalized
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while player.is_recording() {
+            assert!(Instant::now() < deadline, "recording did not finish");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let mut reader = hound::WavReader::open(&file_path).unwrap();
+        let samples = reader
+            .samples::<f32>()
+            .map(|s| s.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(samples, buffer[4..20]);
+
+        let _ = std::fs::remove_file(file_path);
+    }
+}
@@ -4,9 +4,9 @@ use crossbeam_channel::Sender;
 use crossbeam_queue::ArrayQueue;
 
 use crate::{
-    player::{PlaybackId, PlaybackStatusContext, PlaybackStatusEvent},
+    player::{BusId, PlaybackId, PlaybackStatusContext, PlaybackStatusEvent},
     source::Source,
-    utils::db_to_linear,
+    utils::{db_to_linear, fader::FadeCurve},
     Error,
 };
 
@@ -27,6 +27,9 @@ pub struct SynthPlaybackOptions {
     /// By default None: when set, the synth tone should start playing at the given
     /// sample frame time in the audio output stream.
     pub start_time: Option<u64>,
+    /// By default None: when set, the synth tone plays on the given mixer bus, else on the
+    /// player's main bus. See [`Player::add_bus`](crate::Player::add_bus).
+    pub bus: Option<BusId>,
 
     /// By default None: when set, the source's volume will fade in with the given
     /// amount when starting to play.
@@ -34,6 +37,9 @@ pub struct SynthPlaybackOptions {
     /// By default 5ms: volume fade-out duration, applied when the the source gets
     /// stopped before it finished playing.
     pub fade_out_duration: Option<Duration>,
+    /// By default FadeCurve::EqualPower: shape of the fade-in, fade-out and
+    /// [`Player::fade_source_volume`](crate::Player::fade_source_volume) volume fades.
+    pub fade_curve: FadeCurve,
 
     /// Wallclock time rate of playback pos events, emited via PlaybackStatusEvent
     /// in the player. By default one second to avoid unnecessary overhead.
@@ -46,8 +52,10 @@ impl Default for SynthPlaybackOptions {
         Self {
             volume: 1.0f32,
             start_time: None,
+            bus: None,
             fade_in_duration: None,
             fade_out_duration: Some(Duration::from_millis(50)),
+            fade_curve: FadeCurve::default(),
             playback_pos_emit_rate: Some(Duration::from_secs(1)),
         }
     }
@@ -71,12 +79,21 @@ impl SynthPlaybackOptions {
         self.fade_out_duration = Some(duration);
         self
     }
+    pub fn fade_curve(mut self, curve: FadeCurve) -> Self {
+        self.fade_curve = curve;
+        self
+    }
 
     pub fn start_at_time(mut self, sample_time: u64) -> Self {
         self.start_time = Some(sample_time);
         self
     }
 
+    pub fn bus(mut self, bus_id: BusId) -> Self {
+        self.bus = Some(bus_id);
+        self
+    }
+
     pub fn playback_pos_emit_rate(mut self, duration: std::time::Duration) -> Self {
         self.playback_pos_emit_rate = Some(duration);
         self
@@ -98,6 +115,15 @@ impl SynthPlaybackOptions {
 
 /// Events to control playback of a synth source
 pub enum SynthPlaybackMessage {
+    /// Smoothly change the synth's volume to the given linear volume factor
+    SetVolume(f32),
+    /// Fade the synth's fader volume to the given linear volume factor within exactly the
+    /// given duration, using the synth's [`SynthPlaybackOptions::fade_curve`].
+    FadeVolume { volume: f32, duration: Duration },
+    /// Pause the synth source with a short fade-out
+    Pause,
+    /// Resume a paused synth source with a short fade-in
+    Resume,
     /// Stop the synth source
     Stop,
 }
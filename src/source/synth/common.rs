@@ -11,7 +11,8 @@ use crate::{
     player::{PlaybackId, PlaybackStatusContext, PlaybackStatusEvent},
     source::{Source, SourceTime},
     utils::{
-        fader::{FaderState, VolumeFader},
+        fader::{PauseFader, VolumeFader},
+        smoothing::SmoothedValue,
         unique_usize_id,
     },
     Error,
@@ -36,7 +37,9 @@ where
 {
     generator: Box<Generator>,
     sample_rate: u32,
+    volume: SmoothedValue,
     volume_fader: VolumeFader,
+    pause_fader: PauseFader,
     playback_message_queue: Arc<ArrayQueue<SynthPlaybackMessage>>,
     playback_status_send: Option<Sender<PlaybackStatusEvent>>,
     playback_status_context: Option<PlaybackStatusContext>,
@@ -65,6 +68,7 @@ where
         options.validate()?;
         // create volume fader
         let mut volume_fader = VolumeFader::new(Self::CHANNEL_COUNT, sample_rate);
+        volume_fader.set_curve(options.fade_curve);
         if let Some(duration) = options.fade_in_duration {
             volume_fader.start_fade_in(duration);
         }
@@ -72,7 +76,9 @@ where
         Ok(Self {
             generator: Box::new(generator),
             sample_rate,
+            volume: SmoothedValue::new(options.volume, sample_rate),
             volume_fader,
+            pause_fader: PauseFader::new(sample_rate),
             playback_message_queue,
             playback_status_send: event_send,
             playback_id: unique_usize_id(),
@@ -95,6 +101,14 @@ where
         }
     }
 
+    fn send_status_event(&self, event: PlaybackStatusEvent) {
+        if let Some(event_send) = &self.playback_status_send {
+            if let Err(err) = event_send.try_send(event) {
+                log::warn!("failed to send synth playback status event: {}", err);
+            }
+        }
+    }
+
     fn samples_to_duration(&self, samples: u64) -> Duration {
         let frames = samples / Self::CHANNEL_COUNT as u64;
         let seconds = frames as f64 / self.sample_rate as f64;
@@ -136,10 +150,31 @@ where
     fn write(&mut self, output: &mut [f32], _time: &SourceTime) -> usize {
         // receive playback events
         let mut stop_playing = false;
-        if let Some(msg) = self.playback_message_queue.pop() {
+        while let Some(msg) = self.playback_message_queue.pop() {
             match msg {
+                SynthPlaybackMessage::SetVolume(volume) => {
+                    self.volume.set_target(volume);
+                }
+                SynthPlaybackMessage::FadeVolume { volume, duration } => {
+                    self.volume_fader.fade_to(volume, duration);
+                }
+                SynthPlaybackMessage::Pause => {
+                    self.pause_fader.pause();
+                }
+                SynthPlaybackMessage::Resume => {
+                    if self.pause_fader.resume() {
+                        self.send_status_event(PlaybackStatusEvent::Resumed {
+                            id: self.playback_id,
+                            context: self.playback_status_context.clone(),
+                            path: self.playback_name.clone(),
+                        });
+                    }
+                }
                 SynthPlaybackMessage::Stop => {
-                    if let Some(duration) = self.playback_options.fade_out_duration {
+                    if self.pause_fader.is_paused() {
+                        // we're silent already: stop without fading out
+                        stop_playing = true;
+                    } else if let Some(duration) = self.playback_options.fade_out_duration {
                         if !duration.is_zero() {
                             self.volume_fader.start_fade_out(duration);
                         } else {
@@ -157,18 +192,33 @@ where
             return 0;
         }
 
-        // run generator
-        let written = self.generator.generate(output);
+        // run generator: paused synths don't produce any output and keep their position
+        let is_paused = self.pause_fader.is_paused();
+        let written = if is_paused {
+            0
+        } else if let Some(frames) = self.pause_fader.frames_until_paused() {
+            let samples = (frames * Self::CHANNEL_COUNT).min(output.len());
+            self.generator.generate(&mut output[..samples])
+        } else {
+            self.generator.generate(output)
+        };
 
-        // apply volume option when <> 1
-        if (1.0 - self.playback_options.volume).abs() > 0.0001 {
-            let volume = self.playback_options.volume;
-            for o in output[0..written].as_mut() {
-                *o *= volume;
-            }
-        }
+        // apply volume
+        self.volume
+            .apply(&mut output[0..written], Self::CHANNEL_COUNT);
         // apply volume fader
         self.volume_fader.process(&mut output[0..written]);
+        // apply pause fader
+        if self
+            .pause_fader
+            .process(&mut output[0..written], Self::CHANNEL_COUNT)
+        {
+            self.send_status_event(PlaybackStatusEvent::Paused {
+                id: self.playback_id,
+                context: self.playback_status_context.clone(),
+                path: self.playback_name.clone(),
+            });
+        }
 
         // update playback pos
         self.playback_pos += written as u64;
@@ -189,9 +239,8 @@ where
         }
 
         // check if the signal is exhausted and send Stopped event
-        let is_exhausted = self.generator.is_exhausted() || written == 0;
-        let fade_out_finished = self.volume_fader.state() == FaderState::Finished
-            && self.volume_fader.target_volume() == 0.0;
+        let is_exhausted = !is_paused && (self.generator.is_exhausted() || written == 0);
+        let fade_out_finished = self.volume_fader.fade_out_finished();
         if stop_playing || is_exhausted || fade_out_finished {
             self.playback_finished = true;
             if let Some(event_send) = &self.playback_status_send {
@@ -187,6 +187,11 @@ impl Player {
             Some(self.playback_status_sender()),
         )?;
         // and play it
-        self.play_synth_source_with_context(source, options.start_time, context)
+        self.play_synth_source_on_bus(
+            source,
+            options.bus.unwrap_or(Self::MAIN_BUS),
+            options.start_time,
+            context,
+        )
     }
 }
@@ -6,10 +6,12 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 // -------------------------------------------------------------------------------------------------
 
 pub(crate) mod actor;
+pub(crate) mod automation;
 pub(crate) mod buffer;
 pub(crate) mod decoder;
 pub(crate) mod fader;
 pub(crate) mod resampler;
+pub(crate) mod smoothing;
 pub(crate) mod wave;
 
 /// Convert raw audio buffers to audio waveforms for GUIs.
@@ -0,0 +1,156 @@
+use super::fader::FadeCurve;
+use crate::Error;
+
+// -------------------------------------------------------------------------------------------------
+
+/// A breakpoint of a [`VolumeEnvelope`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvelopePoint {
+    /// Absolute sample frame time of the point in the player's output stream.
+    pub sample_time: u64,
+    /// Linear volume factor at the point.
+    pub volume: f32,
+    /// Shape of the ramp from the previous point to this point.
+    pub curve: FadeCurve,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Breakpoint volume automation of a playing source, e.g. to duck music under a voice-over or
+/// to draw swells.
+/// See [`Player::set_source_volume_envelope`](crate::Player::set_source_volume_envelope).
+///
+/// Envelopes get evaluated sample accurately in the player's mixer, on top of all other volume
+/// changes of the source. Before the first point the envelope applies the first point's volume,
+/// after the last point it holds the last point's volume.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VolumeEnvelope {
+    points: Vec<EnvelopePoint>,
+    next_point: usize,
+}
+
+impl VolumeEnvelope {
+    /// Create a new, empty envelope, which does not change the volume.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a point which ramps to the given volume at the given output sample frame time,
+    /// using the given curve. Points with the same time as existing points get added after
+    /// them, so volumes can jump.
+    pub fn with_point(mut self, sample_time: u64, volume: f32, curve: FadeCurve) -> Self {
+        let index = self
+            .points
+            .partition_point(|point| point.sample_time <= sample_time);
+        self.points.insert(
+            index,
+            EnvelopePoint {
+                sample_time,
+                volume,
+                curve,
+            },
+        );
+        self
+    }
+
+    /// The envelope's points, sorted by their sample time.
+    pub fn points(&self) -> &[EnvelopePoint] {
+        &self.points
+    }
+
+    /// Validate all points. Returns Error::ParameterError on errors.
+    pub fn validate(&self) -> Result<(), Error> {
+        for point in &self.points {
+            if point.volume < 0.0 || !point.volume.is_finite() {
+                return Err(Error::ParameterError(format!(
+                    "envelope point 'volume' value is '{}'",
+                    point.volume
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the envelope to the given interleaved output, which starts at the given sample
+    /// frame time. Does not allocate, so this can be called in real-time threads.
+    pub(crate) fn process(&mut self, output: &mut [f32], channel_count: usize, sample_time: u64) {
+        let Some(last_point) = self.points.last() else {
+            return; // empty envelope
+        };
+        if sample_time >= last_point.sample_time && last_point.volume == 1.0 {
+            return; // holding unity volume
+        }
+        for (index, frame) in output.chunks_exact_mut(channel_count).enumerate() {
+            let volume = self.volume_at(sample_time + index as u64);
+            for sample in frame {
+                *sample *= volume;
+            }
+        }
+    }
+
+    /// Volume at the given sample frame time. Moves a cursor to the next point, so evaluating
+    /// the envelope with increasing times is cheap.
+    fn volume_at(&mut self, sample_time: u64) -> f32 {
+        let points = &self.points;
+        if self.next_point > 0 && sample_time < points[self.next_point - 1].sample_time {
+            self.next_point = 0; // time moved backwards
+        }
+        while self.next_point < points.len() && points[self.next_point].sample_time <= sample_time {
+            self.next_point += 1;
+        }
+        if self.next_point == 0 {
+            points[0].volume
+        } else if self.next_point == points.len() {
+            points[points.len() - 1].volume
+        } else {
+            let from = &points[self.next_point - 1];
+            let to = &points[self.next_point];
+            let x = (sample_time - from.sample_time) as f32
+                / (to.sample_time - from.sample_time) as f32;
+            to.curve.fade_volume(from.volume, to.volume, x)
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelope() {
+        let mut envelope = VolumeEnvelope::new()
+            .with_point(20, 0.0, FadeCurve::Linear)
+            .with_point(10, 1.0, FadeCurve::Linear)
+            .with_point(20, 0.5, FadeCurve::Linear)
+            .with_point(30, 1.0, FadeCurve::EqualPower);
+        assert!(envelope.validate().is_ok());
+        assert_eq!(
+            envelope
+                .points()
+                .iter()
+                .map(|point| (point.sample_time, point.volume))
+                .collect::<Vec<_>>(),
+            [(10, 1.0), (20, 0.0), (20, 0.5), (30, 1.0)]
+        );
+
+        let mut output = vec![1.0; 40];
+        envelope.process(&mut output, 1, 0);
+        assert!(output[..=10].iter().all(|s| *s == 1.0));
+        assert_eq!(output[15], 0.5);
+        assert_eq!(output[20], 0.5);
+        assert!(output[21..30].windows(2).all(|w| w[0] < w[1]));
+        assert!(output[30..].iter().all(|s| *s == 1.0));
+
+        // evaluating in blocks gives the same result
+        let mut blocks = vec![1.0; 40];
+        for (index, block) in blocks.chunks_mut(7).enumerate() {
+            envelope.process(block, 1, index as u64 * 7);
+        }
+        assert_eq!(blocks, output);
+
+        let invalid = VolumeEnvelope::new().with_point(0, f32::NAN, FadeCurve::Linear);
+        assert!(invalid.validate().is_err());
+    }
+}
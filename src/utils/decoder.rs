@@ -1,18 +1,29 @@
-use std::{fs::File, io, path::Path, time::Duration};
+use std::{
+    fs::File,
+    io::{self, Cursor, Read, Seek, SeekFrom},
+    ops::Range,
+    path::Path,
+    sync::{Arc, Mutex},
+};
 
 use symphonia::core::{
     audio::{SampleBuffer, SignalSpec},
-    codecs::{CodecParameters, Decoder, DecoderOptions},
+    codecs::{CodecParameters, Decoder, DecoderOptions, CODEC_TYPE_NULL},
     conv::ConvertibleSample,
     errors::Error as SymphoniaError,
-    formats::{FormatOptions, FormatReader, SeekMode, SeekTo},
-    io::MediaSourceStream,
-    meta::MetadataOptions,
-    probe::Hint,
+    formats::{FormatOptions, FormatReader, SeekMode, SeekTo, Track},
+    io::{MediaSource, MediaSourceStream},
+    meta::{Metadata, MetadataOptions, StandardTagKey},
+    probe::{Hint, ProbeResult},
     units::TimeStamp,
 };
 
-use crate::error::Error;
+use crate::{
+    error::Error,
+    source::file::{
+        probe::FileMetadata, EncodedAudio, EncodedAudioData, FileMarker, ReadSeek, TrackSelector,
+    },
+};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -20,43 +31,27 @@ pub struct AudioDecoder {
     track_id: u32, // Internal track index.
     decoder: Box<dyn Decoder>,
     format: Box<dyn FormatReader>,
+    loop_points: Option<Range<u64>>,
+    markers: Vec<FileMarker>,
+    metadata: FileMetadata,
+    metadata_changed: bool,
 }
 
 impl AudioDecoder {
-    pub fn new(path: String) -> Result<Self, Error> {
-        // Create a media source. Note that the MediaSource trait is automatically implemented for File,
-        // among other types.
-        let file = Box::new(File::open(Path::new(&path))?);
-
-        // Create the media source stream using the boxed media source from above.
-        let mss = MediaSourceStream::new(file, Default::default());
-
-        // Create a hint to help the format registry guess what format reader is appropriate. In this
-        // example we'll leave it empty.
-        let hint = Hint::new();
-
-        // Use the default options when reading and decoding.
-        let format_opts: FormatOptions = Default::default();
-        let metadata_opts: MetadataOptions = Default::default();
-        let decoder_opts: DecoderOptions = Default::default();
-
-        // Probe the media source stream for a format.
-        let probed = symphonia::default::get_probe()
-            .format(&hint, mss, &format_opts, &metadata_opts)
-            .map_err(|_| Error::MediaFileProbeError)?;
+    pub fn new(audio: EncodedAudio) -> Result<Self, Error> {
+        let track_selector = audio.track.clone();
+        let (mut probed, wav_info) = probe_audio(audio)?;
+        let metadata = read_metadata(&mut probed);
+        let markers = read_markers(&probed, wav_info.markers);
 
         // Get the format reader yielded by the probe operation.
         let format = probed.format;
 
-        // Get the default track.
-        let track = match format.default_track() {
-            Some(t) => t,
-            None => {
-                return Err(Error::MediaFileNotFound);
-            }
-        };
+        // Get the selected track.
+        let track = select_track(format.as_ref(), &track_selector)?;
 
         // Create a decoder for the track.
+        let decoder_opts: DecoderOptions = Default::default();
         let decoder = symphonia::default::get_codecs()
             .make(&track.codec_params, &decoder_opts)
             .map_err(|err| Error::AudioDecodingError(Box::new(err)))?;
@@ -65,9 +60,33 @@ impl AudioDecoder {
             track_id: track.id,
             decoder,
             format,
+            loop_points: wav_info.loop_points,
+            markers,
+            metadata,
+            metadata_changed: false,
         })
     }
 
+    /// Loop range in sample frames, when the file has embedded loop points.
+    pub fn loop_points(&self) -> Option<Range<u64>> {
+        self.loop_points.clone()
+    }
+
+    /// Markers, e.g. cue points, which are embedded in the file, sorted by their position.
+    pub fn markers(&self) -> Vec<FileMarker> {
+        self.markers.clone()
+    }
+
+    /// Take the file's tags, when they changed while decoding, e.g. in chained streams.
+    pub fn take_metadata_update(&mut self) -> Option<FileMetadata> {
+        if self.metadata_changed {
+            self.metadata_changed = false;
+            Some(self.metadata.clone())
+        } else {
+            None
+        }
+    }
+
     pub fn codec_params(&self) -> &CodecParameters {
         self.decoder.codec_params()
     }
@@ -79,17 +98,20 @@ impl AudioDecoder {
         }
     }
 
-    pub fn seek(&mut self, time: Duration) -> Result<TimeStamp, Error> {
+    /// Seek to the packet which contains the given sample frame. Returns the first frame of
+    /// the packet the decoder got seeked to, which may be before the requested frame.
+    pub fn seek_frame(&mut self, frame: u64) -> Result<u64, Error> {
         let seeked_to = self
             .format
             .seek(
                 SeekMode::Accurate,
-                SeekTo::Time {
-                    time: time.as_secs_f64().into(),
-                    track_id: Some(self.track_id),
+                SeekTo::TimeStamp {
+                    ts: frame,
+                    track_id: self.track_id,
                 },
             )
             .map_err(|_| Error::MediaFileSeekError)?;
+        self.decoder.reset();
         Ok(seeked_to.actual_ts)
     }
 
@@ -111,9 +133,9 @@ impl AudioDecoder {
                     return None; // We cannot recover from format errors, quit.
                 }
             };
-            while !self.format.metadata().is_latest() {
-                // Consume any new metadata that has been read since the last
-                // packet.
+            // Consume any new metadata that has been read since the last packet.
+            if apply_new_metadata_revisions(&mut self.metadata, self.format.metadata()) {
+                self.metadata_changed = true;
             }
             // If the packet does not belong to the selected track, skip over it.
             if packet.track_id() != self.track_id {
@@ -144,3 +166,337 @@ impl AudioDecoder {
         }
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+/// Probe the given encoded audio's container format. Returns the probe result and the loop
+/// points and markers of WAV files.
+pub(crate) fn probe_audio(audio: EncodedAudio) -> Result<(ProbeResult, WavChunkInfo), Error> {
+    // Create a hint to help the format registry guess what format reader is appropriate.
+    let mut hint = Hint::new();
+    if let Some(audio_hint) = &audio.hint {
+        if audio_hint.contains('/') {
+            hint.mime_type(audio_hint);
+        } else {
+            hint.with_extension(audio_hint.trim_start_matches('.'));
+        }
+    } else if let EncodedAudioData::Path(path) = &audio.data {
+        if let Some(extension) = Path::new(path).extension().and_then(|e| e.to_str()) {
+            hint.with_extension(extension);
+        }
+    }
+
+    // Create a media source. Note that the MediaSource trait is automatically implemented for
+    // File and Cursor, among other types.
+    let mut source: Box<dyn MediaSource> = match audio.data {
+        EncodedAudioData::Path(path) => Box::new(File::open(Path::new(&path))?),
+        EncodedAudioData::Bytes(bytes) => Box::new(Cursor::new(bytes)),
+        EncodedAudioData::Reader(reader) => Box::new(ReaderMediaSource(Mutex::new(reader))),
+    };
+
+    // Read loop points and markers from WAV files, which are not exposed by symphonia.
+    let wav_info = read_wav_chunk_info(&mut source);
+    source.rewind()?;
+
+    // Create the media source stream using the boxed media source from above.
+    let mss = MediaSourceStream::new(source, Default::default());
+
+    // Use the default options when reading.
+    let format_opts: FormatOptions = Default::default();
+    let metadata_opts: MetadataOptions = Default::default();
+
+    // Probe the media source stream for a format.
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &format_opts, &metadata_opts)
+        .map_err(|_| Error::MediaFileProbeError)?;
+
+    Ok((probed, wav_info))
+}
+
+/// Read the tags of a probed file: tags in front of the container, e.g. ID3v2 tags, followed by
+/// the container's tags.
+pub(crate) fn read_metadata(probed: &mut ProbeResult) -> FileMetadata {
+    let mut metadata = FileMetadata::default();
+    if let Some(log) = probed.metadata.get() {
+        if let Some(revision) = log.current() {
+            metadata.merge_revision(revision);
+        }
+        apply_new_metadata_revisions(&mut metadata, log);
+    }
+    let log = probed.format.metadata();
+    if let Some(revision) = log.current() {
+        metadata.merge_revision(revision);
+    }
+    apply_new_metadata_revisions(&mut metadata, log);
+    metadata
+}
+
+/// Advance the given metadata log to its latest revision and merge all revisions after the
+/// current one into the given metadata. Returns true when there were any new revisions.
+fn apply_new_metadata_revisions(metadata: &mut FileMetadata, mut log: Metadata<'_>) -> bool {
+    let mut changed = false;
+    while log.pop().is_some() {
+        if let Some(revision) = log.current() {
+            metadata.merge_revision(revision);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Get the markers of a probed file: the given WAV cue points or the container's cues. Only
+/// symphonia's FLAC reader provides cues, so MP4 chapters and other container markers are not
+/// supported.
+pub(crate) fn read_markers(probed: &ProbeResult, wav_markers: Vec<FileMarker>) -> Vec<FileMarker> {
+    if !wav_markers.is_empty() {
+        return wav_markers;
+    }
+    let mut markers = probed
+        .format
+        .cues()
+        .iter()
+        .map(|cue| {
+            let label = cue
+                .tags
+                .iter()
+                .find(|tag| tag.std_key == Some(StandardTagKey::TrackTitle))
+                .or(cue.tags.first())
+                .map_or(String::new(), |tag| tag.value.to_string());
+            FileMarker::new(cue.index, cue.start_ts, label)
+        })
+        .collect::<Vec<_>>();
+    markers.sort_by_key(|marker| marker.frame);
+    markers
+}
+
+/// Find the track which should be decoded in the given format reader.
+pub(crate) fn select_track<'a>(
+    format: &'a dyn FormatReader,
+    selector: &TrackSelector,
+) -> Result<&'a Track, Error> {
+    match selector {
+        TrackSelector::Default => format.default_track().ok_or(Error::MediaFileNotFound),
+        TrackSelector::Index(index) => format.tracks().get(*index).ok_or_else(|| {
+            Error::ParameterError(format!(
+                "audio track index {} is out of range: the file has {} tracks",
+                index,
+                format.tracks().len()
+            ))
+        }),
+        TrackSelector::Language(language) => format
+            .tracks()
+            .iter()
+            .filter(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .find(|track| {
+                track
+                    .language
+                    .as_ref()
+                    .is_some_and(|l| l.eq_ignore_ascii_case(language))
+            })
+            .ok_or_else(|| {
+                Error::ParameterError(format!(
+                    "the file has no audio track with language '{}'",
+                    language
+                ))
+            }),
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Wraps a `Read + Seek + Send` reader into a symphonia [`MediaSource`], which also needs to be
+/// `Sync`. The reader only is accessed by the decoder, so the mutex never is contended.
+struct ReaderMediaSource(Mutex<Box<dyn ReadSeek>>);
+
+impl Read for ReaderMediaSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.get_mut().unwrap().read(buf)
+    }
+}
+
+impl Seek for ReaderMediaSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.get_mut().unwrap().seek(pos)
+    }
+}
+
+impl MediaSource for ReaderMediaSource {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Loop points and markers of RIFF WAVE files, which are not exposed by symphonia.
+#[derive(Default)]
+pub(crate) struct WavChunkInfo {
+    /// The first loop of the `smpl` chunk in sample frames.
+    pub loop_points: Option<Range<u64>>,
+    /// Cue points of the `cue ` chunk, with labels from the `LIST adtl` chunk.
+    pub markers: Vec<FileMarker>,
+}
+
+/// Read loop points and markers of a RIFF WAVE file. Returns empty info when the file is not a
+/// WAV file or has no loops or markers.
+pub(crate) fn read_wav_chunk_info<R: Read + Seek>(reader: &mut R) -> WavChunkInfo {
+    const SMPL_HEADER_SIZE: usize = 36;
+    const SMPL_LOOP_SIZE: usize = 24;
+    const CUE_POINT_SIZE: usize = 24;
+
+    let read_u32 = |bytes: &[u8], offset: usize| {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    };
+    let mut info = WavChunkInfo::default();
+    let mut labels = Vec::new();
+    read_wav_chunks(reader, &[b"smpl", b"cue ", b"LIST"], |id, chunk| match id {
+        b"smpl" if chunk.len() >= SMPL_HEADER_SIZE + SMPL_LOOP_SIZE => {
+            let loop_count = read_u32(chunk, 28);
+            // NB: loop end points are inclusive
+            let loop_start = read_u32(chunk, SMPL_HEADER_SIZE + 8) as u64;
+            let loop_end = read_u32(chunk, SMPL_HEADER_SIZE + 12) as u64 + 1;
+            if loop_count > 0 && loop_start < loop_end {
+                info.loop_points = Some(loop_start..loop_end);
+            }
+        }
+        b"cue " if chunk.len() >= 4 => {
+            let cue_count = read_u32(chunk, 0) as usize;
+            for cue in chunk[4..].chunks_exact(CUE_POINT_SIZE).take(cue_count) {
+                info.markers.push(FileMarker::new(
+                    read_u32(cue, 0),
+                    read_u32(cue, 20) as u64,
+                    "",
+                ));
+            }
+        }
+        b"LIST" if chunk.starts_with(b"adtl") => {
+            // collect labels of cue points from `labl` sub chunks
+            let mut pos = 4;
+            while pos + 8 <= chunk.len() {
+                let size = read_u32(chunk, pos + 4) as usize;
+                let data = &chunk[pos + 8..(pos + 8 + size).min(chunk.len())];
+                if &chunk[pos..pos + 4] == b"labl" && data.len() >= 4 {
+                    let text = String::from_utf8_lossy(&data[4..]);
+                    labels.push((read_u32(data, 0), text.trim_end_matches('\0').to_string()));
+                }
+                pos += 8 + size + size % 2;
+            }
+        }
+        _ => (),
+    });
+    for (cue_id, label) in labels {
+        if let Some(marker) = info.markers.iter_mut().find(|m| m.id == cue_id) {
+            marker.label = Arc::new(label);
+        }
+    }
+    info.markers.sort_by_key(|marker| marker.frame);
+    info
+}
+
+/// Read the chunks with the given ids of a RIFF WAVE file and pass them to the given function.
+/// All other chunks, such as the sample data, get skipped.
+fn read_wav_chunks<R: Read + Seek>(
+    reader: &mut R,
+    ids: &[&[u8; 4]],
+    mut chunk_read: impl FnMut(&[u8; 4], &[u8]),
+) {
+    // avoid allocating huge buffers for broken files
+    const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+    let mut riff_header = [0u8; 12];
+    if reader.read_exact(&mut riff_header).is_err()
+        || &riff_header[0..4] != b"RIFF"
+        || &riff_header[8..12] != b"WAVE"
+    {
+        return;
+    }
+    let mut chunk_header = [0u8; 8];
+    while reader.read_exact(&mut chunk_header).is_ok() {
+        let id: [u8; 4] = chunk_header[0..4].try_into().unwrap();
+        let size = u32::from_le_bytes(chunk_header[4..8].try_into().unwrap()) as usize;
+        if ids.contains(&&id) && size <= MAX_CHUNK_SIZE {
+            let mut chunk = vec![0u8; size];
+            if reader.read_exact(&mut chunk).is_err() {
+                return;
+            }
+            chunk_read(&id, &chunk);
+            // skip padding byte
+            if reader.seek(SeekFrom::Current((size % 2) as i64)).is_err() {
+                return;
+            }
+        } else {
+            // skip chunk and its padding byte
+            let skip = size + size % 2;
+            if reader.seek(SeekFrom::Current(skip as i64)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn wav_loop_points() {
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF\0\0\0\0WAVE");
+        // a chunk with an odd size, which needs to be skipped
+        wav.extend_from_slice(b"junk");
+        wav.extend_from_slice(&3u32.to_le_bytes());
+        wav.extend_from_slice(&[0; 4]);
+        // smpl chunk with a single loop
+        wav.extend_from_slice(b"smpl");
+        wav.extend_from_slice(&60u32.to_le_bytes());
+        let mut smpl = [0u32; 15];
+        smpl[7] = 1; // loop count
+        smpl[11] = 100; // loop start
+        smpl[12] = 199; // loop end (inclusive)
+        for value in smpl {
+            wav.extend_from_slice(&value.to_le_bytes());
+        }
+        let loop_points = |wav: &[u8]| read_wav_chunk_info(&mut Cursor::new(wav)).loop_points;
+        assert_eq!(loop_points(&wav), Some(100..200));
+
+        // no loops
+        assert_eq!(loop_points(&wav[..28]), None);
+        assert_eq!(loop_points(b"RIFF"), None);
+    }
+
+    #[test]
+    fn wav_markers() {
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF\0\0\0\0WAVE");
+        // cue chunk with two cue points, which are not sorted by their position
+        wav.extend_from_slice(b"cue ");
+        wav.extend_from_slice(&52u32.to_le_bytes());
+        wav.extend_from_slice(&2u32.to_le_bytes());
+        for (id, position) in [(1u32, 300u32), (2, 100)] {
+            for value in [id, position, u32::from_le_bytes(*b"data"), 0, 0, position] {
+                wav.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        // label for the second cue point, with an odd size
+        wav.extend_from_slice(b"LIST");
+        wav.extend_from_slice(&22u32.to_le_bytes());
+        wav.extend_from_slice(b"adtl");
+        wav.extend_from_slice(b"labl");
+        wav.extend_from_slice(&9u32.to_le_bytes());
+        wav.extend_from_slice(&2u32.to_le_bytes());
+        wav.extend_from_slice(b"Drop\0\0");
+
+        let markers = read_wav_chunk_info(&mut Cursor::new(&wav)).markers;
+        assert_eq!(
+            markers,
+            [FileMarker::new(2, 100, "Drop"), FileMarker::new(1, 300, "")]
+        );
+    }
+}
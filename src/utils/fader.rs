@@ -1,4 +1,77 @@
-use std::time::Duration;
+use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+use super::{db_to_linear, linear_to_db, smoothing::SmoothedValue};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Volume in dB at which [`FadeCurve::Exponential`] fades start or end when fading from or to
+/// silence.
+const EXPONENTIAL_FADE_MIN_DB: f32 = -60.0;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Shape of a volume fade, e.g. of a crossfade between two sources or of a source's fade-in and
+/// fade-out.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum FadeCurve {
+    /// Linear volume ramp. Crossfades of correlated signals keep a constant level, but
+    /// crossfades of uncorrelated signals dip in the middle.
+    Linear,
+    /// Linear ramp in dB, from or to -60 dB when fading from or to silence. Sounds even to
+    /// the ear, but fade-ins stay quiet for a long time.
+    Exponential,
+    /// Sine and cosine shaped volume ramps, which keep the power of crossfaded uncorrelated
+    /// signals constant.
+    #[default]
+    EqualPower,
+    /// Logarithmic volume ramp, which changes quickly at the start and slowly at the end of a
+    /// fade-in.
+    Logarithmic,
+    /// Smooth S-shaped volume ramp, which starts and ends slowly and changes quickly in the
+    /// middle of the fade.
+    SCurve,
+}
+
+impl FadeCurve {
+    /// Volume of a fade-in at the given fade position in range [0, 1]. The volume of a
+    /// fade-out at position `x` is the fade-in volume at position `1 - x`.
+    #[inline]
+    pub fn fade_in_volume(self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => x,
+            FadeCurve::Exponential => {
+                if x > 0.0 {
+                    db_to_linear(EXPONENTIAL_FADE_MIN_DB * (1.0 - x))
+                } else {
+                    0.0
+                }
+            }
+            FadeCurve::EqualPower => (x * FRAC_PI_2).sin(),
+            FadeCurve::Logarithmic => (1.0 + 9.0 * x).log10(),
+            FadeCurve::SCurve => x * x * (3.0 - 2.0 * x),
+        }
+    }
+
+    /// Volume of a fade from the given start to the given end volume at the given fade
+    /// position in range [0, 1]. Rising fades use the fade-in shape, falling fades the fade-out
+    /// shape. Exponential fades ramp linearly in dB.
+    #[inline]
+    pub fn fade_volume(self, from: f32, to: f32, x: f32) -> f32 {
+        if x >= 1.0 || from == to {
+            return to;
+        }
+        match self {
+            FadeCurve::Exponential => {
+                let from_db = linear_to_db(from).max(EXPONENTIAL_FADE_MIN_DB);
+                let to_db = linear_to_db(to).max(EXPONENTIAL_FADE_MIN_DB);
+                db_to_linear(from_db + (to_db - from_db) * x.max(0.0))
+            }
+            curve if to > from => from + (to - from) * curve.fade_in_volume(x),
+            curve => to + (from - to) * curve.fade_in_volume(1.0 - x),
+        }
+    }
+}
 
 // -------------------------------------------------------------------------------------------------
 
@@ -14,44 +87,59 @@ pub enum FaderState {
 
 // -------------------------------------------------------------------------------------------------
 
-/// Fades out a sample buffer by applying a decaying volume ramp.
+/// Fades a sample buffer in or out, or to any other volume, by applying a volume ramp.
 ///
-/// Fader initially is disabled and needs to be started first. Fading is applied by ramping
-/// volume exponentially on each sample frame with the configured duration.
+/// Fader initially is disabled and needs to be started first. Fades reach their target volume
+/// after exactly the number of sample frames of the requested duration, shaped by the fader's
+/// [`FadeCurve`]. The target volume keeps getting applied after the fade finished.
 #[derive(Clone, Copy)]
 pub struct VolumeFader {
     state: FaderState,
+    curve: FadeCurve,
     current_volume: f32,
+    from_volume: f32,
     target_volume: f32,
-    inertia: f32,
+    fade_frames: usize,
+    fade_pos: usize,
+    is_fading_out: bool,
     channel_count: usize,
     sample_rate: u32,
 }
 
 impl VolumeFader {
-    /// Create a new bypassed fader with the given signal specs.
+    /// Create a new bypassed fader with the given signal specs and the default curve.
     pub fn new(channel_count: usize, sample_rate: u32) -> Self {
         Self {
             state: FaderState::Stopped,
+            curve: FadeCurve::default(),
             current_volume: 1.0,
+            from_volume: 1.0,
             target_volume: 1.0,
-            inertia: 1.0,
+            fade_frames: 0,
+            fade_pos: 0,
+            is_fading_out: false,
             channel_count,
             sample_rate,
         }
     }
 
-    /// Get actual fader state.
-    pub fn state(&self) -> FaderState {
-        self.state
+    /// Set the curve of the fader's fades.
+    pub fn set_curve(&mut self, curve: FadeCurve) {
+        self.curve = curve;
+    }
+
+    /// True when a fade-out got started via [`Self::start_fade_out`].
+    pub fn is_fading_out(&self) -> bool {
+        self.is_fading_out
     }
 
-    /// Get target volume.
-    pub fn target_volume(&self) -> f32 {
-        self.target_volume
+    /// True when a fade-out got started via [`Self::start_fade_out`] and completed, so the
+    /// faded source is silent now.
+    pub fn fade_out_finished(&self) -> bool {
+        self.is_fading_out && self.state == FaderState::Finished
     }
 
-    // Activate the fade with the given duration.
+    /// Fade in from silence, or from the current volume when a fade is running.
     pub fn start_fade_in(&mut self, duration: Duration) {
         if self.state == FaderState::IsRunning {
             self.start(self.current_volume, 1.0, duration)
@@ -59,47 +147,289 @@ impl VolumeFader {
             self.start(0.0, 1.0, duration)
         }
     }
-    // Activate the fade with the given duration.
+
+    /// Fade out from the current volume to silence. Once started, fade-outs can only be
+    /// replaced by other fade-outs, e.g. to stop immediately.
     pub fn start_fade_out(&mut self, duration: Duration) {
-        if self.state == FaderState::IsRunning {
-            self.start(self.current_volume, 0.0, duration)
-        } else {
-            self.start(1.0, 0.0, duration)
-        }
+        self.fade(self.current_volume, 0.0, duration);
+        self.is_fading_out = true;
+    }
+
+    /// Fade from the current volume to the given volume. Ignored while fading out.
+    pub fn fade_to(&mut self, volume: f32, duration: Duration) {
+        self.start(self.current_volume, volume, duration);
     }
-    // Activate the fader with the given start, end values and duration.
+
+    /// Activate the fader with the given start, end values and duration. Ignored while fading
+    /// out.
     pub fn start(&mut self, from: f32, to: f32, duration: Duration) {
-        if duration.is_zero() {
+        if !self.is_fading_out {
+            self.fade(from, to, duration);
+        }
+    }
+
+    /// Process fader on the given interleaved output buffer. Returns the modified output range.
+    pub fn process(&mut self, output: &mut [f32]) -> usize {
+        match self.state {
+            FaderState::Stopped => 0,
+            FaderState::Finished => {
+                // keep applying the target volume
+                if self.target_volume != 1.0 {
+                    for s in output.iter_mut() {
+                        *s *= self.target_volume;
+                    }
+                    output.len()
+                } else {
+                    0
+                }
+            }
+            FaderState::IsRunning => {
+                for f in output.chunks_exact_mut(self.channel_count) {
+                    // ramp per frame, reaching the target with the fade's last frame
+                    if self.fade_pos < self.fade_frames {
+                        self.fade_pos += 1;
+                        let x = self.fade_pos as f32 / self.fade_frames as f32;
+                        self.current_volume =
+                            self.curve
+                                .fade_volume(self.from_volume, self.target_volume, x);
+                    }
+                    // apply per sample
+                    for s in f.iter_mut() {
+                        *s *= self.current_volume;
+                    }
+                }
+                // check if we've finished fading
+                if self.fade_pos >= self.fade_frames {
+                    self.current_volume = self.target_volume;
+                    self.state = FaderState::Finished;
+                }
+                output.len()
+            }
+        }
+    }
+
+    /// Start a new fade, replacing any running fade.
+    fn fade(&mut self, from: f32, to: f32, duration: Duration) {
+        let fade_frames = (duration.as_secs_f64() * self.sample_rate as f64).round() as usize;
+        self.from_volume = from;
+        self.target_volume = to;
+        self.fade_frames = fade_frames;
+        self.fade_pos = 0;
+        if fade_frames == 0 {
             self.current_volume = to;
-            self.target_volume = to;
             self.state = FaderState::Finished;
         } else {
-            self.state = FaderState::IsRunning;
             self.current_volume = from;
-            self.target_volume = to;
-            // HACK: this is a rough guess and should be calculated properly!
-            self.inertia = (1.0 / self.sample_rate as f32) * 4.0 / duration.as_secs_f32();
+            self.state = FaderState::IsRunning;
         }
     }
+}
 
-    // Process fader on the given interleaved output buffer. Returns the modified output range.
-    pub fn process(&mut self, output: &mut [f32]) -> usize {
-        // return empty handed when there's nothing to do
-        if self.state != FaderState::IsRunning {
-            return 0;
-        }
-        for f in output.chunks_exact_mut(self.channel_count) {
-            // ramp per frame
-            self.current_volume += (self.target_volume - self.current_volume) * self.inertia;
-            // apply per sample
-            for s in f.iter_mut() {
-                *s *= self.current_volume;
+// -------------------------------------------------------------------------------------------------
+
+#[derive(PartialEq, Clone, Copy)]
+enum PauseState {
+    Playing,
+    Pausing,
+    Paused,
+}
+
+/// Pauses and resumes a source's output with short volume fades, to avoid clicks.
+///
+/// Sources should stop producing output while the fader is paused, and should continue at the
+/// same position when resuming.
+#[derive(Clone, Copy)]
+pub struct PauseFader {
+    state: PauseState,
+    volume: SmoothedValue,
+}
+
+impl PauseFader {
+    /// Create a new, playing fader with the given sample rate.
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            state: PauseState::Playing,
+            volume: SmoothedValue::new(1.0, sample_rate),
+        }
+    }
+
+    /// True when the fade-out after pausing completed, and thus no output should be produced.
+    pub fn is_paused(&self) -> bool {
+        self.state == PauseState::Paused
+    }
+
+    /// Number of frames until the pause fade-out completes, when pausing. Sources should not
+    /// render more frames than this, so they get paused at an exact position.
+    pub fn frames_until_paused(&self) -> Option<usize> {
+        if self.state == PauseState::Pausing {
+            Some(self.volume.remaining_frames())
+        } else {
+            None
+        }
+    }
+
+    /// Start fading out. Returns false when already pausing or paused.
+    pub fn pause(&mut self) -> bool {
+        if self.state == PauseState::Playing {
+            self.state = PauseState::Pausing;
+            self.volume.set_target(0.0);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Start fading in. Returns false when not pausing or paused.
+    pub fn resume(&mut self) -> bool {
+        if self.state != PauseState::Playing {
+            self.state = PauseState::Playing;
+            self.volume.set_target(1.0);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Process fader on the given interleaved output buffer. Returns true when the pause
+    /// fade-out completed in this buffer.
+    pub fn process(&mut self, output: &mut [f32], channel_count: usize) -> bool {
+        if self.state == PauseState::Paused {
+            output.fill(0.0);
+            return false;
+        }
+        self.volume.apply(output, channel_count);
+        if self.state == PauseState::Pausing && !self.volume.is_ramping() {
+            self.state = PauseState::Paused;
+            return true;
+        }
+        false
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Crossfades the output of a source before seeking with its output after seeking.
+///
+/// Before applying a seek, the source renders a short tail of its old position into the fader's
+/// preallocated buffer. This tail then gets faded out while the new position fades in. The
+/// crossfade duration is limited to [`SeekFader::MAX_DURATION`].
+#[derive(Default)]
+pub struct SeekFader {
+    tail: Vec<f32>,
+    tail_len: usize,
+    tail_pos: usize,
+    channel_count: usize,
+    sample_rate: u32,
+}
+
+impl SeekFader {
+    /// Maximum duration of a seek crossfade.
+    pub const MAX_DURATION: Duration = Duration::from_millis(100);
+
+    pub fn new(channel_count: usize, sample_rate: u32) -> Self {
+        let max_frames = (Self::MAX_DURATION.as_secs_f64() * sample_rate as f64) as usize;
+        Self {
+            tail: vec![0.0; max_frames * channel_count],
+            tail_len: 0,
+            tail_pos: 0,
+            channel_count,
+            sample_rate,
+        }
+    }
+
+    /// True while crossfading.
+    pub fn is_active(&self) -> bool {
+        self.tail_pos < self.tail_len
+    }
+
+    /// Get the buffer the old output should be rendered into for a crossfade with the given
+    /// duration. Call [`Self::start`] after rendering the tail to start crossfading.
+    pub fn tail_mut(&mut self, duration: Duration) -> &mut [f32] {
+        let frames = (duration.as_secs_f64() * self.sample_rate as f64) as usize;
+        let len = (frames * self.channel_count).min(self.tail.len());
+        &mut self.tail[..len]
+    }
+
+    /// Start crossfading with the given number of rendered tail samples.
+    pub fn start(&mut self, tail_len: usize) {
+        self.tail_len = tail_len;
+        self.tail_pos = 0;
+    }
+
+    /// Crossfade the given output, which got `written` samples of new output, with the tail.
+    /// The tail continues playing when the new output is shorter than the tail. Returns the
+    /// new number of written samples.
+    pub fn process(&mut self, output: &mut [f32], written: usize) -> usize {
+        if !self.is_active() {
+            return written;
+        }
+        let channel_count = self.channel_count;
+        let tail_frames = (self.tail_len / channel_count) as f32;
+        let len = output.len().min(self.tail_len - self.tail_pos);
+        for (index, frame) in output[..len].chunks_exact_mut(channel_count).enumerate() {
+            let tail_pos = self.tail_pos + index * channel_count;
+            let fade = ((tail_pos / channel_count) as f32 + 0.5) / tail_frames * FRAC_PI_2;
+            let (fade_in, fade_out) = fade.sin_cos();
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                let new_sample = if index * channel_count < written {
+                    *sample
+                } else {
+                    0.0
+                };
+                *sample = new_sample * fade_in + self.tail[tail_pos + channel] * fade_out;
             }
         }
-        // check if we've finished fading
-        if (self.current_volume - self.target_volume).abs() < 0.0001 {
-            self.state = FaderState::Finished;
+        self.tail_pos += len;
+        written.max(len)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_fader() {
+        const SAMPLE_RATE: u32 = 1000;
+        let duration = Duration::from_millis(100);
+        for curve in [
+            FadeCurve::Linear,
+            FadeCurve::Exponential,
+            FadeCurve::EqualPower,
+            FadeCurve::Logarithmic,
+            FadeCurve::SCurve,
+        ] {
+            let mut fader = VolumeFader::new(2, SAMPLE_RATE);
+            fader.set_curve(curve);
+
+            // fades reach their target with exactly the requested number of frames
+            fader.start_fade_in(duration);
+            let mut output = vec![1.0; 2 * 200];
+            fader.process(&mut output);
+            assert!(output[0] < 0.1, "{curve:?}");
+            assert!(output.windows(2).all(|w| w[0] <= w[1]), "{curve:?}");
+            assert!(output[2 * 98] < 1.0, "{curve:?}");
+            assert!(output[2 * 99..].iter().all(|s| *s == 1.0), "{curve:?}");
+
+            // fades to other volumes keep applying the target volume
+            fader.fade_to(0.25, duration);
+            let mut output = vec![1.0; 2 * 200];
+            fader.process(&mut output);
+            assert!(output.windows(2).all(|w| w[0] >= w[1]), "{curve:?}");
+            assert!(output[2 * 98] > 0.25, "{curve:?}");
+            assert!(output[2 * 99..].iter().all(|s| *s == 0.25), "{curve:?}");
+            assert!(!fader.fade_out_finished());
+
+            // fade-outs can't be interrupted by other fades
+            fader.start_fade_out(duration);
+            fader.fade_to(1.0, duration);
+            let mut output = vec![1.0; 2 * 100];
+            fader.process(&mut output);
+            assert!(output[0] < 0.25 && output[0] > 0.0, "{curve:?}");
+            assert_eq!(output[2 * 99], 0.0, "{curve:?}");
+            assert!(fader.fade_out_finished());
         }
-        output.len()
     }
 }
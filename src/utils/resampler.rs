@@ -52,6 +52,10 @@ pub trait AudioResampler: Send + Sync {
     /// returns ResamplerError or (input_consumed, output_written) on success.
     fn process(&mut self, input: &[f32], output: &mut [f32]) -> Result<(usize, usize), Error>;
 
+    /// Change the output sample rate of a running resampler, e.g. to change playback speed.
+    /// Resamplers which support it, smoothly ramp to the new rate.
+    fn set_output_rate(&mut self, output_rate: u32) -> Result<(), Error>;
+
     /// Reset internal resampler state. Make an existing resampler ready for a new source.
     fn reset(&mut self);
 }
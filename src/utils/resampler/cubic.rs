@@ -3,12 +3,20 @@ use crate::Error;
 
 // -------------------------------------------------------------------------------------------------
 
+/// Number of output frames in which ratio changes are ramped, to avoid zipper noise when
+/// changing the playback speed.
+const RATIO_RAMP_FRAMES: f32 = 1024.0;
+
+// -------------------------------------------------------------------------------------------------
+
 /// Interpolate a single channel of interleaved audio with cubic interpolation.
 #[derive(Clone)]
 struct CubicInterpolator {
     input: [f32; 4],
     sub_pos: f32,
     ratio: f32,
+    target_ratio: f32,
+    ratio_step: f32,
     is_initialized: bool,
 }
 
@@ -21,6 +29,8 @@ impl CubicInterpolator {
             input,
             sub_pos,
             ratio,
+            target_ratio: ratio,
+            ratio_step: 0.0,
             is_initialized,
         }
     }
@@ -28,9 +38,16 @@ impl CubicInterpolator {
     pub fn reset(&mut self) {
         self.input.fill(0.0);
         self.sub_pos = 0.0;
+        self.ratio = self.target_ratio;
         self.is_initialized = false;
     }
 
+    /// Ramp the ratio to the given new ratio within [`RATIO_RAMP_FRAMES`].
+    pub fn set_target_ratio(&mut self, ratio: f32) {
+        self.target_ratio = ratio;
+        self.ratio_step = (ratio - self.ratio) / RATIO_RAMP_FRAMES;
+    }
+
     pub fn process(
         &mut self,
         input: &[f32],
@@ -38,8 +55,8 @@ impl CubicInterpolator {
         channel_index: usize,
         channel_count: usize,
     ) -> (usize, usize) {
-        debug_assert!(input.len() % channel_count == 0);
-        debug_assert!(output.len() % channel_count == 0);
+        debug_assert!(input.len().is_multiple_of(channel_count));
+        debug_assert!(output.len().is_multiple_of(channel_count));
 
         let num_in = input.len() / channel_count;
         let num_out = output.len() / channel_count;
@@ -47,10 +64,16 @@ impl CubicInterpolator {
         let mut num_consumed = 0;
         let mut num_produced = 0;
 
-        if (self.ratio - 1.0).abs() < 0.000001 {
+        if self.ratio == self.target_ratio && (self.ratio - 1.0).abs() < 0.000001 {
             // Bypass conversion in case the sample rates are equal.
             let min = input.len().min(output.len());
             output[..min].copy_from_slice(&input[..min]);
+            // keep the input history up to date for later ratio changes
+            let frames = min / channel_count;
+            for f in frames.saturating_sub(3)..frames {
+                self.push_sample(input[f * channel_count + channel_index]);
+            }
+            self.is_initialized |= frames > 0;
             return (min, min);
         }
 
@@ -66,7 +89,7 @@ impl CubicInterpolator {
         }
 
         // downsample
-        if self.ratio < 1.0 {
+        if self.ratio < 1.0 || (self.ratio == 1.0 && self.target_ratio < 1.0) {
             while num_produced < num_out {
                 if self.sub_pos >= 1.0 {
                     if num_consumed == num_in {
@@ -87,6 +110,8 @@ impl CubicInterpolator {
                 }
                 num_produced += 1;
                 self.sub_pos += self.ratio;
+                // NB: don't switch to upsampling while processing
+                self.ramp_ratio(0.0, 1.0);
             }
         }
         // upsample
@@ -111,12 +136,27 @@ impl CubicInterpolator {
                         self.interpolate(1.0 - self.sub_pos);
                 }
                 num_produced += 1;
+                // NB: don't switch to downsampling while processing
+                self.ramp_ratio(1.0, f32::MAX);
             }
         }
 
         (num_consumed * channel_count, num_produced * channel_count)
     }
 
+    /// Move the ratio one output frame closer to the target ratio, limited to the given range.
+    #[inline]
+    fn ramp_ratio(&mut self, min: f32, max: f32) {
+        if self.ratio != self.target_ratio {
+            let ratio = if self.ratio_step > 0.0 {
+                (self.ratio + self.ratio_step).min(self.target_ratio)
+            } else {
+                (self.ratio + self.ratio_step).max(self.target_ratio)
+            };
+            self.ratio = ratio.clamp(min, max);
+        }
+    }
+
     #[inline]
     fn push_sample(&mut self, new_value: f32) {
         self.input[3] = self.input[2];
@@ -185,9 +225,51 @@ impl AudioResampler for CubicResampler {
         Ok(result)
     }
 
+    fn set_output_rate(&mut self, output_rate: u32) -> Result<(), Error> {
+        if output_rate == 0 {
+            return Err(Error::ParameterError(
+                "resampler output rate must be > 0".to_string(),
+            ));
+        }
+        self.spec.output_rate = output_rate;
+        let ratio = self.spec.input_ratio() as f32;
+        for interpolator in self.interpolators.iter_mut() {
+            interpolator.set_target_ratio(ratio);
+        }
+        Ok(())
+    }
+
     fn reset(&mut self) {
         for interpolator in self.interpolators.iter_mut() {
             interpolator.reset();
         }
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ramped_rate_changes() {
+        let mut resampler = CubicResampler::new(ResamplingSpecs::new(44100, 44100, 1)).unwrap();
+        let input = vec![1.0; 4096];
+        let mut output = vec![0.0; 256];
+        resampler.process(&input, &mut output).unwrap();
+
+        // ratio changes get ramped over multiple blocks, crossing the bypassed 1:1 ratio
+        for (output_rate, target_ratio) in [(22050, 2.0), (88200, 0.5), (44100, 1.0)] {
+            resampler.set_output_rate(output_rate).unwrap();
+            let mut ratios = Vec::new();
+            while resampler.interpolators[0].ratio != target_ratio {
+                let (_, written) = resampler.process(&input, &mut output).unwrap();
+                assert_eq!(written, output.len());
+                assert!(output.iter().all(|s| (s - 1.0).abs() < 0.0001));
+                ratios.push(resampler.interpolators[0].ratio);
+            }
+            assert!(ratios.len() >= 4);
+        }
+    }
+}
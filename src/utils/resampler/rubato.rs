@@ -8,9 +8,16 @@ use crate::{
 
 // -------------------------------------------------------------------------------------------------
 
+/// Maximum factor the output rate can be changed with, relative to the initial output rate.
+const MAX_RELATIVE_RATIO: f64 = 8.0;
+
+// -------------------------------------------------------------------------------------------------
+
 /// `AudioResampler` impl which is using a bandlimited SincFixedIn `rubato` resampler.
 pub struct RubatoResampler {
     spec: ResamplingSpecs,
+    initial_ratio: f64,
+    bypass: bool,
     resampler: SincFixedIn<f32>,
     input: Vec<Vec<f32>>,
     output: Vec<Vec<f32>>,
@@ -29,7 +36,7 @@ impl RubatoResampler {
         };
         match SincFixedIn::new(
             spec.output_ratio(),
-            1.0,
+            MAX_RELATIVE_RATIO,
             parameters,
             CHUNK_SIZE,
             spec.channel_count,
@@ -46,6 +53,8 @@ impl RubatoResampler {
                 Ok(Self {
                     resampler,
                     spec,
+                    initial_ratio: spec.output_ratio(),
+                    bypass: spec.input_rate == spec.output_rate,
                     input,
                     output,
                     pending,
@@ -64,18 +73,18 @@ impl AudioResampler for RubatoResampler {
     }
 
     fn process(&mut self, input: &[f32], output: &mut [f32]) -> Result<(usize, usize), Error> {
-        debug_assert!(
-            input.is_empty() || input.len() >= self.required_input_buffer_size().unwrap(),
-            "invalid input buffer specs"
-        );
-
-        if self.spec.input_rate == self.spec.output_rate {
+        if self.bypass {
             // Bypass conversion in case the sample rates are equal.
             let min = input.len().min(output.len());
             output[..min].copy_from_slice(&input[..min]);
             return Ok((min, min));
         }
 
+        debug_assert!(
+            input.is_empty() || input.len() >= self.required_input_buffer_size().unwrap(),
+            "invalid input buffer specs"
+        );
+
         // flush pending outs
         if !self.pending.is_empty() {
             let input_consumed = 0;
@@ -120,6 +129,24 @@ impl AudioResampler for RubatoResampler {
         }
     }
 
+    fn set_output_rate(&mut self, output_rate: u32) -> Result<(), Error> {
+        if output_rate == 0 {
+            return Err(Error::ParameterError(
+                "resampler output rate must be > 0".to_string(),
+            ));
+        }
+        // NB: once the rate changed, never bypass again to avoid glitches
+        self.spec.output_rate = output_rate;
+        self.bypass = false;
+        let ratio = self.spec.output_ratio().clamp(
+            self.initial_ratio / MAX_RELATIVE_RATIO,
+            self.initial_ratio * MAX_RELATIVE_RATIO,
+        );
+        self.resampler
+            .set_resample_ratio(ratio, true)
+            .map_err(|err| Error::ResamplingError(Box::new(err)))
+    }
+
     fn reset(&mut self) {
         // there's no reset functionality in rubato
     }
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Default duration of a [`SmoothedValue`] ramp.
+const DEFAULT_RAMP_DURATION: Duration = Duration::from_millis(20);
+
+// -------------------------------------------------------------------------------------------------
+
+/// A parameter value which ramps linearly to new target values, to avoid zipper noise when
+/// parameters such as volume or panning change while playing.
+#[derive(Clone, Copy)]
+pub struct SmoothedValue {
+    current: f32,
+    target: f32,
+    step: f32,
+    remaining_frames: usize,
+    ramp_frames: usize,
+}
+
+impl SmoothedValue {
+    /// Create a new smoothed value with the given initial value, which ramps to new target
+    /// values within 20ms at the given sample rate.
+    pub fn new(value: f32, sample_rate: u32) -> Self {
+        let ramp_frames = (DEFAULT_RAMP_DURATION.as_secs_f64() * sample_rate as f64) as usize;
+        Self {
+            current: value,
+            target: value,
+            step: 0.0,
+            remaining_frames: 0,
+            ramp_frames: ramp_frames.max(1),
+        }
+    }
+
+    /// Get the target value.
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    /// True while ramping towards a new target value.
+    pub fn is_ramping(&self) -> bool {
+        self.remaining_frames > 0
+    }
+
+    /// Number of frames until the target value is reached.
+    pub fn remaining_frames(&self) -> usize {
+        self.remaining_frames
+    }
+
+    /// Immediately set a new value, without ramping.
+    pub fn reset(&mut self, value: f32) {
+        self.current = value;
+        self.target = value;
+        self.step = 0.0;
+        self.remaining_frames = 0;
+    }
+
+    /// Start ramping from the current to the given target value.
+    pub fn set_target(&mut self, value: f32) {
+        if value != self.target {
+            self.target = value;
+            self.step = (self.target - self.current) / self.ramp_frames as f32;
+            self.remaining_frames = self.ramp_frames;
+        }
+    }
+
+    /// Get the next frame's value, advancing the ramp.
+    #[inline]
+    pub fn next_value(&mut self) -> f32 {
+        if self.remaining_frames > 0 {
+            self.remaining_frames -= 1;
+            if self.remaining_frames == 0 {
+                self.current = self.target;
+            } else {
+                self.current += self.step;
+            }
+        }
+        self.current
+    }
+
+    /// Multiply the given interleaved buffer with the value, ramping per frame.
+    pub fn apply(&mut self, output: &mut [f32], channel_count: usize) {
+        if self.is_ramping() {
+            for frame in output.chunks_exact_mut(channel_count) {
+                let value = self.next_value();
+                for sample in frame {
+                    *sample *= value;
+                }
+            }
+        } else if (1.0 - self.current).abs() > 0.0001 {
+            let value = self.current;
+            for sample in output.iter_mut() {
+                *sample *= value;
+            }
+        }
+    }
+}
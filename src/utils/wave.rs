@@ -7,7 +7,7 @@
 //!
 //! ```rust no_run
 //! use svg::{node::element::{path::Data, Path}, Document};
-//! use phonic::utils::mixed_down_waveform;
+//! use phonic::utils::waveform::mixed_down as mixed_down_waveform;
 //!
 //! # fn main() { || -> Result<(), Box<dyn std::error::Error>> {
 //! #